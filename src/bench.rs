@@ -0,0 +1,146 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use rust_chess::chess::chess::{make_board_from_fen, Board, ChessMove, BOARD_SIZE};
+
+use crate::engine::teros_engine::{
+    generate_captures, generate_checks, Engine, EvalProfile, InterestEvaluationWeights,
+    MinimaxSettings, SearchSettings, StaticEvaluationWeights,
+};
+
+const BENCH_POSITIONS: [&str; 3] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+];
+
+const BENCH_PONDERING: usize = 2000;
+//times each way of getting the move subsets runs, to get times worth comparing
+const SUBSET_REPEATS: usize = 200;
+
+fn bench_engine(board: Board, thread_count: usize, eval_profile: EvalProfile) -> Arc<Engine> {
+    let mut engine = Engine::new(
+        board,
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    engine.set_eval_profile(eval_profile);
+    Arc::new(engine.multi_thread_think_next_num_moves(thread_count, BENCH_PONDERING, None))
+}
+
+fn nodes_per_second(nodes: usize, time: Duration) -> f64 {
+    nodes as f64 / time.as_secs_f64()
+}
+
+//the capture and check subsets as generate_captures and generate_checks find them, against
+//playing every legal move and keeping the ones that qualify
+fn bench_move_subsets(board: &Board) {
+    let start = Instant::now();
+    let mut direct = (0, 0);
+    for _ in 0..SUBSET_REPEATS {
+        direct = (generate_captures(board).len(), generate_checks(board).len());
+    }
+    let direct_time = start.elapsed();
+
+    let start = Instant::now();
+    let mut filtered = (0, 0);
+    for _ in 0..SUBSET_REPEATS {
+        filtered = (0, 0);
+        for i in 0..BOARD_SIZE {
+            for j in 0..BOARD_SIZE {
+                for chess_move in board.generate_moves(i, j).unwrap_or_default() {
+                    let mut after = board.clone();
+                    if after.make_legal_move(chess_move).is_err() {
+                        continue;
+                    }
+                    let capture = match chess_move {
+                        ChessMove::Normal(normal_move) => board
+                            .get_piece(normal_move.destination_row, normal_move.destination_col)
+                            .unwrap()
+                            .is_some(),
+                        ChessMove::Promotion(_, _) => true,
+                        ChessMove::Castling(_) => false,
+                    };
+                    filtered.0 += capture as usize;
+                    filtered.1 += after.is_check.is_some() as usize;
+                }
+            }
+        }
+    }
+    let filtered_time = start.elapsed();
+
+    println!(
+        "  move subsets: {} captures, {} checks; generated in {:?}, filtered in {:?}",
+        direct.0, direct.1, direct_time, filtered_time
+    );
+    debug_assert_eq!(direct, filtered);
+}
+
+//runs both parallel search schemes over a fixed set of positions and reports nodes and wall
+//time, then the root split again with the fast eval profile
+pub fn run_bench(thread_count: usize) {
+    for fen in BENCH_POSITIONS {
+        let board = make_board_from_fen(fen).expect("bench positions are valid");
+        println!("{}", fen);
+        bench_move_subsets(&board);
+        let engine = bench_engine(board.clone(), thread_count, EvalProfile::Full);
+
+        engine.reset_nodes_searched();
+        let start = Instant::now();
+        let root_split = engine.clone().parallel_eval_and_best_move(thread_count);
+        let root_split_time = start.elapsed();
+        let root_split_nodes = engine.nodes_searched();
+
+        engine.reset_nodes_searched();
+        let start = Instant::now();
+        let tree_split = engine
+            .clone()
+            .split_parallel_eval_and_best_move(thread_count);
+        let tree_split_time = start.elapsed();
+        let tree_split_nodes = engine.nodes_searched();
+
+        let name = |chess_move: Option<ChessMove>| match chess_move {
+            Some(chess_move) => chess_move.name(engine.get_board()).unwrap(),
+            None => String::from("none"),
+        };
+        println!(
+            "  root split: {} nodes in {:?}, {:.0} nodes/s ({} {})",
+            root_split_nodes,
+            root_split_time,
+            nodes_per_second(root_split_nodes, root_split_time),
+            root_split.0.clamp_for_display(),
+            name(root_split.1)
+        );
+        println!(
+            "  tree split: {} nodes in {:?}, {:.0} nodes/s ({} {})",
+            tree_split_nodes,
+            tree_split_time,
+            nodes_per_second(tree_split_nodes, tree_split_time),
+            tree_split.0.clamp_for_display(),
+            name(tree_split.1)
+        );
+
+        let fast_engine = bench_engine(board, thread_count, EvalProfile::Fast);
+        let start = Instant::now();
+        let fast = fast_engine
+            .clone()
+            .parallel_eval_and_best_move(thread_count);
+        let fast_time = start.elapsed();
+        let fast_nodes = fast_engine.nodes_searched();
+        println!(
+            "  fast eval:  {} nodes in {:?}, {:.0} nodes/s ({} {})",
+            fast_nodes,
+            fast_time,
+            nodes_per_second(fast_nodes, fast_time),
+            fast.0.clamp_for_display(),
+            match fast.1 {
+                Some(chess_move) => chess_move.name(fast_engine.get_board()).unwrap(),
+                None => String::from("none"),
+            }
+        );
+    }
+}
@@ -0,0 +1,2337 @@
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt,
+    hash::{Hash, Hasher},
+    sync::{Arc, OnceLock},
+};
+
+use rust_chess::chess::chess::*;
+
+use super::eval::{piece_worth_king_inf, piece_worth_king_zero};
+use super::tree::EngineError;
+
+//identifies a position by piece placement, side to move, castling rights and en passant
+//square, so that move orders reaching the same position share a key
+pub(super) fn position_key(board: &Board, castling: CastlingRights) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (board.get_turn() == Color::White).hash(&mut hasher);
+    for i in 0..BOARD_SIZE {
+        for j in 0..BOARD_SIZE {
+            if let Some(piece) = board.get_piece(i, j).unwrap() {
+                (i, j, piece.kind, piece.color == Color::White).hash(&mut hasher);
+            }
+        }
+    }
+    castling.hash(&mut hasher);
+    en_passant_captures(board).hash(&mut hasher);
+    hasher.finish()
+}
+
+//castling rights, as the K, Q, k and q bits of a FEN. the board keeps its own but doesn't
+//hand them out, so they're followed move by move alongside it
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub(super) struct CastlingRights(u8);
+
+impl CastlingRights {
+    //the FEN letter of each right with the home row of its king and the column of its rook
+    const RIGHTS: [(char, usize, usize); 4] = [
+        ('K', 0, BOARD_SIZE - 1),
+        ('Q', 0, 0),
+        ('k', BOARD_SIZE - 1, BOARD_SIZE - 1),
+        ('q', BOARD_SIZE - 1, 0),
+    ];
+
+    //every right the kings and rooks are still placed for. all a board alone can tell
+    pub(super) fn of(board: &Board) -> CastlingRights {
+        let mut rights = 0;
+        for (bit, (_, row, rook_col)) in CastlingRights::RIGHTS.into_iter().enumerate() {
+            let color = match row {
+                0 => Color::White,
+                _ => Color::Black,
+            };
+            let is = |col: usize, kind: PieceKind| {
+                board.get_piece(row, col).unwrap() == Some(Piece { kind, color })
+            };
+            if is(4, PieceKind::King) && is(rook_col, PieceKind::Rook) {
+                rights |= 1 << bit;
+            }
+        }
+        CastlingRights(rights)
+    }
+
+    //the rights a FEN castling field gives. letters other than KQkq give none
+    pub(super) fn from_fen(field: &str) -> CastlingRights {
+        let mut rights = 0;
+        for (bit, (letter, _, _)) in CastlingRights::RIGHTS.into_iter().enumerate() {
+            if field.contains(letter) {
+                rights |= 1 << bit;
+            }
+        }
+        CastlingRights(rights)
+    }
+
+    //the castling field of a FEN
+    pub(super) fn fen(&self) -> String {
+        let field: String = CastlingRights::RIGHTS
+            .into_iter()
+            .enumerate()
+            .filter(|(bit, _)| self.0 & (1 << bit) != 0)
+            .map(|(_, (letter, _, _))| letter)
+            .collect();
+        match field.is_empty() {
+            true => String::from("-"),
+            false => field,
+        }
+    }
+
+    //the rights left once chess_move is played on board. a king or rook leaving its home
+    //square, or a rook being taken on it, loses the rights that need it
+    pub(super) fn after(&self, board: &Board, chess_move: &ChessMove) -> CastlingRights {
+        let normal_move = match chess_move {
+            ChessMove::Normal(normal_move) | ChessMove::Promotion(normal_move, _) => normal_move,
+            ChessMove::Castling(_) => {
+                let row = match board.get_turn() {
+                    Color::White => 0,
+                    Color::Black => BOARD_SIZE - 1,
+                };
+                return self.without(|(_, home_row, _)| home_row == row);
+            }
+        };
+        let touched = [
+            (normal_move.initial_row, normal_move.initial_col),
+            (normal_move.destination_row, normal_move.destination_col),
+        ];
+        self.without(|(_, row, rook_col)| {
+            touched.contains(&(row, 4)) || touched.contains(&(row, rook_col))
+        })
+    }
+
+    fn without(&self, lost: impl Fn((char, usize, usize)) -> bool) -> CastlingRights {
+        let mut rights = self.0;
+        for (bit, right) in CastlingRights::RIGHTS.into_iter().enumerate() {
+            if lost(right) {
+                rights &= !(1 << bit);
+            }
+        }
+        CastlingRights(rights)
+    }
+}
+
+//the en passant captures of the side to move: pawns on their fifth rank taking diagonally
+//onto an empty square. they stand in for the en passant square, which the board doesn't
+//hand out either
+fn en_passant_captures(board: &Board) -> Vec<ChessMove> {
+    let turn = board.get_turn();
+    let fifth = match turn {
+        Color::White => BOARD_SIZE - 4,
+        Color::Black => 3,
+    };
+    let pawn = Some(Piece {
+        kind: PieceKind::Pawn,
+        color: turn,
+    });
+    let mut captures = Vec::new();
+    for j in 0..BOARD_SIZE {
+        if board.get_piece(fifth, j).unwrap() != pawn {
+            continue;
+        }
+        for chess_move in board.generate_moves(fifth, j).unwrap() {
+            if let ChessMove::Normal(normal_move) = chess_move {
+                let to = (normal_move.destination_row, normal_move.destination_col);
+                if to.1 != j && board.get_piece(to.0, to.1).unwrap().is_none() {
+                    captures.push(chess_move);
+                }
+            }
+        }
+    }
+    captures
+}
+
+//every legal move from the position, paired with the board it leads to
+pub(super) fn legal_moves(board: &Board) -> Vec<(ChessMove, Board)> {
+    (0..BOARD_SIZE)
+        .flat_map(|i| legal_moves_on_rank(board, i))
+        .collect()
+}
+
+//legal_moves of the pieces on one rank, in the same order
+pub(super) fn legal_moves_on_rank(board: &Board, i: usize) -> Vec<(ChessMove, Board)> {
+    let mut all_moves = Vec::new();
+    for j in 0..BOARD_SIZE {
+        match board.generate_moves(i, j) {
+            Ok(moves) => {
+                for chess_move in moves {
+                    let mut new_board = board.clone();
+                    if new_board.make_legal_move(chess_move).is_ok() {
+                        all_moves.push((chess_move, new_board));
+                    }
+                }
+            }
+            Err(BoardError::NoPieceError | BoardError::WrongTurnError) => {}
+            Err(_) => {
+                panic!("what");
+            }
+        }
+    }
+    all_moves
+}
+
+const PROMOTIONS: [PieceKind; 4] = [
+    PieceKind::Queen,
+    PieceKind::Rook,
+    PieceKind::Bishop,
+    PieceKind::Knight,
+];
+
+//captures and promotions of the side to move, with the material each one wins.
+//a promotion's gain includes the piece it turns into, so the delta margin
+//doesn't have to assume one. captures are found from the pieces they take, through
+//attackers_of, rather than by generating every move and keeping the ones that land on
+//something, with en_passant_captures for the pawns taken off a square they don't stand
+//on. like generate_moves, they may still leave the king in check
+pub(super) fn capture_moves(board: &Board) -> Vec<(ChessMove, f32)> {
+    let color = board.get_turn();
+    let last_row = match color {
+        Color::White => BOARD_SIZE - 1,
+        Color::Black => 0,
+    };
+    let mut captures = Vec::new();
+    let mut push = |from: (usize, usize), to: (usize, usize), captured: f32| {
+        let normal_move = NormalChessMove {
+            initial_row: from.0,
+            initial_col: from.1,
+            destination_row: to.0,
+            destination_col: to.1,
+        };
+        let pawn = board
+            .get_piece(from.0, from.1)
+            .unwrap()
+            .is_some_and(|piece| piece.kind == PieceKind::Pawn);
+        match pawn && to.0 == last_row {
+            true => captures.extend(PROMOTIONS.map(|piece_kind| {
+                (
+                    ChessMove::Promotion(normal_move, piece_kind),
+                    captured + *piece_worth_king_zero(piece_kind)
+                        - *piece_worth_king_zero(PieceKind::Pawn),
+                )
+            })),
+            false => captures.push((ChessMove::Normal(normal_move), captured)),
+        }
+    };
+    for i in 0..BOARD_SIZE {
+        for j in 0..BOARD_SIZE {
+            match board.get_piece(i, j).unwrap() {
+                Some(piece) if piece.color != color && piece.kind != PieceKind::King => {
+                    for from in attackers_of(board, i, j, color) {
+                        push(from, (i, j), *piece_worth_king_zero(piece.kind));
+                    }
+                }
+                //a pawn pushing onto the last rank promotes without taking anything
+                Some(piece) if piece.color == color && piece.kind == PieceKind::Pawn => {
+                    let forward_row = match color {
+                        Color::White => i + 1,
+                        Color::Black => i.wrapping_sub(1),
+                    };
+                    if forward_row == last_row && board.get_piece(forward_row, j).unwrap().is_none()
+                    {
+                        push((i, j), (forward_row, j), 0.0);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    for chess_move in en_passant_captures(board) {
+        captures.push((chess_move, *piece_worth_king_zero(PieceKind::Pawn)));
+    }
+    captures
+}
+
+//the legal captures and promotions of the side to move
+pub fn generate_captures(board: &Board) -> Vec<ChessMove> {
+    capture_moves(board)
+        .into_iter()
+        .map(|(chess_move, _)| chess_move)
+        .filter(|chess_move| board.clone().make_legal_move(*chess_move).is_ok())
+        .collect()
+}
+
+//whether a piece of kind standing on to would attack target, with from taken to be empty
+//since the piece just left it
+fn attacks_from(
+    board: &Board,
+    kind: PieceKind,
+    color: Color,
+    from: (usize, usize),
+    to: (usize, usize),
+    target: (usize, usize),
+) -> bool {
+    let (rows, cols) = (target.0 as i32 - to.0 as i32, target.1 as i32 - to.1 as i32);
+    match kind {
+        PieceKind::Pawn => pawn_attacks(to.0, to.1, color).contains(&target),
+        PieceKind::Knight => KNIGHT_JUMPS.contains(&(rows, cols)),
+        PieceKind::King => false,
+        kind => {
+            let aligned = (rows == 0) != (cols == 0) || (rows != 0 && rows.abs() == cols.abs());
+            let (step_rows, step_cols) = (rows.signum(), cols.signum());
+            if !aligned || !sliders_along(step_rows, step_cols).contains(&kind) {
+                return false;
+            }
+            (1..rows.abs().max(cols.abs())).all(|distance| {
+                let square = (
+                    (to.0 as i32 + step_rows * distance) as usize,
+                    (to.1 as i32 + step_cols * distance) as usize,
+                );
+                square == from || board.get_piece(square.0, square.1).unwrap().is_none()
+            })
+        }
+    }
+}
+
+//the squares of color's pieces standing between a slider of color and target, with
+//nothing else in the way, so moving them off the line uncovers an attack
+fn discovery_squares(board: &Board, color: Color, target: (usize, usize)) -> Vec<(usize, usize)> {
+    let on_board = |row: i32, col: i32| {
+        (0..BOARD_SIZE as i32).contains(&row) && (0..BOARD_SIZE as i32).contains(&col)
+    };
+    let mut res = Vec::new();
+    for (rows, cols) in LINES {
+        let (mut row, mut col) = (target.0 as i32 + rows, target.1 as i32 + cols);
+        let mut blocker = None;
+        while on_board(row, col) {
+            if let Some(piece) = board.get_piece(row as usize, col as usize).unwrap() {
+                if piece.color != color {
+                    break;
+                }
+                match blocker {
+                    None => blocker = Some((row as usize, col as usize)),
+                    Some(square) => {
+                        if sliders_along(rows, cols).contains(&piece.kind) {
+                            res.push(square);
+                        }
+                        break;
+                    }
+                }
+            }
+            row += rows;
+            col += cols;
+        }
+    }
+    res
+}
+
+//the legal moves of the side to move that give check. only moves that could are played
+//out: ones landing where the piece, or what it promotes to, attacks the enemy king, ones
+//by a piece shielding the king from a slider, castling and en passant
+pub fn generate_checks(board: &Board) -> Vec<ChessMove> {
+    let color = board.get_turn();
+    let king = match find_king(board, color.opposite()) {
+        Some(king) => king,
+        None => return Vec::new(),
+    };
+    let discoveries = discovery_squares(board, color, king);
+    let mut checks = Vec::new();
+    for i in 0..BOARD_SIZE {
+        for j in 0..BOARD_SIZE {
+            let moves = match board.generate_moves(i, j) {
+                Ok(moves) => moves,
+                Err(BoardError::NoPieceError | BoardError::WrongTurnError) => continue,
+                Err(_) => {
+                    panic!("what");
+                }
+            };
+            let kind = board.get_piece(i, j).unwrap().unwrap().kind;
+            let discovering = discoveries.contains(&(i, j));
+            for chess_move in moves {
+                let might_check = match chess_move {
+                    ChessMove::Castling(_) => true,
+                    ChessMove::Normal(normal_move) => {
+                        let to = (normal_move.destination_row, normal_move.destination_col);
+                        let en_passant = kind == PieceKind::Pawn
+                            && to.1 != j
+                            && board.get_piece(to.0, to.1).unwrap().is_none();
+                        discovering
+                            || en_passant
+                            || attacks_from(board, kind, color, (i, j), to, king)
+                    }
+                    ChessMove::Promotion(normal_move, piece_kind) => {
+                        let to = (normal_move.destination_row, normal_move.destination_col);
+                        discovering || attacks_from(board, piece_kind, color, (i, j), to, king)
+                    }
+                };
+                if !might_check {
+                    continue;
+                }
+                let mut after = board.clone();
+                if after.make_legal_move(chess_move).is_ok() && after.is_check.is_some() {
+                    checks.push(chess_move);
+                }
+            }
+        }
+    }
+    checks
+}
+
+//material the side to move comes out ahead by after playing chess_move and letting both
+//sides keep recapturing on its destination with their least valuable piece, each free to
+//stop when recapturing no longer pays
+pub(super) fn static_exchange(board: &Board, chess_move: &ChessMove) -> f32 {
+    let destination = match chess_move {
+        ChessMove::Normal(normal_move) | ChessMove::Promotion(normal_move, _) => {
+            (normal_move.destination_row, normal_move.destination_col)
+        }
+        ChessMove::Castling(_) => return 0.0,
+    };
+    //en passant takes the pawn beside the empty square it lands on
+    let taken = match chess_move {
+        ChessMove::Normal(normal_move)
+            if normal_move.initial_col != normal_move.destination_col
+                && board
+                    .get_piece(normal_move.initial_row, normal_move.initial_col)
+                    .unwrap()
+                    .is_some_and(|piece| piece.kind == PieceKind::Pawn)
+                && board
+                    .get_piece(destination.0, destination.1)
+                    .unwrap()
+                    .is_none() =>
+        {
+            *piece_worth_king_zero(PieceKind::Pawn)
+        }
+        _ => worth_on(board, destination),
+    };
+    let mut after = board.clone();
+    if after.make_legal_move(*chess_move).is_err() {
+        return 0.0;
+    }
+    taken - exchange_gain(&after, destination)
+}
+
+fn worth_on(board: &Board, (row, col): (usize, usize)) -> f32 {
+    match board.get_piece(row, col).unwrap() {
+        Some(piece) => *piece_worth_king_zero(piece.kind),
+        None => 0.0,
+    }
+}
+
+//what the side to move wins by recapturing on square, 0 when it had better not
+fn exchange_gain(board: &Board, square: (usize, usize)) -> f32 {
+    let mut attackers = attackers_of(board, square.0, square.1, board.get_turn());
+    attackers
+        .sort_by_key(|&(i, j)| piece_worth_king_inf(board.get_piece(i, j).unwrap().unwrap().kind));
+    //the cheapest attacker that can legally take, a pinned one can't
+    for (i, j) in attackers {
+        let recapture = board
+            .generate_moves(i, j)
+            .unwrap_or_default()
+            .into_iter()
+            .find_map(|chess_move| {
+                let reaches = match chess_move {
+                    ChessMove::Normal(normal_move) | ChessMove::Promotion(normal_move, _) => {
+                        (normal_move.destination_row, normal_move.destination_col) == square
+                    }
+                    ChessMove::Castling(_) => false,
+                };
+                let mut after = board.clone();
+                (reaches && after.make_legal_move(chess_move).is_ok()).then_some(after)
+            });
+        if let Some(after) = recapture {
+            return (worth_on(board, square) - exchange_gain(&after, square)).max(0.0);
+        }
+    }
+    0.0
+}
+
+//FEN of the position with the given side to move. the board doesn't expose castling
+//rights or the en passant square, so rights are granted wherever king and rook are still
+//home and en passant is never available
+fn fen_with_turn(board: &Board, turn: Color) -> String {
+    let mut placement = Vec::new();
+    for i in (0..BOARD_SIZE).rev() {
+        let mut rank = String::new();
+        let mut empty = 0;
+        for j in 0..BOARD_SIZE {
+            let piece = match board.get_piece(i, j).unwrap() {
+                Some(piece) => piece,
+                None => {
+                    empty += 1;
+                    continue;
+                }
+            };
+            if empty > 0 {
+                rank.push_str(&empty.to_string());
+                empty = 0;
+            }
+            let letter = match piece.kind {
+                PieceKind::Pawn => 'p',
+                PieceKind::Knight => 'n',
+                PieceKind::Bishop => 'b',
+                PieceKind::Rook => 'r',
+                PieceKind::Queen => 'q',
+                PieceKind::King => 'k',
+            };
+            rank.push(match piece.color {
+                Color::White => letter.to_ascii_uppercase(),
+                Color::Black => letter,
+            });
+        }
+        if empty > 0 {
+            rank.push_str(&empty.to_string());
+        }
+        placement.push(rank);
+    }
+
+    let is = |row: usize, col: usize, kind: PieceKind, color: Color| {
+        board.get_piece(row, col).unwrap() == Some(Piece { kind, color })
+    };
+    let mut castling = String::new();
+    for (row, color, letters) in [
+        (0, Color::White, ['K', 'Q']),
+        (BOARD_SIZE - 1, Color::Black, ['k', 'q']),
+    ] {
+        if !is(row, 4, PieceKind::King, color) {
+            continue;
+        }
+        if is(row, BOARD_SIZE - 1, PieceKind::Rook, color) {
+            castling.push(letters[0]);
+        }
+        if is(row, 0, PieceKind::Rook, color) {
+            castling.push(letters[1]);
+        }
+    }
+    if castling.is_empty() {
+        castling.push('-');
+    }
+
+    format!(
+        "{} {} {} - 0 1",
+        placement.join("/"),
+        match turn {
+            Color::White => 'w',
+            Color::Black => 'b',
+        },
+        castling
+    )
+}
+
+//FEN of the position as far as the board can tell (see fen_with_turn)
+pub fn board_fen(board: &Board) -> String {
+    fen_with_turn(board, board.get_turn())
+}
+
+//board_fen with the castling rights that were followed to it rather than guessed, and
+//the en passant square the side to move can take on
+pub(super) fn fen_with_castling(board: &Board, castling: CastlingRights) -> String {
+    let fen = board_fen(board);
+    let mut fields: Vec<String> = fen.split(' ').map(String::from).collect();
+    fields[2] = castling.fen();
+    if let Some(ChessMove::Normal(normal_move)) = en_passant_captures(board).first() {
+        fields[3] = square_name(normal_move.destination_row, normal_move.destination_col);
+    }
+    fields.join(" ")
+}
+
+//SAN already worked out on this thread, by position_key and move. a move's SAN only
+//depends on where the pieces stand and whose turn it is, so the key is enough
+thread_local! {
+    static SAN_CACHE: RefCell<HashMap<(u64, ChessMove), String>> = RefCell::new(HashMap::new());
+}
+//the cache starts over once it holds this many names
+const SAN_CACHE_SIZE: usize = 4096;
+
+//chess_move.name(board), remembered so the lines printed over and over (analyze's
+//reports, the PV, the frontier) aren't named from scratch every time
+pub fn san(board: &Board, chess_move: &ChessMove) -> Result<String, BoardError> {
+    let key = (position_key(board, CastlingRights::of(board)), *chess_move);
+    if let Some(name) = SAN_CACHE.with(|cache| cache.borrow().get(&key).cloned()) {
+        return Ok(name);
+    }
+    let name = chess_move.name(board)?;
+    SAN_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.len() >= SAN_CACHE_SIZE {
+            cache.clear();
+        }
+        cache.insert(key, name.clone());
+    });
+    Ok(name)
+}
+
+//the SAN of every move of line, played out from board
+pub fn san_line(board: &Board, line: &[ChessMove]) -> Result<Vec<String>, BoardError> {
+    let mut board = board.clone();
+    let mut names = Vec::new();
+    for chess_move in line {
+        names.push(san(&board, chess_move)?);
+        board.make_legal_move(*chess_move)?;
+    }
+    Ok(names)
+}
+
+//passes the turn without moving anything, for null-move style searches.
+//only valid when the side to move is not in check. castling rights are re-derived
+//and any en passant chance is lost (see fen_with_turn)
+pub fn make_null_move(board: &Board) -> Board {
+    debug_assert!(board.is_check.is_none(), "null move while in check");
+    let mut passed = make_board_from_fen(&fen_with_turn(board, board.get_turn().opposite()))
+        .expect("a FEN built from a valid board parses");
+    refresh_status(&mut passed);
+    passed
+}
+
+//recomputes is_check and is_checkmate from the pieces alone. make_legal_move keeps them
+//up to date, but a board built any other way (from a FEN, a null move, a mirror) may come
+//with them missing or stale, and the search and static eval trust them
+pub fn refresh_status(board: &mut Board) {
+    let turn = board.get_turn();
+    board.is_check = find_king(board, turn)
+        .filter(|king| !attackers_of(board, king.0, king.1, turn.opposite()).is_empty())
+        .map(|_| turn);
+    board.is_checkmate = None;
+    board.is_checkmate = game_end(board);
+}
+
+//the same position seen from the other side: ranks flipped top to bottom, every piece and
+//the turn handed to the other color. castling rights follow the pieces, as fen_with_turn
+//re-derives them, and en passant is never available either way. a symmetric evaluation
+//scores it as the negative of board
+pub fn mirror(board: &Board) -> Board {
+    let fen = board_fen(board);
+    let fields: Vec<&str> = fen.split(' ').collect();
+    let swap_case = |text: &str| -> String {
+        text.chars()
+            .map(|c| match c.is_ascii_uppercase() {
+                true => c.to_ascii_lowercase(),
+                false => c.to_ascii_uppercase(),
+            })
+            .collect()
+    };
+    let placement: Vec<String> = fields[0].split('/').rev().map(swap_case).collect();
+    let turn = match board.get_turn() {
+        Color::White => "b",
+        Color::Black => "w",
+    };
+    let mut mirrored = make_board_from_fen(&format!(
+        "{} {} {} - 0 1",
+        placement.join("/"),
+        turn,
+        swap_case(fields[2])
+    ))
+    .expect("a mirrored valid board parses");
+    refresh_status(&mut mirrored);
+    mirrored
+}
+
+//how far the game is from the opening by the non-pawn material left on the board:
+//0.0 with everything still on, 1.0 once only kings and pawns remain
+pub fn game_phase(board: &Board) -> f32 {
+    const OPENING_PHASE: i32 = 24;
+    let mut phase = 0;
+    for i in 0..BOARD_SIZE {
+        for j in 0..BOARD_SIZE {
+            phase += match board.get_piece(i, j).unwrap() {
+                Some(piece) => match piece.kind {
+                    PieceKind::Knight | PieceKind::Bishop => 1,
+                    PieceKind::Rook => 2,
+                    PieceKind::Queen => 4,
+                    PieceKind::Pawn | PieceKind::King => 0,
+                },
+                None => 0,
+            };
+        }
+    }
+    1.0 - phase.min(OPENING_PHASE) as f32 / OPENING_PHASE as f32
+}
+
+//squares the piece on (row, col) can move to, whoever's turn it is
+pub(super) fn destinations(board: &Board, row: usize, col: usize) -> Vec<(usize, usize)> {
+    board
+        .generate_moves_ignore_turn(row, col)
+        .expect("we know there's a piece there")
+        .into_iter()
+        .filter_map(|chess_move| match chess_move {
+            ChessMove::Normal(normal_move) | ChessMove::Promotion(normal_move, _) => {
+                Some((normal_move.destination_row, normal_move.destination_col))
+            }
+            ChessMove::Castling(_) => None,
+        })
+        .collect()
+}
+
+//every piece's generated moves and how the game stands, generated once per position so
+//the eval terms, the terminal check and the node's expansion share one pass of move
+//generation
+#[derive(Debug, Clone)]
+pub(super) struct BoardMoves {
+    //what generate_moves_ignore_turn gives each piece, square by square in legal_moves
+    //order, whoever's turn it is
+    moves: Vec<ChessMove>,
+    //the moves of the piece on row * BOARD_SIZE + col start at starts[row * BOARD_SIZE + col]
+    //and end where the next square's start
+    starts: [u16; BOARD_SIZE * BOARD_SIZE + 1],
+    //destination_bits of every square, read off the moves once since the eval terms ask
+    //for them over and over
+    destinations: [u64; BOARD_SIZE * BOARD_SIZE],
+    //only the eval needs it, the interest of a move doesn't
+    game_end: OnceLock<Option<GameEnd>>,
+}
+
+impl BoardMoves {
+    pub(super) fn of(board: &Board) -> BoardMoves {
+        let mut moves = Vec::new();
+        let mut starts = [0; BOARD_SIZE * BOARD_SIZE + 1];
+        for i in 0..BOARD_SIZE {
+            for j in 0..BOARD_SIZE {
+                starts[i * BOARD_SIZE + j] = moves.len() as u16;
+                if let Ok(generated) = board.generate_moves_ignore_turn(i, j) {
+                    moves.extend(generated);
+                }
+            }
+        }
+        starts[BOARD_SIZE * BOARD_SIZE] = moves.len() as u16;
+        let mut board_moves = BoardMoves {
+            moves,
+            starts,
+            destinations: [0; BOARD_SIZE * BOARD_SIZE],
+            game_end: OnceLock::new(),
+        };
+        for square in 0..BOARD_SIZE * BOARD_SIZE {
+            board_moves.destinations[square] =
+                board_moves.destination_bits(square / BOARD_SIZE, square % BOARD_SIZE);
+        }
+        board_moves
+    }
+
+    //the moves generated for the piece on (row, col)
+    fn moves_from(&self, row: usize, col: usize) -> &[ChessMove] {
+        let square = row * BOARD_SIZE + col;
+        &self.moves[self.starts[square] as usize..self.starts[square + 1] as usize]
+    }
+
+    //bit row * BOARD_SIZE + col set for each square the piece on (row, col) can move to
+    fn destination_bits(&self, row: usize, col: usize) -> u64 {
+        self.moves_from(row, col)
+            .iter()
+            .fold(0, |bits, chess_move| match chess_move {
+                ChessMove::Normal(normal_move) | ChessMove::Promotion(normal_move, _) => {
+                    bits | 1
+                        << (normal_move.destination_row * BOARD_SIZE + normal_move.destination_col)
+                }
+                ChessMove::Castling(_) => bits,
+            })
+    }
+
+    //the squares the piece on (row, col) can move to, as destinations gives them
+    pub(super) fn squares_from(
+        &self,
+        row: usize,
+        col: usize,
+    ) -> impl Iterator<Item = (usize, usize)> {
+        let bits = self.destinations[row * BOARD_SIZE + col];
+        (0..BOARD_SIZE * BOARD_SIZE)
+            .filter(move |square| bits & (1 << square) != 0)
+            .map(|square| (square / BOARD_SIZE, square % BOARD_SIZE))
+    }
+
+    pub(super) fn count_from(&self, row: usize, col: usize) -> usize {
+        self.destinations[row * BOARD_SIZE + col].count_ones() as usize
+    }
+
+    //legal_moves of the board these moves were generated from, played out from the
+    //cached moves instead of generating them again
+    pub(super) fn legal_moves(&self, board: &Board) -> Vec<(ChessMove, Board)> {
+        let turn = board.get_turn();
+        let mut all_moves = Vec::new();
+        for i in 0..BOARD_SIZE {
+            for j in 0..BOARD_SIZE {
+                match board.get_piece(i, j).unwrap() {
+                    Some(piece) if piece.color == turn => {}
+                    _ => continue,
+                }
+                for chess_move in self.moves_from(i, j) {
+                    let mut new_board = board.clone();
+                    if new_board.make_legal_move(*chess_move).is_ok() {
+                        all_moves.push((*chess_move, new_board));
+                    }
+                }
+            }
+        }
+        all_moves
+    }
+
+    //game_end of the board these moves were generated from
+    pub(super) fn game_end(&self, board: &Board) -> Option<GameEnd> {
+        self.game_end
+            .get_or_init(|| game_end_among(board, |i, j| self.moves_from(i, j)))
+            .clone()
+    }
+}
+
+//a node's BoardMoves, generated the first time something asks for them and kept once
+//the node is expanded. clones share them. they follow from the board, so they never
+//make two nodes compare differently
+#[derive(Debug, Clone, Default)]
+pub(super) struct BoardMovesCache(OnceLock<Arc<BoardMoves>>);
+
+impl BoardMovesCache {
+    pub(super) fn get(&self, board: &Board) -> &BoardMoves {
+        self.0.get_or_init(|| Arc::new(BoardMoves::of(board)))
+    }
+
+    #[cfg(test)]
+    pub(super) fn is_generated(&self) -> bool {
+        self.0.get().is_some()
+    }
+}
+
+impl PartialEq for BoardMovesCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for BoardMovesCache {}
+
+impl Ord for BoardMovesCache {
+    fn cmp(&self, _other: &Self) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+impl PartialOrd for BoardMovesCache {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+//the side to move's king, whether it's in check and which of its pieces are pinned to
+//it, worked out once so that most of its moves can be called legal without playing them
+pub(super) struct LegalityFilter {
+    king: Option<(usize, usize)>,
+    in_check: bool,
+    //bit row * BOARD_SIZE + col set for each pinned piece, as in BoardMoves
+    pinned: u64,
+}
+
+impl LegalityFilter {
+    pub(super) fn of(board: &Board) -> LegalityFilter {
+        let color = board.get_turn();
+        let king = match find_king(board, color) {
+            Some(king) => king,
+            None => {
+                return LegalityFilter {
+                    king: None,
+                    in_check: false,
+                    pinned: 0,
+                }
+            }
+        };
+        let (king_row, king_col) = (king.0 as i32, king.1 as i32);
+        let in_check = !attackers_of(board, king.0, king.1, color.opposite()).is_empty();
+        let mut pinned = 0;
+        //along each line out of the king: an enemy slider with exactly one of the king's
+        //own pieces in between pins that piece
+        for (rows, cols) in LINES {
+            let sliders = sliders_along(rows, cols);
+            let mut own = None;
+            let (mut row, mut col) = (king_row + rows, king_col + cols);
+            while (0..BOARD_SIZE as i32).contains(&row) && (0..BOARD_SIZE as i32).contains(&col) {
+                match board.get_piece(row as usize, col as usize).unwrap() {
+                    None => {}
+                    Some(piece) if piece.color == color => match own {
+                        None => own = Some(row as usize * BOARD_SIZE + col as usize),
+                        Some(_) => break,
+                    },
+                    Some(piece) => {
+                        if let (true, Some(square)) = (sliders.contains(&piece.kind), own) {
+                            pinned |= 1 << square;
+                        }
+                        break;
+                    }
+                }
+                row += rows;
+                col += cols;
+            }
+        }
+        LegalityFilter {
+            king: Some(king),
+            in_check,
+            pinned,
+        }
+    }
+
+    //a move of a piece that isn't pinned, played while not in check, can't leave its king
+    //attacked. king moves, castling and en passant (which can uncover a check along the
+    //rank) are played out like every move was before
+    pub(super) fn is_legal(&self, board: &Board, chess_move: &ChessMove) -> bool {
+        let normal_move = match chess_move {
+            ChessMove::Normal(normal_move) | ChessMove::Promotion(normal_move, _) => normal_move,
+            ChessMove::Castling(_) => return board.clone().make_legal_move(*chess_move).is_ok(),
+        };
+        let from = (normal_move.initial_row, normal_move.initial_col);
+        let en_passant = normal_move.initial_col != normal_move.destination_col
+            && board
+                .get_piece(from.0, from.1)
+                .unwrap()
+                .is_some_and(|piece| piece.kind == PieceKind::Pawn)
+            && board
+                .get_piece(normal_move.destination_row, normal_move.destination_col)
+                .unwrap()
+                .is_none();
+        let pinned = self.pinned & (1 << (from.0 * BOARD_SIZE + from.1)) != 0;
+        match self.in_check || self.king.is_none_or(|king| king == from) || pinned || en_passant {
+            true => board.clone().make_legal_move(*chess_move).is_ok(),
+            false => true,
+        }
+    }
+}
+
+//the board's own verdict if it has one, otherwise worked out from there being no legal
+//moves, since boards built from FEN don't always come with is_checkmate filled in
+pub(super) fn game_end(board: &Board) -> Option<GameEnd> {
+    game_end_among(board, |i, j| board.generate_moves(i, j).unwrap_or_default())
+}
+
+//game_end with the moves of the piece on (i, j) given by moves_from, which only gets
+//asked about the side to move's pieces
+fn game_end_among<M: AsRef<[ChessMove]>>(
+    board: &Board,
+    moves_from: impl Fn(usize, usize) -> M,
+) -> Option<GameEnd> {
+    if board.is_checkmate.is_some() {
+        return board.is_checkmate.clone();
+    }
+    let turn = board.get_turn();
+    let legality = LegalityFilter::of(board);
+    for i in 0..BOARD_SIZE {
+        for j in 0..BOARD_SIZE {
+            match board.get_piece(i, j).unwrap() {
+                Some(piece) if piece.color == turn => {}
+                _ => continue,
+            }
+            if moves_from(i, j)
+                .as_ref()
+                .iter()
+                .any(|chess_move| legality.is_legal(board, chess_move))
+            {
+                return None;
+            }
+        }
+    }
+    match board.is_check {
+        Some(color) => Some(GameEnd::Mated(color)),
+        None => Some(GameEnd::StaleMate),
+    }
+}
+
+pub(super) fn is_mate(board: &Board) -> bool {
+    matches!(board.is_checkmate, Some(GameEnd::Mated(_)))
+}
+
+//moves that reset the fifty-move count
+pub(super) fn is_capture_or_pawn_move(board: &Board, chess_move: &ChessMove) -> bool {
+    match chess_move {
+        ChessMove::Normal(normal_move) | ChessMove::Promotion(normal_move, _) => {
+            let moving = board
+                .get_piece(normal_move.initial_row, normal_move.initial_col)
+                .unwrap();
+            let captured = board
+                .get_piece(normal_move.destination_row, normal_move.destination_col)
+                .unwrap();
+            captured.is_some() || matches!(moving, Some(piece) if piece.kind == PieceKind::Pawn)
+        }
+        ChessMove::Castling(_) => false,
+    }
+}
+
+fn square_name(row: usize, col: usize) -> String {
+    format!("{}{}", (b'a' + col as u8) as char, row + 1)
+}
+
+//how castling is written in long algebraic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CastlingStyle {
+    //the king's own move, e1g1
+    #[default]
+    KingMove,
+    //the king taking its own rook, e1h1, as UCI_Chess960 expects
+    KingTakesRook,
+}
+
+fn lan_of(
+    board: &Board,
+    chess_move: &ChessMove,
+    ending_board: &Board,
+    style: CastlingStyle,
+) -> String {
+    match chess_move {
+        ChessMove::Normal(normal_move) => format!(
+            "{}{}",
+            square_name(normal_move.initial_row, normal_move.initial_col),
+            square_name(normal_move.destination_row, normal_move.destination_col)
+        ),
+        ChessMove::Promotion(normal_move, piece_kind) => format!(
+            "{}{}{}",
+            square_name(normal_move.initial_row, normal_move.initial_col),
+            square_name(normal_move.destination_row, normal_move.destination_col),
+            match piece_kind {
+                PieceKind::Knight => 'n',
+                PieceKind::Bishop => 'b',
+                PieceKind::Rook => 'r',
+                _ => 'q',
+            }
+        ),
+        ChessMove::Castling(_) => {
+            let color = board.get_turn();
+            let rook = Some(Piece {
+                kind: PieceKind::Rook,
+                color,
+            });
+            let from = find_king(board, color);
+            let to = match style {
+                CastlingStyle::KingMove => find_king(ending_board, color),
+                //the rook that left its square
+                CastlingStyle::KingTakesRook => from.and_then(|(row, _)| {
+                    (0..BOARD_SIZE)
+                        .find(|&col| {
+                            board.get_piece(row, col).unwrap() == rook
+                                && ending_board.get_piece(row, col).unwrap() != rook
+                        })
+                        .map(|col| (row, col))
+                }),
+            };
+            match (from, to) {
+                (Some((from_row, from_col)), Some((to_row, to_col))) => format!(
+                    "{}{}",
+                    square_name(from_row, from_col),
+                    square_name(to_row, to_col)
+                ),
+                _ => String::from("0000"),
+            }
+        }
+    }
+}
+
+//the long algebraic of a move whose resulting board is already known, without replaying it
+pub(super) fn lan_between(board: &Board, chess_move: &ChessMove, ending_board: &Board) -> String {
+    lan_of(board, chess_move, ending_board, CastlingStyle::KingMove)
+}
+
+//long algebraic (coordinate) notation, as spoken by UCI: e2e4, e7e8q, e1g1
+fn move_to_lan(
+    board: &Board,
+    chess_move: &ChessMove,
+    style: CastlingStyle,
+) -> Result<String, EngineError> {
+    let mut ending_board = board.clone();
+    ending_board
+        .make_legal_move(*chess_move)
+        .map_err(|_| EngineError::IllegalMoveError)?;
+    Ok(lan_of(board, chess_move, &ending_board, style))
+}
+
+fn interpret_lan(
+    board: &Board,
+    move_string: &str,
+    style: CastlingStyle,
+) -> Result<ChessMove, EngineError> {
+    let move_string = move_string.trim().to_ascii_lowercase();
+    //a promotion without its piece is to a queen
+    let queening = move_string.clone() + "q";
+    let moves = legal_moves(board);
+    moves
+        .iter()
+        .find(|(chess_move, ending_board)| {
+            lan_of(board, chess_move, ending_board, style) == move_string
+        })
+        .or_else(|| {
+            moves.iter().find(|(chess_move, ending_board)| {
+                lan_of(board, chess_move, ending_board, style) == queening
+            })
+        })
+        .map(|(chess_move, _)| *chess_move)
+        .ok_or(EngineError::IllegalMoveError)
+}
+
+//reading long algebraic moves off a board
+pub trait InterpretLan {
+    fn interpret_lan(&self, move_string: &str) -> Result<ChessMove, EngineError> {
+        self.interpret_lan_with(move_string, CastlingStyle::KingMove)
+    }
+
+    fn interpret_lan_with(
+        &self,
+        move_string: &str,
+        style: CastlingStyle,
+    ) -> Result<ChessMove, EngineError>;
+}
+
+impl InterpretLan for Board {
+    fn interpret_lan_with(
+        &self,
+        move_string: &str,
+        style: CastlingStyle,
+    ) -> Result<ChessMove, EngineError> {
+        interpret_lan(self, move_string, style)
+    }
+}
+
+//writing moves as long algebraic; a move that can't be played on the board comes out as the
+//null move, 0000
+pub trait ToLan {
+    fn to_lan(&self, board: &Board) -> String {
+        self.to_lan_with(board, CastlingStyle::KingMove)
+    }
+
+    fn to_lan_with(&self, board: &Board, style: CastlingStyle) -> String;
+}
+
+impl ToLan for ChessMove {
+    fn to_lan_with(&self, board: &Board, style: CastlingStyle) -> String {
+        move_to_lan(board, self, style).unwrap_or_else(|_| String::from("0000"))
+    }
+}
+
+//ICCF numeric notation, the long algebraic with files and promotions as digits: 5254 for e2e4,
+//57581 for e7e8q, castling as the king's move, 5171
+fn iccf_of(board: &Board, chess_move: &ChessMove, ending_board: &Board) -> String {
+    lan_of(board, chess_move, ending_board, CastlingStyle::KingMove)
+        .chars()
+        .enumerate()
+        .map(|(index, c)| match (index, c) {
+            (0 | 2, 'a'..='h') => (b'1' + (c as u8 - b'a')) as char,
+            (4, 'q') => '1',
+            (4, 'r') => '2',
+            (4, 'b') => '3',
+            (4, 'n') => '4',
+            _ => c,
+        })
+        .collect()
+}
+
+//reading ICCF numeric moves off a board
+pub trait InterpretIccf {
+    fn interpret_iccf(&self, move_string: &str) -> Result<ChessMove, EngineError>;
+}
+
+impl InterpretIccf for Board {
+    fn interpret_iccf(&self, move_string: &str) -> Result<ChessMove, EngineError> {
+        let move_string = move_string.trim();
+        //as with LAN, a promotion without its piece is to a queen
+        let queening = format!("{}1", move_string);
+        let moves = legal_moves(self);
+        moves
+            .iter()
+            .find(|(chess_move, ending_board)| {
+                iccf_of(self, chess_move, ending_board) == move_string
+            })
+            .or_else(|| {
+                moves.iter().find(|(chess_move, ending_board)| {
+                    iccf_of(self, chess_move, ending_board) == queening
+                })
+            })
+            .map(|(chess_move, _)| *chess_move)
+            .ok_or(EngineError::IllegalMoveError)
+    }
+}
+
+//writing moves as ICCF numeric; like ToLan, an unplayable move comes out as 0000
+pub trait ToIccf {
+    fn to_iccf(&self, board: &Board) -> String;
+}
+
+impl ToIccf for ChessMove {
+    fn to_iccf(&self, board: &Board) -> String {
+        let mut ending_board = board.clone();
+        match ending_board.make_legal_move(*self) {
+            Ok(_) => iccf_of(board, self, &ending_board),
+            Err(_) => String::from("0000"),
+        }
+    }
+}
+
+//why a Chess960 FEN can't be set up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chess960Error {
+    //too few fields to be a FEN
+    Malformed,
+    //a castling right that is neither one of KQkq nor a file
+    BadCastling(char),
+    //a castling right for a king off the e-file or a rook out of the corner. the board
+    //plays castling itself and only knows the standard squares, so such castling can't be
+    //played at all
+    UnsupportedCastling(char),
+}
+
+impl fmt::Display for Chess960Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Chess960Error::Malformed => write!(f, "not a FEN"),
+            Chess960Error::BadCastling(right) => write!(f, "no castling right {}", right),
+            Chess960Error::UnsupportedCastling(right) => write!(
+                f,
+                "castling right {} isn't for a king on the e-file and a corner rook, the only \
+                 Chess960 castling that can be played",
+                right
+            ),
+        }
+    }
+}
+
+//a Chess960 FEN with its castling field rewritten as KQkq for the board to read. Chess960
+//is only supported as far as castling goes in the standard arrangement: any start position
+//without castling rights is read, but a right is only taken, in X-FEN (KQkq for the
+//outermost rooks) or Shredder-FEN (the rooks' files), when it castles a king on the
+//e-file with a corner rook. any other right is refused rather than played wrong
+pub fn chess960_fen(fen: &str) -> Result<String, Chess960Error> {
+    let mut fields: Vec<&str> = fen.split_whitespace().collect();
+    if fields.len() < 3 {
+        return Err(Chess960Error::Malformed);
+    }
+    let ranks: Vec<&str> = fields[0].split('/').collect();
+    if ranks.len() != BOARD_SIZE {
+        return Err(Chess960Error::Malformed);
+    }
+    //the piece letter on a square of the placement field
+    let letter_on = |row: usize, col: usize| {
+        let mut file = 0;
+        for letter in ranks[BOARD_SIZE - 1 - row].chars() {
+            match letter.to_digit(10) {
+                Some(empty) => file += empty as usize,
+                None if file == col => return Some(letter),
+                None => file += 1,
+            }
+        }
+        None
+    };
+
+    let mut rights = String::new();
+    for right in fields[2].chars().filter(|&right| right != '-') {
+        let white = right.is_ascii_uppercase();
+        let (home_row, king, rook) = match white {
+            true => (0, 'K', 'R'),
+            false => (BOARD_SIZE - 1, 'k', 'r'),
+        };
+        let rook_col = match right.to_ascii_lowercase() {
+            'k' => BOARD_SIZE - 1,
+            'q' => 0,
+            file @ 'a'..='h' => (file as u8 - b'a') as usize,
+            _ => return Err(Chess960Error::BadCastling(right)),
+        };
+        let supported = (rook_col == 0 || rook_col == BOARD_SIZE - 1)
+            && letter_on(home_row, 4) == Some(king)
+            && letter_on(home_row, rook_col) == Some(rook);
+        if !supported {
+            return Err(Chess960Error::UnsupportedCastling(right));
+        }
+        let standard = match (white, rook_col == 0) {
+            (true, false) => 'K',
+            (true, true) => 'Q',
+            (false, false) => 'k',
+            (false, true) => 'q',
+        };
+        if !rights.contains(standard) {
+            rights.push(standard);
+        }
+    }
+    if rights.is_empty() {
+        rights.push('-');
+    }
+    fields[2] = &rights;
+    Ok(fields.join(" "))
+}
+
+//why a move typed by a person couldn't be played
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveParseError {
+    //not something that reads as a move at all
+    Unparseable,
+    //the side to move has no piece of that kind
+    NoSuchPiece,
+    //several moves fit; holds their SAN
+    Ambiguous(Vec<String>),
+    //reads fine, but no legal move matches it
+    IllegalMove,
+}
+
+impl fmt::Display for MoveParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveParseError::Unparseable => write!(f, "that doesn't look like a move"),
+            MoveParseError::NoSuchPiece => write!(f, "there's no such piece to move"),
+            MoveParseError::Ambiguous(candidates) => {
+                write!(f, "ambiguous, could be {}", candidates.join(" or "))
+            }
+            MoveParseError::IllegalMove => write!(f, "that move isn't legal here"),
+        }
+    }
+}
+
+//SAN as read from a person: the piece letter (None for pawns), whatever disambiguation was
+//given, the destination and the promotion piece
+struct LooseSan {
+    kind: PieceKind,
+    from_col: Option<usize>,
+    from_row: Option<usize>,
+    to: (usize, usize),
+    promotion: Option<PieceKind>,
+}
+
+fn piece_kind_of_letter(letter: char) -> Option<PieceKind> {
+    match letter {
+        'K' => Some(PieceKind::King),
+        'Q' => Some(PieceKind::Queen),
+        'R' => Some(PieceKind::Rook),
+        'B' => Some(PieceKind::Bishop),
+        'N' => Some(PieceKind::Knight),
+        _ => None,
+    }
+}
+
+fn parse_loose_san(text: &str) -> Option<LooseSan> {
+    let mut chars: Vec<char> = text.chars().filter(|c| *c != 'x' && *c != '=').collect();
+    let kind = match chars.first().copied().and_then(piece_kind_of_letter) {
+        Some(kind) => {
+            chars.remove(0);
+            kind
+        }
+        None => PieceKind::Pawn,
+    };
+    let promotion = match chars.last().copied().and_then(piece_kind_of_letter) {
+        Some(promotion) => {
+            chars.pop();
+            Some(promotion)
+        }
+        None => None,
+    };
+    let square = |file: char, rank: char| match (file, rank) {
+        ('a'..='h', '1'..='8') => {
+            Some((rank as usize - '1' as usize, file as usize - 'a' as usize))
+        }
+        _ => None,
+    };
+    let (disambiguation, destination) = chars.split_at(chars.len().checked_sub(2)?);
+    let to = square(destination[0], destination[1])?;
+    let (mut from_col, mut from_row) = (None, None);
+    for c in disambiguation {
+        match c {
+            'a'..='h' if from_col.is_none() => from_col = Some(*c as usize - 'a' as usize),
+            '1'..='8' if from_row.is_none() => from_row = Some(*c as usize - '1' as usize),
+            _ => return None,
+        }
+    }
+    Some(LooseSan {
+        kind,
+        from_col,
+        from_row,
+        to,
+        promotion,
+    })
+}
+
+//SAN for people: accepts whatever the board accepts and otherwise works out why the move
+//can't be played, tolerating missing or extra capture marks and disambiguation
+pub fn interpret_san(board: &Board, move_string: &str) -> Result<ChessMove, MoveParseError> {
+    if let Ok(chess_move) = board.interpret_move(move_string) {
+        return Ok(chess_move);
+    }
+    let text = move_string.trim().trim_end_matches(['+', '#', '!', '?']);
+    let moves = legal_moves(board);
+    if text.replace('0', "O").starts_with("O-O") {
+        let wanted = text.replace('0', "O");
+        return moves
+            .iter()
+            .find(|(chess_move, _)| {
+                matches!(chess_move, ChessMove::Castling(_))
+                    && chess_move.name(board).unwrap().trim_end_matches(['+', '#']) == wanted
+            })
+            .map(|(chess_move, _)| *chess_move)
+            .ok_or(MoveParseError::IllegalMove);
+    }
+
+    let san = parse_loose_san(text).ok_or(MoveParseError::Unparseable)?;
+    let piece = Piece {
+        kind: san.kind,
+        color: board.get_turn(),
+    };
+    let has_piece = (0..BOARD_SIZE)
+        .any(|i| (0..BOARD_SIZE).any(|j| board.get_piece(i, j).unwrap() == Some(piece.clone())));
+    if !has_piece {
+        return Err(MoveParseError::NoSuchPiece);
+    }
+    let candidates: Vec<ChessMove> = moves
+        .into_iter()
+        .map(|(chess_move, _)| chess_move)
+        .filter(|chess_move| {
+            let (normal_move, promotion) = match chess_move {
+                ChessMove::Normal(normal_move) => (normal_move, None),
+                ChessMove::Promotion(normal_move, piece_kind) => (normal_move, Some(*piece_kind)),
+                ChessMove::Castling(_) => return false,
+            };
+            board
+                .get_piece(normal_move.initial_row, normal_move.initial_col)
+                .unwrap()
+                == Some(piece.clone())
+                && (normal_move.destination_row, normal_move.destination_col) == san.to
+                && san
+                    .from_col
+                    .is_none_or(|col| col == normal_move.initial_col)
+                && san
+                    .from_row
+                    .is_none_or(|row| row == normal_move.initial_row)
+                //a promotion without its piece is to a queen
+                && promotion == san.promotion.or(promotion.map(|_| PieceKind::Queen))
+        })
+        .collect();
+    match candidates.len() {
+        0 => Err(MoveParseError::IllegalMove),
+        1 => Ok(candidates[0]),
+        _ => Err(MoveParseError::Ambiguous(
+            candidates
+                .iter()
+                .map(|chess_move| chess_move.name(board).unwrap())
+                .collect(),
+        )),
+    }
+}
+
+//what makes a position that parsed fine impossible to reach or to search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PositionError {
+    MissingKing(Color),
+    ExtraKings(Color),
+    //the side that just moved left its king in check
+    OpponentInCheck,
+    PawnOnBackRank,
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = |color: &Color| match color {
+            Color::White => "white",
+            Color::Black => "black",
+        };
+        match self {
+            PositionError::MissingKing(color) => write!(f, "{} has no king", side(color)),
+            PositionError::ExtraKings(color) => write!(f, "{} has more than one king", side(color)),
+            PositionError::OpponentInCheck => {
+                write!(f, "the side not to move is in check")
+            }
+            PositionError::PawnOnBackRank => write!(f, "there's a pawn on the first or last rank"),
+        }
+    }
+}
+
+//why a FEN can't be read, or set up once it is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    //fewer than the four fields a position needs, or more than the six there are
+    FieldCount(usize),
+    RankCount(usize),
+    //a rank, counted from the eighth as the FEN lists them, that doesn't fill its eight
+    //squares exactly
+    RankLength(usize),
+    BadPiece(char),
+    BadTurn(String),
+    //anything but "-" or some of KQkq, each once
+    BadCastling(String),
+    //anything but "-" or a square on the rank a double step by the side that just moved
+    //passes over
+    BadEnPassant(String),
+    BadClock(String),
+    //well formed, but the board wouldn't take it
+    Unreadable(String),
+    Position(PositionError),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::FieldCount(count) => {
+                write!(f, "a FEN has 4 to 6 fields, this one has {}", count)
+            }
+            FenError::RankCount(count) => {
+                write!(f, "the placement has {} ranks instead of 8", count)
+            }
+            FenError::RankLength(rank) => {
+                write!(
+                    f,
+                    "rank {} doesn't add up to 8 squares",
+                    BOARD_SIZE + 1 - rank
+                )
+            }
+            FenError::BadPiece(letter) => write!(f, "{} isn't a piece", letter),
+            FenError::BadTurn(turn) => write!(f, "the side to move is {}, not w or b", turn),
+            FenError::BadCastling(castling) => {
+                write!(f, "{} isn't a castling field, expected - or KQkq", castling)
+            }
+            FenError::BadEnPassant(square) => {
+                write!(f, "{} can't be the en passant square", square)
+            }
+            FenError::BadClock(clock) => write!(f, "{} isn't a move count", clock),
+            FenError::Unreadable(err) => write!(f, "the board can't read it ({})", err),
+            FenError::Position(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+//a board from a FEN, with every field checked first so a mistake is named rather than
+//left to the board's own error, then checked for a position the engine can play
+pub fn parse_fen(fen: &str) -> Result<Board, FenError> {
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    if !(4..=6).contains(&fields.len()) {
+        return Err(FenError::FieldCount(fields.len()));
+    }
+    let ranks: Vec<&str> = fields[0].split('/').collect();
+    if ranks.len() != BOARD_SIZE {
+        return Err(FenError::RankCount(ranks.len()));
+    }
+    for (index, rank) in ranks.iter().enumerate() {
+        let mut squares = 0;
+        for letter in rank.chars() {
+            squares += match letter {
+                '1'..='8' => letter as usize - '0' as usize,
+                'p' | 'n' | 'b' | 'r' | 'q' | 'k' | 'P' | 'N' | 'B' | 'R' | 'Q' | 'K' => 1,
+                _ => return Err(FenError::BadPiece(letter)),
+            };
+        }
+        if squares != BOARD_SIZE {
+            return Err(FenError::RankLength(index + 1));
+        }
+    }
+    //the rank the en passant square is on, behind a pawn of the side that just moved
+    let passed_rank = match fields[1] {
+        "w" => '6',
+        "b" => '3',
+        turn => return Err(FenError::BadTurn(turn.to_string())),
+    };
+    let castling = fields[2];
+    let mut rights = castling.chars().collect::<Vec<char>>();
+    rights.sort();
+    rights.dedup();
+    let castling_ok = castling == "-"
+        || (rights.len() == castling.len() && rights.iter().all(|right| "KQkq".contains(*right)));
+    if !castling_ok {
+        return Err(FenError::BadCastling(castling.to_string()));
+    }
+    let en_passant: Vec<char> = fields[3].chars().collect();
+    let en_passant_ok = match en_passant[..] {
+        ['-'] => true,
+        [file, rank] => ('a'..='h').contains(&file) && rank == passed_rank,
+        _ => false,
+    };
+    if !en_passant_ok {
+        return Err(FenError::BadEnPassant(fields[3].to_string()));
+    }
+    if let Some(clock) = fields[4..]
+        .iter()
+        .find(|clock| clock.parse::<u32>().is_err())
+    {
+        return Err(FenError::BadClock(clock.to_string()));
+    }
+
+    let mut board = make_board_from_fen(fen.trim())
+        .map_err(|err| FenError::Unreadable(format!("{:?}", err)))?;
+    validate_position(&board).map_err(FenError::Position)?;
+    refresh_status(&mut board);
+    Ok(board)
+}
+
+//rejects positions the rules can't produce, which the search would otherwise trip over
+pub fn validate_position(board: &Board) -> Result<(), PositionError> {
+    let side_index = |color: Color| match color {
+        Color::White => 0,
+        Color::Black => 1,
+    };
+    let mut kings = [0, 0];
+    for i in 0..BOARD_SIZE {
+        for j in 0..BOARD_SIZE {
+            match board.get_piece(i, j).unwrap() {
+                Some(piece) if piece.kind == PieceKind::King => {
+                    kings[side_index(piece.color)] += 1;
+                }
+                Some(piece) if piece.kind == PieceKind::Pawn && (i == 0 || i == BOARD_SIZE - 1) => {
+                    return Err(PositionError::PawnOnBackRank);
+                }
+                _ => {}
+            }
+        }
+    }
+    for color in [Color::White, Color::Black] {
+        match kings[side_index(color)] {
+            0 => return Err(PositionError::MissingKing(color)),
+            1 => {}
+            _ => return Err(PositionError::ExtraKings(color)),
+        }
+    }
+
+    let turn = board.get_turn();
+    let enemy_king = find_king(board, turn.opposite()).expect("both kings were counted");
+    for i in 0..BOARD_SIZE {
+        for j in 0..BOARD_SIZE {
+            let attacked = match board.get_piece(i, j).unwrap() {
+                Some(piece) if piece.color != turn => continue,
+                Some(piece) if piece.kind == PieceKind::Pawn => pawn_attacks(i, j, turn),
+                Some(_) => destinations(board, i, j),
+                None => continue,
+            };
+            if attacked.contains(&enemy_king) {
+                return Err(PositionError::OpponentInCheck);
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(super) fn find_king(board: &Board, color: Color) -> Option<(usize, usize)> {
+    let king = Piece {
+        kind: PieceKind::King,
+        color,
+    };
+    for i in 0..BOARD_SIZE {
+        for j in 0..BOARD_SIZE {
+            if board.get_piece(i, j).unwrap() == Some(king.clone()) {
+                return Some((i, j));
+            }
+        }
+    }
+    None
+}
+
+fn piece_glyph(piece: &Piece) -> char {
+    match (piece.color, piece.kind) {
+        (Color::White, PieceKind::King) => '♔',
+        (Color::White, PieceKind::Queen) => '♕',
+        (Color::White, PieceKind::Rook) => '♖',
+        (Color::White, PieceKind::Bishop) => '♗',
+        (Color::White, PieceKind::Knight) => '♘',
+        (Color::White, PieceKind::Pawn) => '♙',
+        (Color::Black, PieceKind::King) => '♚',
+        (Color::Black, PieceKind::Queen) => '♛',
+        (Color::Black, PieceKind::Rook) => '♜',
+        (Color::Black, PieceKind::Bishop) => '♝',
+        (Color::Black, PieceKind::Knight) => '♞',
+        (Color::Black, PieceKind::Pawn) => '♟',
+    }
+}
+
+//the squares a move that was just played left and reached. the board before castling is
+//gone, so castling marks where the king and its rook landed
+fn last_move_squares(board: &Board, last_move: &ChessMove) -> Vec<(usize, usize)> {
+    match last_move {
+        ChessMove::Normal(normal_move) | ChessMove::Promotion(normal_move, _) => vec![
+            (normal_move.initial_row, normal_move.initial_col),
+            (normal_move.destination_row, normal_move.destination_col),
+        ],
+        ChessMove::Castling(_) => match find_king(board, board.get_turn().opposite()) {
+            Some((row, col)) => vec![(row, col), (row, if col > 4 { col - 1 } else { col + 1 })],
+            None => Vec::new(),
+        },
+    }
+}
+
+//the board with rank and file labels and unicode pieces, white at the bottom. the squares
+//of last_move are set off in reverse video when ansi, and in brackets when not
+pub fn pretty_board(board: &Board, last_move: Option<&ChessMove>, ansi: bool) -> String {
+    let highlighted = last_move
+        .map(|last_move| last_move_squares(board, last_move))
+        .unwrap_or_default();
+    let mut res = String::new();
+    for i in (0..BOARD_SIZE).rev() {
+        res += &format!("{} ", i + 1);
+        for j in 0..BOARD_SIZE {
+            let glyph = match board.get_piece(i, j).unwrap() {
+                Some(piece) => piece_glyph(&piece),
+                None => '·',
+            };
+            res += &match (highlighted.contains(&(i, j)), ansi) {
+                (false, _) => format!(" {} ", glyph),
+                (true, true) => format!("\x1b[7m {} \x1b[0m", glyph),
+                (true, false) => format!("[{}]", glyph),
+            };
+        }
+        res += "\n";
+    }
+    res += "  ";
+    for j in 0..BOARD_SIZE {
+        res += &format!(" {} ", (b'a' + j as u8) as char);
+    }
+    res + "\n"
+}
+
+pub(super) fn same_position(board: &Board, other: &Board) -> bool {
+    if board.get_turn() != other.get_turn()
+        || en_passant_captures(board) != en_passant_captures(other)
+    {
+        return false;
+    }
+    for i in 0..BOARD_SIZE {
+        for j in 0..BOARD_SIZE {
+            if board.get_piece(i, j).unwrap() != other.get_piece(i, j).unwrap() {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+//the squares a pawn of the given color standing on (row, col) captures on
+pub(super) fn pawn_attacks(row: usize, col: usize, color: Color) -> Vec<(usize, usize)> {
+    let forward_row = match color {
+        Color::White => row + 1,
+        Color::Black => match row.checked_sub(1) {
+            Some(forward_row) => forward_row,
+            None => return Vec::new(),
+        },
+    };
+    if forward_row >= BOARD_SIZE {
+        return Vec::new();
+    }
+    [col.checked_sub(1), Some(col + 1)]
+        .into_iter()
+        .flatten()
+        .filter(|&attacked_col| attacked_col < BOARD_SIZE)
+        .map(|attacked_col| (forward_row, attacked_col))
+        .collect()
+}
+
+const KNIGHT_JUMPS: [(i32, i32); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+
+//the eight directions out of a square, straight ones first
+const LINES: [(i32, i32); 8] = [
+    (0, 1),
+    (1, 0),
+    (0, -1),
+    (-1, 0),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
+//the pieces that slide along a direction out of LINES
+fn sliders_along(rows: i32, cols: i32) -> [PieceKind; 2] {
+    match rows == 0 || cols == 0 {
+        true => [PieceKind::Rook, PieceKind::Queen],
+        false => [PieceKind::Bishop, PieceKind::Queen],
+    }
+}
+
+//the squares of color's pieces that attack (row, col), whatever stands there, so a side's
+//defenders of its own pieces count as well. it looks out from the square for each kind
+//of piece instead of trying every piece on the board
+pub(super) fn attackers_of(
+    board: &Board,
+    row: usize,
+    col: usize,
+    color: Color,
+) -> Vec<(usize, usize)> {
+    let on_board = |row: i32, col: i32| {
+        (0..BOARD_SIZE as i32).contains(&row) && (0..BOARD_SIZE as i32).contains(&col)
+    };
+    let holds = |row: i32, col: i32, kinds: &[PieceKind]| {
+        on_board(row, col)
+            && board
+                .get_piece(row as usize, col as usize)
+                .unwrap()
+                .is_some_and(|piece| piece.color == color && kinds.contains(&piece.kind))
+    };
+    let (row, col) = (row as i32, col as i32);
+    let mut res = Vec::new();
+    //a pawn attacks the square from where one of the other color standing on it would
+    for (pawn_row, pawn_col) in pawn_attacks(row as usize, col as usize, color.opposite()) {
+        if holds(pawn_row as i32, pawn_col as i32, &[PieceKind::Pawn]) {
+            res.push((pawn_row, pawn_col));
+        }
+    }
+    for (rows, cols) in KNIGHT_JUMPS {
+        if holds(row + rows, col + cols, &[PieceKind::Knight]) {
+            res.push(((row + rows) as usize, (col + cols) as usize));
+        }
+    }
+    for (rows, cols) in LINES {
+        if holds(row + rows, col + cols, &[PieceKind::King]) {
+            res.push(((row + rows) as usize, (col + cols) as usize));
+        }
+        let (mut line_row, mut line_col) = (row + rows, col + cols);
+        while on_board(line_row, line_col) {
+            if board
+                .get_piece(line_row as usize, line_col as usize)
+                .unwrap()
+                .is_some()
+            {
+                if holds(line_row, line_col, &sliders_along(rows, cols)) {
+                    res.push((line_row as usize, line_col as usize));
+                }
+                break;
+            }
+            line_row += rows;
+            line_col += cols;
+        }
+    }
+    res
+}
+
+//how many pieces of the occupant's side attack square
+pub(super) fn defenders(board: &Board, square: (usize, usize)) -> usize {
+    match board.get_piece(square.0, square.1).unwrap() {
+        Some(piece) => attackers_of(board, square.0, square.1, piece.color).len(),
+        None => 0,
+    }
+}
+
+pub(super) fn is_past_pawn(row: usize, col: usize, board: &Board, color: Color) -> bool {
+    let to_left_option = col.checked_sub(1);
+    let to_center = col;
+    let to_right = col + 1;
+
+    let cols = match to_left_option {
+        Some(to_left) => vec![to_left, to_center, to_right],
+        None => vec![to_center, to_right],
+    };
+
+    let mut past = true;
+    //the ranks in front of the pawn, its own left out either way
+    for i in match color {
+        Color::White => row + 1..BOARD_SIZE,
+        Color::Black => 0..row,
+    } {
+        for j in cols.clone() {
+            let piece_result = board.get_piece(i, j);
+            match piece_result {
+                Ok(Some(piece)) => {
+                    if piece
+                        == (Piece {
+                            kind: PieceKind::Pawn,
+                            color: color.opposite(),
+                        })
+                    {
+                        past = false;
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    past
+}
+
+#[test]
+fn test_lan_round_trip() {
+    let board = make_board_from_fen("4k3/1P6/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+    for (chess_move, _) in legal_moves(&board) {
+        let lan = chess_move.to_lan(&board);
+        assert_eq!(board.interpret_lan(&lan).unwrap().to_lan(&board), lan);
+    }
+    assert_eq!(
+        board.interpret_lan("b7b8q").unwrap().to_lan(&board),
+        "b7b8q"
+    );
+    assert_eq!(board.interpret_lan("e1g1").unwrap().to_lan(&board), "e1g1");
+    assert!(board.interpret_lan("e1e3").is_err());
+}
+
+#[test]
+fn test_iccf_round_trip() {
+    let board = make_board_from_fen("4k3/1P6/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+    for (chess_move, _) in legal_moves(&board) {
+        assert_eq!(
+            board.interpret_iccf(&chess_move.to_iccf(&board)).unwrap(),
+            chess_move
+        );
+    }
+    assert_eq!(
+        board.interpret_iccf("27281").unwrap().to_lan(&board),
+        "b7b8q"
+    );
+    assert_eq!(
+        board.interpret_iccf("27284").unwrap().to_lan(&board),
+        "b7b8n"
+    );
+    assert_eq!(board.interpret_iccf("5171").unwrap().to_lan(&board), "e1g1");
+    assert_eq!(board.interpret_lan("e1c1").unwrap().to_iccf(&board), "5131");
+    assert!(board.interpret_iccf("5153").is_err());
+}
+
+#[test]
+fn test_promotion_input() {
+    let board = make_board_from_fen("1n2k3/P1P5/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let promotions = [
+        ("Q", "q", "1"),
+        ("R", "r", "2"),
+        ("B", "b", "3"),
+        ("N", "n", "4"),
+    ];
+    for (san_letter, lan_letter, iccf_digit) in promotions {
+        let lan = format!("c7c8{}", lan_letter);
+        for san_text in [format!("c8={}", san_letter), format!("c8{}", san_letter)] {
+            assert_eq!(
+                interpret_san(&board, &san_text).unwrap().to_lan(&board),
+                lan
+            );
+        }
+        assert_eq!(board.interpret_lan(&lan).unwrap().to_lan(&board), lan);
+        let iccf = format!("3738{}", iccf_digit);
+        assert_eq!(board.interpret_iccf(&iccf).unwrap().to_lan(&board), lan);
+    }
+    //no piece given is a queen
+    assert_eq!(interpret_san(&board, "c8").unwrap().to_lan(&board), "c7c8q");
+    assert_eq!(board.interpret_lan("c7c8").unwrap().to_lan(&board), "c7c8q");
+    assert_eq!(
+        board.interpret_iccf("3738").unwrap().to_lan(&board),
+        "c7c8q"
+    );
+    assert_eq!(
+        interpret_san(&board, "axb8").unwrap().to_lan(&board),
+        "a7b8q"
+    );
+    //two pawns can take on b8, which is ambiguous whatever they promote to
+    assert!(matches!(
+        interpret_san(&board, "xb8=N"),
+        Err(MoveParseError::Ambiguous(candidates)) if candidates.len() == 2
+    ));
+    assert!(interpret_san(&board, "e4=Q").is_err());
+}
+
+#[test]
+fn test_chess960_castling() {
+    let board = make_board_from_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+    let castle = board
+        .interpret_lan_with("e1a1", CastlingStyle::KingTakesRook)
+        .unwrap();
+    assert_eq!(castle.to_lan(&board), "e1c1");
+    assert_eq!(
+        castle.to_lan_with(&board, CastlingStyle::KingTakesRook),
+        "e1a1"
+    );
+
+    //Shredder-FEN rights for the corner rooks are read, others can't be played
+    assert_eq!(
+        chess960_fen("r3k2r/8/8/8/8/8/8/R3K2R w HAh - 0 1"),
+        Ok(String::from("r3k2r/8/8/8/8/8/8/R3K2R w KQk - 0 1"))
+    );
+    assert_eq!(
+        chess960_fen("1r2k1r1/8/8/8/8/8/8/1R2K1R1 w KQkq - 0 1"),
+        Err(Chess960Error::UnsupportedCastling('K'))
+    );
+    assert_eq!(
+        chess960_fen("1r2k1r1/8/8/8/8/8/8/1R2K1R1 w BG - 0 1"),
+        Err(Chess960Error::UnsupportedCastling('B'))
+    );
+    assert_eq!(
+        chess960_fen("r3k2r/8/8/8/8/8/8/R3K2R w X - 0 1"),
+        Err(Chess960Error::BadCastling('X'))
+    );
+    assert_eq!(
+        chess960_fen("r4kr1/8/8/8/8/8/8/R4KR1 w - - 0 1"),
+        Ok(String::from("r4kr1/8/8/8/8/8/8/R4KR1 w - - 0 1"))
+    );
+}
+
+#[test]
+fn test_interpret_san_errors() {
+    let board = make_board_from_fen("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1").unwrap();
+    assert!(matches!(
+        interpret_san(&board, "Nd2"),
+        Err(MoveParseError::Ambiguous(candidates)) if candidates.len() == 2
+    ));
+    assert!(interpret_san(&board, "Nbd2").is_ok());
+    assert!(interpret_san(&board, "Nfxd2").is_ok());
+    assert_eq!(
+        interpret_san(&board, "Qd1"),
+        Err(MoveParseError::NoSuchPiece)
+    );
+    assert_eq!(
+        interpret_san(&board, "Ke3"),
+        Err(MoveParseError::IllegalMove)
+    );
+    assert_eq!(
+        interpret_san(&board, "hello"),
+        Err(MoveParseError::Unparseable)
+    );
+}
+
+#[test]
+fn test_validate_position() {
+    let check = |fen: &str| validate_position(&make_board_from_fen(fen).unwrap());
+    assert_eq!(
+        check("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+        Ok(())
+    );
+    assert_eq!(
+        check("8/8/8/8/8/8/8/4K3 w - - 0 1"),
+        Err(PositionError::MissingKing(Color::Black))
+    );
+    assert_eq!(
+        check("4k3/8/8/8/8/8/8/K3K3 w - - 0 1"),
+        Err(PositionError::ExtraKings(Color::White))
+    );
+    assert_eq!(check("4k3/8/8/8/8/8/8/4K2R w - - 0 1"), Ok(()));
+    assert_eq!(
+        check("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1"),
+        Err(PositionError::OpponentInCheck)
+    );
+    assert_eq!(
+        check("P3k3/8/8/8/8/8/8/4K3 w - - 0 1"),
+        Err(PositionError::PawnOnBackRank)
+    );
+}
+
+#[test]
+fn test_mirror() {
+    let board =
+        make_board_from_fen("r3k2r/pp3ppp/2n5/3pP3/8/5N2/PPP2PPP/R3K2R b Kq - 0 1").unwrap();
+    let mirrored = mirror(&board);
+    assert_eq!(
+        board_fen(&mirrored),
+        "r3k2r/ppp2ppp/5n2/8/3Pp3/2N5/PP3PPP/R3K2R w KQkq - 0 1"
+    );
+    assert!(same_position(&mirror(&mirrored), &board));
+}
+
+#[test]
+fn test_refresh_status() {
+    //fool's mate played out, against the same positions read from FEN
+    let mut played = Board::new();
+    for san_text in ["f3", "e5", "g4"] {
+        played
+            .make_legal_move(interpret_san(&played, san_text).unwrap())
+            .unwrap();
+        let mut read = make_board_from_fen(&board_fen(&played)).unwrap();
+        read.is_check = Some(Color::White);
+        read.is_checkmate = Some(GameEnd::StaleMate);
+        refresh_status(&mut read);
+        assert_eq!(read.is_check, played.is_check);
+        assert_eq!(read.is_checkmate, played.is_checkmate);
+    }
+    played
+        .make_legal_move(interpret_san(&played, "Qh4").unwrap())
+        .unwrap();
+    let mut mated = make_board_from_fen(&board_fen(&played)).unwrap();
+    mated.is_check = None;
+    mated.is_checkmate = None;
+    refresh_status(&mut mated);
+    assert_eq!(mated.is_check, Some(Color::White));
+    assert_eq!(mated.is_checkmate, Some(GameEnd::Mated(Color::White)));
+    assert_eq!(mated.is_checkmate, played.is_checkmate);
+
+    let mut stalemate = make_board_from_fen("k7/8/1Q6/8/8/8/8/7K b - - 0 1").unwrap();
+    refresh_status(&mut stalemate);
+    assert_eq!(stalemate.is_check, None);
+    assert_eq!(stalemate.is_checkmate, Some(GameEnd::StaleMate));
+}
+
+#[test]
+fn test_position_key_rights() {
+    let key = |fen: &str| {
+        let fields: Vec<&str> = fen.split(' ').collect();
+        position_key(
+            &make_board_from_fen(fen).unwrap(),
+            CastlingRights::from_fen(fields[2]),
+        )
+    };
+    let castling = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+    assert_eq!(key(castling), key(castling));
+    assert_ne!(key(castling), key("r3k2r/8/8/8/8/8/8/R3K2R w Kkq - 0 1"));
+    assert_ne!(key(castling), key("r3k2r/8/8/8/8/8/8/R3K2R w KQk - 0 1"));
+    assert_ne!(
+        key("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1"),
+        key("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1")
+    );
+
+    //the king walking out and back loses its rights
+    let play = |sans: [&str; 6]| {
+        let mut board = Board::new();
+        let mut rights = CastlingRights::of(&board);
+        for san in sans {
+            let chess_move = board.interpret_move(san).unwrap();
+            rights = rights.after(&board, &chess_move);
+            board.make_legal_move(chess_move).unwrap();
+        }
+        (board, rights)
+    };
+    let (walked, walked_rights) = play(["e4", "e5", "Ke2", "Ke7", "Ke1", "Ke8"]);
+    let (moved, moved_rights) = play(["e4", "e5", "Nf3", "Nf6", "Ng1", "Ng8"]);
+    assert!(same_position(&walked, &moved));
+    assert_eq!(walked_rights.fen(), "-");
+    assert_eq!(moved_rights.fen(), "KQkq");
+    assert_ne!(
+        position_key(&walked, walked_rights),
+        position_key(&moved, moved_rights)
+    );
+}
+
+#[test]
+fn test_null_move() {
+    let board =
+        make_board_from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3")
+            .unwrap();
+    let passed = make_null_move(&board);
+    assert_eq!(passed.get_turn(), Color::Black);
+    let rights = CastlingRights::from_fen("KQkq");
+    assert_ne!(position_key(&passed, rights), position_key(&board, rights));
+    assert!(same_position(&make_null_move(&passed), &board));
+}
+
+#[test]
+fn test_board_moves() {
+    let board =
+        make_board_from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3")
+            .unwrap();
+    let moves = BoardMoves::of(&board);
+    for i in 0..BOARD_SIZE {
+        for j in 0..BOARD_SIZE {
+            if board.get_piece(i, j).unwrap().is_none() {
+                continue;
+            }
+            let mut expected = destinations(&board, i, j);
+            expected.sort();
+            expected.dedup();
+            assert_eq!(moves.squares_from(i, j).collect::<Vec<_>>(), expected);
+            assert_eq!(moves.count_from(i, j), expected.len());
+        }
+    }
+    assert_eq!(moves.game_end(&board), None);
+    assert_eq!(moves.legal_moves(&board), legal_moves(&board));
+    //castling, promotions and en passant, in legal_moves order
+    for fen in [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1",
+    ] {
+        let board = make_board_from_fen(fen).unwrap();
+        assert_eq!(
+            BoardMoves::of(&board).legal_moves(&board),
+            legal_moves(&board),
+            "{}",
+            fen
+        );
+    }
+
+    let stalemate = make_board_from_fen("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1").unwrap();
+    assert_eq!(
+        BoardMoves::of(&stalemate).game_end(&stalemate),
+        Some(GameEnd::StaleMate)
+    );
+}
+
+//leaves of the move tree to depth, with the last ply counted by the legality filter
+//instead of played, and checked against playing it out
+#[cfg(test)]
+fn perft(board: &Board, depth: usize) -> usize {
+    if depth > 1 {
+        return legal_moves(board)
+            .iter()
+            .map(|(_, child)| perft(child, depth - 1))
+            .sum();
+    }
+    let legality = LegalityFilter::of(board);
+    let filtered = (0..BOARD_SIZE)
+        .flat_map(|i| (0..BOARD_SIZE).map(move |j| (i, j)))
+        .flat_map(|(i, j)| board.generate_moves(i, j).unwrap_or_default())
+        .filter(|chess_move| legality.is_legal(board, chess_move))
+        .count();
+    assert_eq!(filtered, legal_moves(board).len());
+    filtered
+}
+
+//the first n positions of a pseudo-random game from the starting position, each move
+//picked by a linear congruential sequence from seed. the game stops early at a position
+//without legal moves, which is still included
+#[cfg(test)]
+pub(super) fn random_positions(mut seed: u64, n: usize) -> Vec<Board> {
+    let mut positions = Vec::new();
+    let mut board = Board::new();
+    while positions.len() < n {
+        let moves = legal_moves(&board);
+        positions.push(board);
+        if moves.is_empty() {
+            break;
+        }
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        board = moves[(seed >> 33) as usize % moves.len()].1.clone();
+    }
+    positions
+}
+
+#[test]
+fn test_legality_filter_perft() {
+    let perft_of = |fen: &str, depth| perft(&make_board_from_fen(fen).unwrap(), depth);
+    assert_eq!(perft(&Board::new(), 3), 8902);
+    //castling, pins and promotions
+    assert_eq!(
+        perft_of(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            2
+        ),
+        2039
+    );
+    //en passant that would uncover a check along the rank
+    assert_eq!(
+        perft_of("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 3),
+        2812
+    );
+    //starting in check
+    assert_eq!(
+        perft_of(
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            2
+        ),
+        264
+    );
+}
+
+#[test]
+fn test_pretty_board() {
+    let mut board = Board::new();
+    let chess_move = board.interpret_move("e4").unwrap();
+    board.make_legal_move(chess_move).unwrap();
+    let pretty = pretty_board(&board, Some(&chess_move), false);
+    let lines: Vec<&str> = pretty.lines().collect();
+    assert_eq!(lines[0], "8  ♜  ♞  ♝  ♛  ♚  ♝  ♞  ♜ ");
+    assert_eq!(lines[4], "4  ·  ·  ·  · [♙] ·  ·  · ");
+    assert_eq!(lines[6], "2  ♙  ♙  ♙  ♙ [·] ♙  ♙  ♙ ");
+    assert_eq!(lines[8], "   a  b  c  d  e  f  g  h ");
+
+    //castling marks the king and the rook beside it
+    let mut castled =
+        make_board_from_fen("r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1").unwrap();
+    let castling = castled.interpret_move("O-O").unwrap();
+    castled.make_legal_move(castling).unwrap();
+    let pretty = pretty_board(&castled, Some(&castling), false);
+    assert_eq!(pretty.lines().nth(7), Some("1  ♖  ·  ·  ·  · [♖][♔] · "));
+}
+
+#[test]
+fn test_attackers_of_agrees_with_move_generation() {
+    for board in random_positions(0x7e405, 60) {
+        for i in 0..BOARD_SIZE {
+            for j in 0..BOARD_SIZE {
+                let target = match board.get_piece(i, j).unwrap() {
+                    Some(piece) => piece,
+                    None => continue,
+                };
+                //a piece is attacked by whatever could capture it
+                let mut capturers: Vec<(usize, usize)> = (0..BOARD_SIZE)
+                    .flat_map(|row| (0..BOARD_SIZE).map(move |col| (row, col)))
+                    .filter(|&(row, col)| {
+                        board
+                            .get_piece(row, col)
+                            .unwrap()
+                            .is_some_and(|piece| piece.color != target.color)
+                            && destinations(&board, row, col).contains(&(i, j))
+                    })
+                    .collect();
+                let mut attackers = attackers_of(&board, i, j, target.color.opposite());
+                capturers.sort();
+                attackers.sort();
+                assert_eq!(
+                    attackers,
+                    capturers,
+                    "{} on {}",
+                    board_fen(&board),
+                    square_name(i, j)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_capture_and_check_subsets() {
+    let occupied = |board: &Board| {
+        (0..BOARD_SIZE)
+            .flat_map(|i| (0..BOARD_SIZE).map(move |j| (i, j)))
+            .filter(|&(i, j)| board.get_piece(i, j).unwrap().is_some())
+            .count()
+    };
+    for board in random_positions(0x7e405, 80) {
+        let moves = legal_moves(&board);
+        //every move that takes a piece off the board, en passant included, and every
+        //promotion
+        let mut expected_captures: Vec<ChessMove> = moves
+            .iter()
+            .filter(|(chess_move, ending_board)| match chess_move {
+                ChessMove::Normal(_) => occupied(ending_board) < occupied(&board),
+                ChessMove::Promotion(_, _) => true,
+                ChessMove::Castling(_) => false,
+            })
+            .map(|(chess_move, _)| *chess_move)
+            .collect();
+        let mut expected_checks: Vec<ChessMove> = moves
+            .iter()
+            .filter(|(_, ending_board)| ending_board.is_check.is_some())
+            .map(|(chess_move, _)| *chess_move)
+            .collect();
+        let mut captures = generate_captures(&board);
+        let mut checks = generate_checks(&board);
+        for list in [
+            &mut expected_captures,
+            &mut expected_checks,
+            &mut captures,
+            &mut checks,
+        ] {
+            list.sort();
+        }
+        assert_eq!(captures, expected_captures, "{}", board_fen(&board));
+        assert_eq!(checks, expected_checks, "{}", board_fen(&board));
+    }
+    //en passant right after the double push
+    let mut board = make_board_from_fen("4k3/3p4/8/4P3/8/8/8/4K3 b - - 0 1").unwrap();
+    let push = board.interpret_move("d5").unwrap();
+    board.make_legal_move(push).unwrap();
+    let captures: Vec<String> = generate_captures(&board)
+        .iter()
+        .map(|chess_move| chess_move.to_lan(&board))
+        .collect();
+    assert_eq!(captures, ["e5d6"]);
+    assert_eq!(
+        static_exchange(&board, &generate_captures(&board)[0]),
+        *piece_worth_king_zero(PieceKind::Pawn)
+    );
+    //a promotion that checks and a discovered check
+    let board = make_board_from_fen("1k6/4P3/8/8/1N6/8/8/1R2K3 w - - 0 1").unwrap();
+    let checks: Vec<String> = generate_checks(&board)
+        .iter()
+        .map(|chess_move| chess_move.to_lan(&board))
+        .collect();
+    assert!(checks.contains(&String::from("e7e8q")));
+    assert!(checks.contains(&String::from("b4d3")));
+}
+
+#[test]
+fn test_san_line_matches_name() {
+    //two knights that can reach d2, so the names need disambiguating
+    let board = make_board_from_fen("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1").unwrap();
+    let line: Vec<ChessMove> = ["Nbd2", "Ke7", "Nf3d4"]
+        .iter()
+        .scan(board.clone(), |board, name| {
+            let chess_move = interpret_san(board, name).unwrap();
+            board.make_legal_move(chess_move).unwrap();
+            Some(chess_move)
+        })
+        .collect();
+    let mut expected = Vec::new();
+    let mut played = board.clone();
+    for chess_move in &line {
+        expected.push(chess_move.name(&played).unwrap());
+        played.make_legal_move(*chess_move).unwrap();
+    }
+    assert_eq!(san_line(&board, &line).unwrap(), expected);
+    //the second time comes from the cache
+    assert_eq!(san_line(&board, &line).unwrap(), expected);
+    assert_eq!(expected[0], "Nbd2");
+}
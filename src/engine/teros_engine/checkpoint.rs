@@ -0,0 +1,340 @@
+use std::collections::{BTreeMap, BinaryHeap, HashMap, VecDeque};
+
+use ordered_float::NotNan;
+use rust_chess::chess::chess::*;
+
+use super::board::{
+    fen_with_castling, lan_between, legal_moves, refresh_status, BoardMovesCache, CastlingRights,
+};
+#[cfg(test)]
+use super::tree::EngineError;
+use super::tree::{Engine, Material, MoveTree, ValuedChessMove, ValuedMoveLocation};
+#[cfg(test)]
+use super::weights::{
+    InterestEvaluationWeights, MinimaxSettings, SearchSettings, StaticEvaluationWeights,
+};
+
+const HEADER: &str = "teros checkpoint 1";
+
+//the long algebraic of a move already in the tree, read off the board it leads to
+fn child_lan(tree: &MoveTree, chess_move: &ChessMove) -> String {
+    lan_between(
+        &tree.board_state,
+        chess_move,
+        &tree.moves[chess_move].board_state,
+    )
+}
+
+//the child a long algebraic move leads to. matched against the children already there,
+//so loading doesn't generate moves for every step of every path
+fn child_by_lan<'t>(tree: &'t MoveTree, lan: &str) -> Result<(ChessMove, &'t MoveTree), String> {
+    tree.moves
+        .iter()
+        .find(|(chess_move, child)| {
+            lan_between(&tree.board_state, chess_move, &child.board_state) == lan
+        })
+        .map(|(chess_move, child)| (*chess_move, child))
+        .ok_or_else(|| format!("{} leads outside the tree", lan))
+}
+
+//the moves of a location written out as long algebraic
+fn location_lan(engine: &Engine, location: &VecDeque<ChessMove>) -> String {
+    let mut tree = &engine.move_tree;
+    let mut lans = Vec::new();
+    for chess_move in location {
+        lans.push(child_lan(tree, chess_move));
+        tree = &tree.moves[chess_move];
+    }
+    lans.join(" ")
+}
+
+//the moves of a written out location, followed down the tree
+fn read_location(engine: &Engine, lans: &str) -> Result<VecDeque<ChessMove>, String> {
+    let mut tree = &engine.move_tree;
+    let mut location = VecDeque::new();
+    for lan in lans.split_whitespace() {
+        let (chess_move, child) = child_by_lan(tree, lan)?;
+        location.push_back(chess_move);
+        tree = child;
+    }
+    Ok(location)
+}
+
+fn frontier_line(engine: &Engine, kind: &str, entry: &ValuedMoveLocation) -> String {
+    let parent = engine
+        .get_location(&entry.location)
+        .expect("frontier entries live in the tree");
+    //the bits of the floats, so they read back exactly
+    format!(
+        "{} {:08x} {:08x} {} -> {}",
+        kind,
+        entry.valued_move.value.into_inner().to_bits(),
+        entry.depth_cost.into_inner().to_bits(),
+        location_lan(engine, &entry.location),
+        child_lan(parent, &entry.valued_move.chess_move)
+    )
+}
+
+//the castling rights in the third field of a FEN
+fn castling_field(fen: &str) -> CastlingRights {
+    CastlingRights::from_fen(fen.split_whitespace().nth(2).unwrap_or("-"))
+}
+
+fn read_float(bits: &str) -> Result<NotNan<f32>, String> {
+    let bits = u32::from_str_radix(bits, 16).map_err(|_| format!("bad float {}", bits))?;
+    NotNan::new(f32::from_bits(bits)).map_err(|_| String::from("NaN in checkpoint"))
+}
+
+fn read_frontier_entry(engine: &Engine, rest: &str) -> Result<ValuedMoveLocation, String> {
+    let (head, chess_move) = rest
+        .split_once("->")
+        .ok_or_else(|| String::from("frontier entry without a move"))?;
+    let mut fields = head.splitn(3, ' ');
+    let value = read_float(fields.next().unwrap_or(""))?;
+    let depth_cost = read_float(fields.next().unwrap_or(""))?;
+    let location = read_location(engine, fields.next().unwrap_or(""))?;
+    let parent = engine.get_location(&location).unwrap();
+    let (chess_move, _) = child_by_lan(parent, chess_move.trim())?;
+    Ok(ValuedMoveLocation {
+        valued_move: ValuedChessMove { value, chess_move },
+        location,
+        depth_cost,
+        //like the other settings, the frontier strategy is the resuming engine's
+        strategy: engine.search_settings.frontier_strategy,
+    })
+}
+
+impl Engine {
+    //everything pondering has built, as text: the tree, the frontier (as the heap's own
+    //vector, so it rebuilds the same), the transposition table and the game history that
+    //draws are judged by, with the moves played since the game started. weights and
+    //settings aren't included, they come from the engine that resumes
+    pub fn checkpoint(&self) -> String {
+        let (start, start_castling) = &self.game_start;
+        let mut board = start.clone();
+        let mut played = Vec::new();
+        for chess_move in &self.move_history {
+            let before = board.clone();
+            board
+                .make_legal_move(*chess_move)
+                .expect("the game's moves are legal where they were played");
+            played.push(lan_between(&before, chess_move, &board));
+        }
+        let mut lines = vec![
+            String::from(HEADER),
+            format!(
+                "root {}",
+                fen_with_castling(&self.move_tree.board_state, self.move_tree.castling)
+            ),
+            format!("start {}", fen_with_castling(start, *start_castling)),
+            format!("played {}", played.join(" ")),
+            format!("halfmove {}", self.halfmove_clock),
+            format!(
+                "history {}",
+                self.position_history
+                    .iter()
+                    .map(|key| format!("{:016x}", key))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+        ];
+        //parents before their children, so loading always has the node to add to
+        let mut stack = vec![(&self.move_tree, VecDeque::new())];
+        while let Some((tree, location)) = stack.pop() {
+            if tree.is_leaf() {
+                continue;
+            }
+            let children: Vec<String> = tree
+                .moves
+                .keys()
+                .map(|chess_move| child_lan(tree, chess_move))
+                .collect();
+            lines.push(format!(
+                "node {} -> {}",
+                location_lan(self, &location),
+                children.join(" ")
+            ));
+            for (chess_move, child) in tree.moves.iter().rev() {
+                let mut child_location = location.clone();
+                child_location.push_back(*chess_move);
+                stack.push((child, child_location));
+            }
+        }
+        for entry in self.moves.clone().into_vec().iter() {
+            lines.push(frontier_line(self, "frontier", entry));
+        }
+        for entry in self.linked_moves.iter() {
+            lines.push(frontier_line(self, "linked", entry));
+        }
+        //the table keeps the first location each position was expanded at, which a
+        //rebuild from the tree wouldn't always pick
+        let mut canonical: Vec<&VecDeque<ChessMove>> = self.transpositions.values().collect();
+        canonical.sort();
+        for location in canonical {
+            lines.push(format!("transposition {}", location_lan(self, location)));
+        }
+        lines.join("\n") + "\n"
+    }
+
+    //picks up a checkpoint where it was taken, replacing the position and everything
+    //pondered so far
+    pub fn resume(&mut self, checkpoint: &str) -> Result<(), String> {
+        let mut lines = checkpoint.lines().enumerate();
+        match lines.next() {
+            Some((_, HEADER)) => {}
+            _ => return Err(String::from("not a teros checkpoint")),
+        }
+        let mut position_history = Vec::new();
+        let mut game_start = None;
+        let mut move_history = Vec::new();
+        let mut halfmove_clock = 0;
+        let mut frontier = Vec::new();
+        for (index, line) in lines {
+            let at_line = |message: String| format!("line {}: {}", index + 1, message);
+            let (kind, rest) = line.split_once(' ').unwrap_or((line, ""));
+            match kind {
+                "root" => {
+                    let mut board = make_board_from_fen(rest)
+                        .map_err(|err| at_line(format!("bad root ({:?})", err)))?;
+                    //a FEN board doesn't know whether the side to move is in check
+                    refresh_status(&mut board);
+                    self.move_tree = MoveTree {
+                        material: Material::of(&board),
+                        castling: castling_field(rest),
+                        board_state: board,
+                        moves: BTreeMap::new(),
+                        moves_cache: BoardMovesCache::default(),
+                    };
+                    self.transpositions = HashMap::new();
+                    self.linked_moves = Vec::new();
+                }
+                "start" => {
+                    let board = make_board_from_fen(rest)
+                        .map_err(|err| at_line(format!("bad start ({:?})", err)))?;
+                    game_start = Some((board, castling_field(rest)));
+                }
+                "played" => {
+                    let mut board = match &game_start {
+                        Some((board, _)) => board.clone(),
+                        None => return Err(at_line(String::from("moves played before a start"))),
+                    };
+                    for lan in rest.split_whitespace() {
+                        let (chess_move, after) = legal_moves(&board)
+                            .into_iter()
+                            .find(|(chess_move, after)| {
+                                lan_between(&board, chess_move, after) == lan
+                            })
+                            .ok_or_else(|| at_line(format!("illegal move {}", lan)))?;
+                        move_history.push(chess_move);
+                        board = after;
+                    }
+                }
+                "halfmove" => {
+                    halfmove_clock = rest
+                        .parse()
+                        .map_err(|_| at_line(format!("bad halfmove clock {}", rest)))?;
+                }
+                "history" => {
+                    for key in rest.split_whitespace() {
+                        position_history.push(
+                            u64::from_str_radix(key, 16)
+                                .map_err(|_| at_line(format!("bad position key {}", key)))?,
+                        );
+                    }
+                }
+                "node" => {
+                    let (location, children) = rest
+                        .split_once("->")
+                        .ok_or_else(|| at_line(String::from("node without children")))?;
+                    let location = read_location(self, location).map_err(at_line)?;
+                    let tree = self.go_to_location(&location).unwrap();
+                    //the node's moves are generated once and picked from, as its
+                    //expansion did
+                    let mut legal = legal_moves(&tree.board_state);
+                    for lan in children.split_whitespace() {
+                        let index = legal
+                            .iter()
+                            .position(|(chess_move, board)| {
+                                lan_between(&tree.board_state, chess_move, board) == lan
+                            })
+                            .ok_or_else(|| at_line(format!("illegal move {}", lan)))?;
+                        let (chess_move, board) = legal.swap_remove(index);
+                        let child = tree.child(&chess_move, board);
+                        tree.moves.insert(chess_move, child);
+                    }
+                }
+                "frontier" => frontier.push(read_frontier_entry(self, rest).map_err(at_line)?),
+                "linked" => {
+                    let entry = read_frontier_entry(self, rest).map_err(at_line)?;
+                    self.linked_moves.push(entry);
+                }
+                "transposition" => {
+                    let location = read_location(self, rest).map_err(at_line)?;
+                    let key = self.get_location(&location).unwrap().key();
+                    self.transpositions.insert(key, location);
+                }
+                _ => return Err(at_line(format!("unknown entry {}", kind))),
+            }
+        }
+        self.moves = BinaryHeap::from(frontier);
+        self.tree_size = self.move_tree.size();
+        self.position_history = position_history;
+        self.move_history = move_history;
+        self.game_start = game_start.ok_or_else(|| String::from("checkpoint without a start"))?;
+        self.halfmove_clock = halfmove_clock;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_checkpoint_resume() -> Result<(), EngineError> {
+    let new_engine = || {
+        Engine::new(
+            Board::new(),
+            StaticEvaluationWeights::new(),
+            InterestEvaluationWeights::new(),
+            MinimaxSettings::new(),
+            SearchSettings::new(),
+        )
+    };
+    let mut engine = new_engine();
+    //the kings walk out and back, so the root has lost rights its board can't show
+    for san in ["e4", "e5", "Ke2", "Ke7", "Ke1", "Ke8"] {
+        engine.interpret_and_make_move(san)?;
+    }
+    for _ in 0..40 {
+        engine.think_next_move()?;
+    }
+    let checkpoint = engine.checkpoint();
+
+    let mut resumed = new_engine();
+    resumed.resume(&checkpoint).unwrap();
+    assert_eq!(resumed.checkpoint(), checkpoint);
+    assert_eq!(resumed.move_history(), engine.move_history());
+    assert_eq!(resumed.move_tree.castling, CastlingRights::from_fen("-"));
+    assert_eq!(resumed.move_tree.key(), engine.move_tree.key());
+    assert_eq!(resumed.moves.peek(), engine.moves.peek());
+
+    for _ in 0..40 {
+        engine.think_next_move()?;
+        resumed.think_next_move()?;
+    }
+    assert!(resumed.move_tree == engine.move_tree);
+    assert_eq!(resumed.tree_size(), engine.tree_size());
+    assert_eq!(
+        resumed.moves.clone().into_sorted_vec(),
+        engine.moves.clone().into_sorted_vec()
+    );
+
+    //a root with the side to move in check
+    let mut engine = new_engine();
+    engine.set_position(make_board_from_fen("4k3/8/8/8/8/8/8/4K2r w - - 0 1").unwrap());
+    for _ in 0..20 {
+        engine.think_next_move()?;
+    }
+    let mut resumed = new_engine();
+    resumed.resume(&engine.checkpoint()).unwrap();
+    assert!(resumed.get_board().is_check.is_some());
+    assert!(resumed.move_tree == engine.move_tree);
+    Ok(())
+}
@@ -0,0 +1,1426 @@
+use std::{
+    cell::Cell,
+    collections::{BTreeMap, BinaryHeap, VecDeque},
+    sync::{
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+#[cfg(test)]
+use ordered_float::NotNan;
+use rust_chess::chess::chess::*;
+
+#[cfg(test)]
+use super::board::legal_moves;
+use super::board::{is_mate, legal_moves_on_rank, CastlingRights, MoveParseError};
+use super::eval::Eval;
+use super::search::{count, Iteration, SearchLimits, SearchResult};
+use super::tree::{
+    interpret_on, Engine, EngineError, MoveTree, ValuedChessMove, ValuedMoveLocation,
+};
+use super::weights::SearchSettings;
+#[cfg(test)]
+use super::weights::{InterestEvaluationWeights, MinimaxSettings, StaticEvaluationWeights};
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+//f of every item, split over thread_count workers and returned in item order, so callers
+//see the same result as a serial map. a single worker maps on the calling thread
+pub(super) fn parallel_map<T: Sync, R: Send>(
+    items: &[T],
+    thread_count: usize,
+    f: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    if thread_count <= 1 {
+        return items.iter().map(f).collect();
+    }
+    let chunk_size = items.len().div_ceil(thread_count).max(1);
+    let f = &f;
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(f).collect::<Vec<R>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+//legal_moves with the ranks generated side by side
+pub(super) fn parallel_legal_moves(board: &Board, thread_count: usize) -> Vec<(ChessMove, Board)> {
+    let ranks: Vec<usize> = (0..BOARD_SIZE).collect();
+    parallel_map(&ranks, thread_count, |&rank| {
+        legal_moves_on_rank(board, rank)
+    })
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+//told the index and move of a root move as the search enters it
+pub type RootMoveCallback<'a> = &'a dyn Fn(usize, &ChessMove);
+
+thread_local! {
+    //set when a search on this thread bailed out because of the stop flag
+    pub(super) static SEARCH_ABORTED: Cell<bool> = const { Cell::new(false) };
+}
+
+//how a pondering worker breaks near ties on the frontier, so workers sharing it don't all
+//chase the same near-equal moves. the queued interests are left alone: at each pop the
+//worker picks among the entries within epsilon of the best one by a fixed linear
+//congruential sequence, so a run can be repeated
+#[derive(Debug, Clone)]
+pub(super) struct OrderingNoise {
+    state: u64,
+    epsilon: f32,
+}
+
+//most entries a worker chooses among, however many are tied
+const MAX_TIED_ENTRIES: usize = 8;
+
+impl OrderingNoise {
+    //none for the first worker, which keeps the engine's own ordering so a single thread
+    //searches exactly like the serial engine
+    pub(super) fn for_worker(settings: &SearchSettings, worker: usize) -> Option<OrderingNoise> {
+        (worker > 0 && settings.ordering_epsilon > 0.0).then(|| OrderingNoise {
+            state: settings.ordering_seed.wrapping_add(worker as u64),
+            epsilon: settings.ordering_epsilon,
+        })
+    }
+
+    //an index below count
+    fn pick(&mut self, count: usize) -> usize {
+        self.state = self
+            .state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.state >> 33) as usize % count
+    }
+
+    //the best entry of the frontier, or one of those tied with it. the others go back
+    //unchanged
+    fn pop(&mut self, moves: &mut BinaryHeap<ValuedMoveLocation>) -> Option<ValuedMoveLocation> {
+        let mut tied = vec![moves.pop()?];
+        while let Some(next) = moves.peek() {
+            let best = &tied[0];
+            if tied.len() == MAX_TIED_ENTRIES
+                || next.breadth_rank() != best.breadth_rank()
+                || (best.value_accounted_for_distance() - next.value_accounted_for_distance())
+                    .into_inner()
+                    > self.epsilon
+            {
+                break;
+            }
+            tied.push(moves.pop().unwrap());
+        }
+        let picked = tied.swap_remove(self.pick(tied.len()));
+        moves.extend(tied);
+        Some(picked)
+    }
+}
+
+//worker threads pondering a shared engine until told to finish
+#[derive(Debug)]
+struct Pondering {
+    engine: Arc<Mutex<Engine>>,
+    keep_going: Arc<Mutex<bool>>,
+    counter: Arc<Mutex<usize>>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl Pondering {
+    fn start(mut engine: Engine, thread_count: usize) -> Pondering {
+        engine.thread_count = thread_count;
+        let keep_going = Arc::new(Mutex::new(true));
+        let search_settings = engine.search_settings.clone();
+        let engine_arc: Arc<Mutex<Engine>> = Arc::new(Mutex::new(engine));
+        let mut threads = Vec::new();
+        let counter = Arc::new(Mutex::new(0));
+        for worker in 0..thread_count {
+            let my_engine = engine_arc.clone();
+            let my_keep_going = keep_going.clone();
+            let my_counter = counter.clone();
+            let mut noise = OrderingNoise::for_worker(&search_settings, worker);
+            threads.push(thread::spawn(move || loop {
+                match Engine::think_next_move_cocurrent_with(&*my_engine, noise.as_mut()) {
+                    Ok(_) => {}
+                    //other threads may still be refilling the frontier, and once a mate
+                    //is found it stays empty until the workers are told to finish
+                    Err(EngineError::NoValidMovesErrror) => {
+                        if !*my_keep_going.lock().unwrap() {
+                            break;
+                        }
+                        thread::yield_now();
+                        continue;
+                    }
+                    Err(err) => panic!("{:?}", err),
+                };
+                let keep_going_lock = my_keep_going.lock().unwrap();
+                let mut counter_lock = my_counter.lock().unwrap();
+                *counter_lock += 1;
+                if !*keep_going_lock {
+                    break;
+                }
+            }))
+        }
+        Pondering {
+            engine: engine_arc,
+            keep_going,
+            counter,
+            threads,
+        }
+    }
+
+    //stops the workers, returning the engine and how many expansions they did
+    fn finish(self) -> (Engine, usize) {
+        let mut keep_going_lock = self.keep_going.lock().unwrap();
+
+        *keep_going_lock = false;
+
+        drop(keep_going_lock);
+
+        for thread in self.threads {
+            thread.join().unwrap();
+        }
+
+        let engine_lock = Arc::try_unwrap(self.engine).expect("Lock still has multiple owners");
+        let counter_lock = Arc::try_unwrap(self.counter).expect("Lock still has multiple owners");
+        (
+            engine_lock.into_inner().expect("Mutex cannot be locked"),
+            counter_lock.into_inner().expect("Mutex cannot be locked"),
+        )
+    }
+}
+
+//what Engine::ponder leaves running: workers expanding the position after the reply
+//the engine expects, while the opponent thinks
+#[derive(Debug)]
+pub struct Ponder {
+    pondering: Pondering,
+    predicted: ChessMove,
+    //the position and game history from before the predicted reply, to start over from
+    //when the opponent plays something else
+    board: Board,
+    castling: CastlingRights,
+    position_history: Vec<u64>,
+    move_history: Vec<ChessMove>,
+    game_start: (Board, CastlingRights),
+    halfmove_clock: usize,
+}
+
+impl Ponder {
+    pub fn predicted(&self) -> &ChessMove {
+        &self.predicted
+    }
+
+    //the position the opponent is to reply from
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    //Engine::try_interpret for the opponent's reply
+    pub fn try_interpret(&self, move_string: &str) -> Result<ChessMove, MoveParseError> {
+        interpret_on(&self.board, move_string)
+    }
+
+    //stops the workers without a reply, for when the engine is wanted back before the
+    //opponent moves. what was pondered is dropped with the predicted reply
+    pub fn cancel(self) -> Engine {
+        let (mut engine, _) = self.pondering.finish();
+        engine.reset_to(self.board, Some(self.castling));
+        engine.position_history = self.position_history;
+        engine.move_history = self.move_history;
+        engine.game_start = self.game_start;
+        engine.halfmove_clock = self.halfmove_clock;
+        engine
+    }
+
+    //stops the workers once the opponent's real reply is known. when it's the predicted
+    //one the engine comes back with everything pondered on top of its tree, otherwise
+    //the tree is dropped and grown again from the position the reply really leads to.
+    //the bool says whether the prediction hit. an illegal reply gives the pondering
+    //back still running
+    pub fn resolve(self, chess_move: &ChessMove) -> Result<(Engine, bool), Box<Ponder>> {
+        if self.board.clone().make_legal_move(*chess_move).is_err() {
+            return Err(Box::new(self));
+        }
+        if *chess_move == self.predicted {
+            let (engine, _) = self.pondering.finish();
+            return Ok((engine, true));
+        }
+        let mut engine = self.cancel();
+        engine
+            .make_move(chess_move)
+            .expect("a legal reply is in the regenerated tree");
+        Ok((engine, false))
+    }
+}
+
+impl Engine {
+    //progress, if given, is called from the calling thread every PROGRESS_INTERVAL
+    //with the number of expansions done so far
+    pub fn multi_thread_think_next_num_moves(
+        mut self,
+        thread_count: usize,
+        num: usize,
+        progress: Option<&dyn Fn(usize)>,
+    ) -> Engine {
+        self.thread_count = thread_count;
+        let search_settings = self.search_settings.clone();
+        let engine_arc: Arc<Mutex<Engine>> = Arc::new(Mutex::new(self));
+        let mut threads = Vec::new();
+        let counter = Arc::new(Mutex::new(0));
+        for worker in 0..thread_count {
+            let my_engine = engine_arc.clone();
+            let my_counter = counter.clone();
+            let my_num = num.clone();
+            let mut noise = OrderingNoise::for_worker(&search_settings, worker);
+            threads.push(thread::spawn(move || {
+                loop {
+                    match Engine::think_next_move_cocurrent_with(&*my_engine, noise.as_mut()) {
+                        Ok(_) => {}
+                        Err(EngineError::NoValidMovesErrror) => break,
+                        Err(err) => {
+                            Result::<(), EngineError>::Err(err).unwrap();
+                        }
+                    };
+                    let mut counter_lock = my_counter.lock().unwrap();
+                    *counter_lock += 1;
+                    if *counter_lock > my_num {
+                        break;
+                    }
+                }
+
+                return;
+            }))
+        }
+
+        if let Some(progress) = progress {
+            while !threads.iter().all(|thread| thread.is_finished()) {
+                thread::sleep(PROGRESS_INTERVAL);
+                let done = *counter.lock().unwrap();
+                progress(done.min(num));
+            }
+        }
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let lock = Arc::try_unwrap(engine_arc).expect("Lock still has multiple owners");
+        lock.into_inner().expect("Mutex cannot be locked")
+    }
+
+    pub fn multi_thread_think_next_moves_until_stop(
+        self,
+        thread_count: usize,
+        stopper: Receiver<()>,
+    ) -> (Engine, usize) {
+        let pondering = Pondering::start(self, thread_count);
+        stopper.recv().unwrap();
+        pondering.finish()
+    }
+
+    //ponders until stop like multi_thread_think_next_moves_until_stop, deepening over the
+    //tree every interval and passing report the root board and the iterations whenever
+    //the final eval or best move changed since the last report. a snapshot of the tree is
+    //searched, so pondering only waits while it's taken
+    pub fn analyze(
+        self,
+        thread_count: usize,
+        interval: Duration,
+        stopper: Receiver<()>,
+        mut report: impl FnMut(&Board, &[Iteration]),
+    ) -> (Engine, usize) {
+        let pondering = Pondering::start(self, thread_count);
+        let mut last_reported = None;
+        while let Err(RecvTimeoutError::Timeout) = stopper.recv_timeout(interval) {
+            let engine = pondering.engine.lock().unwrap().snapshot();
+            let iterations = engine.iterations(None);
+            let last = iterations.last().expect("the first iteration is kept");
+            let current = (last.eval.clone(), last.best_move);
+            if last_reported.as_ref() != Some(&current) {
+                report(engine.get_board(), &iterations);
+                last_reported = Some(current);
+            }
+        }
+        pondering.finish()
+    }
+
+    //ponders with every thread until duration has passed, for runs that shouldn't wait on
+    //anyone to stop them
+    pub fn think_for(self, thread_count: usize, duration: Duration) -> (Engine, usize) {
+        let (stop_sender, stop_receiver) = mpsc::channel();
+        let timer = thread::spawn(move || {
+            thread::sleep(duration);
+            stop_sender.send(()).unwrap();
+        });
+        let res = self.multi_thread_think_next_moves_until_stop(thread_count, stop_receiver);
+        timer.join().unwrap();
+        res
+    }
+
+    //for the opponent's thinking time, right after the engine moved: predicts the reply
+    //from the principal variation, plays it, and leaves thread_count workers pondering
+    //the position behind it until Ponder::resolve. gives the engine back when the
+    //opponent has no reply to predict
+    pub fn ponder(mut self, thread_count: usize) -> Result<Ponder, Box<Engine>> {
+        let predicted = match self.principal_variation().first() {
+            Some(chess_move) => *chess_move,
+            None => return Err(Box::new(self)),
+        };
+        let board = self.get_board().clone();
+        let castling = self.move_tree.castling;
+        let position_history = self.position_history.clone();
+        let move_history = self.move_history.clone();
+        let game_start = self.game_start.clone();
+        let halfmove_clock = self.halfmove_clock;
+        self.make_move(&predicted)
+            .expect("the principal variation starts with a legal move");
+        Ok(Ponder {
+            pondering: Pondering::start(self, thread_count),
+            predicted,
+            board,
+            castling,
+            position_history,
+            move_history,
+            game_start,
+            halfmove_clock,
+        })
+    }
+
+    pub fn think_next_move_cocurrent(engine: &Mutex<Engine>) -> Result<(), EngineError> {
+        Engine::think_next_move_cocurrent_with(engine, None)
+    }
+
+    //think_next_move_cocurrent for a worker that breaks near ties on the frontier by noise
+    pub(super) fn think_next_move_cocurrent_with(
+        engine: &Mutex<Engine>,
+        noise: Option<&mut OrderingNoise>,
+    ) -> Result<(), EngineError> {
+        let mut engine_access = engine.lock().unwrap();
+        let next_move = match noise {
+            Some(noise) => noise.pop(&mut engine_access.moves),
+            None => engine_access.moves.pop(),
+        }
+        .ok_or(EngineError::NoValidMovesErrror)?;
+        let mut location = next_move.location.clone();
+        location.push_back(next_move.valued_move.chess_move);
+        if engine_access.transposition_of(&location)?.is_some() {
+            engine_access.linked_moves.push(next_move);
+            return Ok(());
+        }
+        //a stale entry for a node that was expanded already
+        if !engine_access.get_location(&location)?.is_leaf() {
+            return Ok(());
+        }
+        drop(engine_access);
+        Engine::generate_all_moves_cocurrent(engine, location)
+    }
+
+    fn generate_all_moves_cocurrent(
+        engine: &Mutex<Engine>,
+        location: VecDeque<ChessMove>,
+    ) -> Result<(), EngineError> {
+        //get weights
+        let mut engine_access = engine.lock().unwrap();
+
+        let depth_cost = engine_access.search_settings.frontier_depth_cost;
+        let strategy = engine_access.search_settings.frontier_strategy;
+
+        //copy board to work on local thread
+        let tree = engine_access.go_to_location(&location)?.clone();
+        let interest_weights = engine_access.interest_weights_at(&tree.board_state);
+        //free engine for others to use
+        drop(engine_access);
+        //generate moves, from the ones the node's eval already generated
+        let all_moves = tree.board_moves().legal_moves(&tree.board_state);
+
+        let mut move_map = BTreeMap::new();
+
+        for (chesss_move, ending_board) in all_moves {
+            move_map.insert(chesss_move, tree.child(&chesss_move, ending_board));
+        }
+
+        //once a move mates, its siblings aren't worth pondering
+        let mate_found = move_map.values().any(|child| is_mate(&child.board_state));
+
+        //store all the moves in proper formats
+        let valued_move_locations: Vec<ValuedMoveLocation> = move_map
+            .iter()
+            .filter(|(_, child)| !mate_found || is_mate(&child.board_state))
+            .map(|(chess_move, child)| ValuedMoveLocation {
+                valued_move: ValuedChessMove {
+                    value: Engine::evaluate_interest(
+                        &interest_weights,
+                        chess_move,
+                        &tree.board_state,
+                        &child.board_state,
+                        tree.board_moves(),
+                        child.board_moves(),
+                    )
+                    .unwrap(),
+                    chess_move: *chess_move,
+                },
+                location: location.clone(),
+                depth_cost,
+                strategy,
+            })
+            .collect();
+
+        //get back on engine to add values
+        engine_access = engine.lock().unwrap();
+
+        let real_tree = engine_access.go_to_location(&location)?;
+
+        //another worker expanded the node while this one worked on it. its children are
+        //already in the tree and the frontier, so this expansion is dropped instead of
+        //replacing them and leaving their frontier entries pointing at nothing
+        if !real_tree.is_leaf() {
+            return Ok(());
+        }
+        real_tree.moves = move_map;
+        //the clone's generated moves stay with the node
+        real_tree.moves_cache = tree.moves_cache.clone();
+        engine_access.tree_size += real_tree.moves.len();
+
+        for valued_move_location in valued_move_locations {
+            engine_access.moves.push(valued_move_location);
+        }
+
+        engine_access
+            .transpositions
+            .entry(tree.key())
+            .or_insert(location);
+        let evicted = engine_access.evict_if_needed();
+        engine_access.evicted += evicted;
+
+        drop(engine_access);
+        //yipee!
+
+        Ok(())
+    }
+
+    pub fn parallel_eval_and_best_move(
+        self: Arc<Self>,
+        thread_count: usize,
+    ) -> (Eval, Option<ChessMove>) {
+        let result = self.parallel_search(thread_count, None);
+        (result.eval, result.best_move)
+    }
+
+    //root_move_entered, if given, is called from the calling thread with the index and
+    //move of every root move a worker starts on, in root order
+    pub fn parallel_search(
+        self: Arc<Self>,
+        thread_count: usize,
+        root_move_entered: Option<RootMoveCallback>,
+    ) -> SearchResult {
+        if self.move_tree.is_leaf() || self.tablebase_root_move().is_some() {
+            return self.search();
+        }
+        self.reset_depths_reached();
+        let maximizing_player = self.move_tree.board_state.get_turn() == Color::White;
+
+        //pick in root order so ties break exactly like the serial minimax
+        let mut best_eval = Eval::MateIn(
+            match maximizing_player {
+                true => Color::Black,
+                false => Color::White,
+            },
+            -1,
+        );
+        let mut best_move = None;
+        for (chess_move, eval) in self
+            .clone()
+            .parallel_root_evals(thread_count, root_move_entered)
+        {
+            let better = match maximizing_player {
+                true => eval > best_eval,
+                false => eval < best_eval,
+            };
+            if better {
+                best_eval = eval;
+                best_move = Some(chess_move);
+            }
+        }
+        self.search_result(best_eval, best_move)
+    }
+
+    //the n best root moves with their evals, best first. ties keep root order
+    //depths_reached tells how deep they were searched
+    pub fn best_n_moves(self: Arc<Self>, thread_count: usize, n: usize) -> Vec<(Eval, ChessMove)> {
+        self.reset_depths_reached();
+        let maximizing_player = self.move_tree.board_state.get_turn() == Color::White;
+        let mut lines: Vec<(Eval, ChessMove)> = self
+            .parallel_root_evals(thread_count, None)
+            .into_iter()
+            .map(|(chess_move, eval)| (eval, chess_move))
+            .collect();
+        match maximizing_player {
+            true => lines.sort_by(|a, b| b.0.cmp(&a.0)),
+            false => lines.sort_by(|a, b| a.0.cmp(&b.0)),
+        }
+        lines.truncate(n);
+        lines
+    }
+
+    //every worker pulls the next unsearched root move and runs a serial minimax on it,
+    //so no thread idles while another is still busy deep in the tree.
+    //returns the eval of every root move, in root order
+    fn parallel_root_evals(
+        self: Arc<Self>,
+        thread_count: usize,
+        root_move_entered: Option<RootMoveCallback>,
+    ) -> Vec<(ChessMove, Eval)> {
+        self.interruptible_root_evals(thread_count, None, root_move_entered)
+            .into_iter()
+            .map(|(chess_move, eval)| {
+                (
+                    chess_move,
+                    eval.expect("every root move is searched by some worker"),
+                )
+            })
+            .collect()
+    }
+
+    //like parallel_eval_and_best_move, but a message on stop ends the search early.
+    //the answer then comes from the root moves that were searched to the end, so a
+    //move is returned as long as at least one of them finished
+    pub fn parallel_eval_and_best_move_interruptible(
+        self: Arc<Self>,
+        thread_count: usize,
+        stop: Receiver<()>,
+    ) -> (Eval, Option<ChessMove>) {
+        let maximizing_player = self.move_tree.board_state.get_turn() == Color::White;
+        let mut best_eval = Eval::worst_for(maximizing_player);
+        let mut best_move = None;
+        for (chess_move, eval) in self.interruptible_root_evals(thread_count, Some(stop), None) {
+            let eval = match eval {
+                Some(eval) => eval,
+                None => continue,
+            };
+            let better = match maximizing_player {
+                true => eval > best_eval,
+                false => eval < best_eval,
+            };
+            if better || best_move.is_none() {
+                best_eval = eval;
+                best_move = Some(chess_move);
+            }
+        }
+        (best_eval, best_move)
+    }
+
+    //iterative deepening over the pondered tree, one ply further each time, until a
+    //limit or a message on stop ends it. answers with the eval, best move and principal
+    //variation of the deepest search that finished. the first ply always finishes, so a
+    //move comes back whenever the root has one
+    pub fn go(
+        &self,
+        limits: SearchLimits,
+        stop: Receiver<()>,
+    ) -> (Eval, Option<ChessMove>, Vec<ChessMove>) {
+        let started = Instant::now();
+        self.reset_search_stats();
+        let mut stopped = false;
+        let answer = match self.tablebase_root_move() {
+            Some((eval, chess_move)) => (eval, Some(chess_move), vec![chess_move]),
+            None => {
+                self.reset_depths_reached();
+                let first = self.first_iteration();
+                //set only now, so the first ply is never cut short
+                if let Some(nodes) = limits.nodes {
+                    self.node_limit.store(nodes, AtomicOrdering::Relaxed);
+                }
+                let iterations = thread::scope(|scope| {
+                    let search = scope.spawn(|| self.deepen(limits.depth, first));
+                    while !search.is_finished() {
+                        stopped = stopped || stop.try_recv().is_ok();
+                        let out_of_time = limits
+                            .movetime
+                            .is_some_and(|movetime| started.elapsed() >= movetime);
+                        if stopped || out_of_time {
+                            self.stop_search.store(true, AtomicOrdering::Relaxed);
+                        }
+                        thread::sleep(STOP_POLL_INTERVAL);
+                    }
+                    search.join().unwrap()
+                });
+                let last = iterations
+                    .into_iter()
+                    .last()
+                    .expect("the first iteration is kept");
+                (last.eval, last.best_move, last.principal_variation)
+            }
+        };
+        self.stop_search.store(false, AtomicOrdering::Relaxed);
+        self.node_limit.store(usize::MAX, AtomicOrdering::Relaxed);
+        if limits.infinite && !stopped {
+            //a dropped sender can't stop anything anymore, so it ends the wait too
+            let _ = stop.recv();
+        }
+        answer
+    }
+
+    //go's iterative deepening without the limits, every iteration up to depth_cap (or
+    //max_depth) kept. the last one is the answer, and the best_move_changed_from of the
+    //others tell where the engine changed its mind on the way
+    pub fn iterations(&self, depth_cap: Option<i32>) -> Vec<Iteration> {
+        if let Some((eval, chess_move)) = self.tablebase_root_move() {
+            return vec![Iteration {
+                depth: 1,
+                eval,
+                best_move: Some(chess_move),
+                principal_variation: vec![chess_move],
+                best_move_changed_from: None,
+            }];
+        }
+        self.reset_depths_reached();
+        let first = self.first_iteration();
+        self.deepen(depth_cap, first)
+    }
+
+    fn first_iteration(&self) -> Iteration {
+        let (eval, best_move, principal_variation) =
+            self.line_to_depth(self.search_settings.min_depth.min(1), 1);
+        Iteration {
+            depth: 1,
+            eval,
+            best_move,
+            principal_variation,
+            best_move_changed_from: None,
+        }
+    }
+
+    //first followed by the rest of the iterations, from ply 2 up to depth_cap (or
+    //max_depth), leaving out one that was aborted. deepening stops early once a search
+    //never reaches its cap, as every deeper one would come out the same
+    fn deepen(&self, depth_cap: Option<i32>, first: Iteration) -> Vec<Iteration> {
+        let max_depth = self.search_settings.max_depth;
+        let depth_cap = depth_cap.map_or(max_depth, |depth| depth.clamp(1, max_depth));
+        let mut iterations = vec![first];
+        for depth in 2..=depth_cap {
+            if self.deepest_ply.load(AtomicOrdering::Relaxed) < depth as usize - 1 {
+                break;
+            }
+            self.reset_depths_reached();
+            SEARCH_ABORTED.with(|aborted| aborted.set(false));
+            let (eval, best_move, principal_variation) =
+                self.line_to_depth(self.search_settings.min_depth.min(depth), depth);
+            if SEARCH_ABORTED.with(|aborted| aborted.get()) {
+                break;
+            }
+            let previous = iterations.last().and_then(|iteration| iteration.best_move);
+            iterations.push(Iteration {
+                depth,
+                eval,
+                best_move,
+                principal_variation,
+                best_move_changed_from: previous.filter(|&previous| Some(previous) != best_move),
+            });
+        }
+        iterations
+    }
+
+    //root moves whose search was cut short by stop come back as None
+    fn interruptible_root_evals(
+        self: Arc<Self>,
+        thread_count: usize,
+        stop: Option<Receiver<()>>,
+        root_move_entered: Option<RootMoveCallback>,
+    ) -> Vec<(ChessMove, Option<Eval>)> {
+        let min_depth = self.search_settings.min_depth;
+        let max_depth = self.search_settings.max_depth;
+        let maximizing_player = self.move_tree.board_state.get_turn() == Color::White;
+        let root_key = self.move_tree.key();
+
+        let root_moves: Arc<Vec<ChessMove>> =
+            Arc::new(self.move_tree.moves.keys().copied().collect());
+        let next_root_move = Arc::new(AtomicUsize::new(0));
+        let mut threads = Vec::new();
+
+        for _ in 0..thread_count.max(1) {
+            let my_self = self.clone();
+            let my_root_moves = root_moves.clone();
+            let my_next_root_move = next_root_move.clone();
+            threads.push(thread::spawn(move || {
+                let mut results = Vec::new();
+                loop {
+                    if my_self.stop_search.load(AtomicOrdering::Relaxed) {
+                        break;
+                    }
+                    let index = my_next_root_move.fetch_add(1, AtomicOrdering::Relaxed);
+                    let chess_move = match my_root_moves.get(index) {
+                        Some(chess_move) => *chess_move,
+                        None => break,
+                    };
+                    let child = &my_self.move_tree.moves[&chess_move];
+                    SEARCH_ABORTED.with(|aborted| aborted.set(false));
+                    let eval = my_self
+                        .minimax(
+                            child,
+                            1,
+                            min_depth,
+                            max_depth,
+                            !maximizing_player,
+                            Eval::worst_for(true),
+                            Eval::worst_for(false),
+                            &mut vec![root_key],
+                        )
+                        .0
+                        .increase_mate_counter();
+                    if SEARCH_ABORTED.with(|aborted| aborted.get()) {
+                        break;
+                    }
+                    results.push((index, eval));
+                }
+                results
+            }));
+        }
+
+        //every index handed out by next_root_move is a root move some worker entered
+        let mut reported = 0;
+        let report_entered = |reported: &mut usize| {
+            let entered = next_root_move
+                .load(AtomicOrdering::Relaxed)
+                .min(root_moves.len());
+            if let Some(root_move_entered) = root_move_entered {
+                for index in *reported..entered {
+                    root_move_entered(index, &root_moves[index]);
+                }
+            }
+            *reported = entered;
+        };
+        if stop.is_some() || root_move_entered.is_some() {
+            while !threads.iter().all(|thread| thread.is_finished()) {
+                report_entered(&mut reported);
+                if stop.as_ref().is_some_and(|stop| stop.try_recv().is_ok()) {
+                    self.stop_search.store(true, AtomicOrdering::Relaxed);
+                    break;
+                }
+                thread::sleep(STOP_POLL_INTERVAL);
+            }
+        }
+
+        let mut sub_evals: Vec<Option<Eval>> = vec![None; root_moves.len()];
+        for thread in threads {
+            for (index, eval) in thread.join().unwrap() {
+                sub_evals[index] = Some(eval);
+            }
+        }
+        self.stop_search.store(false, AtomicOrdering::Relaxed);
+        report_entered(&mut reported);
+
+        root_moves.iter().copied().zip(sub_evals).collect()
+    }
+
+    //the previous scheme, which splits subtrees between threads on a shrinking budget.
+    //kept around so the bench harness can compare the two
+    pub fn split_parallel_eval_and_best_move(
+        self: Arc<Self>,
+        thread_count: usize,
+    ) -> (Eval, Option<ChessMove>) {
+        let thread_count_arc = Arc::new(Mutex::new(thread_count - 1));
+        self.clone().parallel_minimax(
+            &self.move_tree,
+            0,
+            self.search_settings.min_depth,
+            self.search_settings.max_depth,
+            self.move_tree.board_state.get_turn() == Color::White,
+            Eval::worst_for(true),
+            Eval::worst_for(false),
+            thread_count_arc,
+            &mut Vec::new(),
+        )
+    }
+
+    //young brothers wait: the eldest child is searched first to get a bound, then the
+    //younger ones run in parallel, each starting from the best bound found so far.
+    //results are then replayed in move order, re-searching any child whose cutoff came
+    //from a bound the serial search wouldn't have had yet, so the outcome matches minimax
+    #[allow(clippy::too_many_arguments)]
+    fn parallel_minimax(
+        self: Arc<Self>,
+        tree: &MoveTree,
+        depth: i32,
+        min_depth: i32,
+        max_depth: i32,
+        maximizing_player: bool,
+        mut alpha: Eval,
+        mut beta: Eval,
+        threads_left_arc: Arc<Mutex<usize>>,
+        path: &mut Vec<u64>,
+    ) -> (Eval, Option<ChessMove>) {
+        let key = tree.key();
+        let stopped = self.stop_search.load(AtomicOrdering::Relaxed);
+        if stopped || depth == max_depth || tree.is_leaf() || path.contains(&key) {
+            return self.minimax(
+                tree,
+                depth,
+                min_depth,
+                max_depth,
+                maximizing_player,
+                alpha,
+                beta,
+                path,
+            );
+        }
+        self.count_node();
+        path.push(key);
+
+        let moves: Vec<(ChessMove, MoveTree)> = tree.moves.clone().into_iter().collect();
+        let search_child = |child: &MoveTree, alpha: Eval, beta: Eval, path: &mut Vec<u64>| {
+            self.clone()
+                .parallel_minimax(
+                    child,
+                    depth + 1,
+                    min_depth,
+                    max_depth,
+                    !maximizing_player,
+                    alpha.decrease_mate_counter(),
+                    beta.decrease_mate_counter(),
+                    threads_left_arc.clone(),
+                    path,
+                )
+                .0
+                .increase_mate_counter()
+        };
+
+        //eldest brother
+        let mut best_eval = search_child(&moves[0].1, alpha.clone(), beta.clone(), path);
+        let mut best_move = Some(moves[0].0);
+        let cutoff = match maximizing_player {
+            true => best_eval >= beta,
+            false => best_eval <= alpha,
+        };
+        if cutoff {
+            count(&self.search_counters.beta_cutoffs);
+            path.pop();
+            return (best_eval, best_move);
+        }
+        match maximizing_player {
+            true => alpha = alpha.max(best_eval.clone()),
+            false => beta = beta.min(best_eval.clone()),
+        }
+
+        //the bound younger brothers share: alpha when maximizing, beta when minimizing
+        let shared_bound = Arc::new(Mutex::new(best_eval.clone()));
+        let mut threads = Vec::new();
+        //(eval, bound it was searched against) per younger brother, in move order
+        let mut sub_evals: Vec<Option<(Eval, Eval)>> = vec![None; moves.len()];
+
+        for (index, (_, child)) in moves.iter().enumerate().skip(1) {
+            let mut threads_left = threads_left_arc.lock().unwrap();
+            if *threads_left >= 1 {
+                *threads_left -= 1;
+                drop(threads_left);
+                count(&self.search_counters.split_threads);
+                let my_self = self.clone();
+                let my_threads_left_arc = threads_left_arc.clone();
+                let my_shared_bound = shared_bound.clone();
+                let (my_alpha, my_beta) = (alpha.clone(), beta.clone());
+                let child = child.clone();
+                let mut my_path = path.clone();
+                threads.push(thread::spawn(move || {
+                    let bound = my_shared_bound.lock().unwrap().clone();
+                    let (child_alpha, child_beta) = match maximizing_player {
+                        true => (bound.clone(), my_beta),
+                        false => (my_alpha, bound.clone()),
+                    };
+                    let eval = my_self
+                        .parallel_minimax(
+                            &child,
+                            depth + 1,
+                            min_depth,
+                            max_depth,
+                            !maximizing_player,
+                            child_alpha.decrease_mate_counter(),
+                            child_beta.decrease_mate_counter(),
+                            my_threads_left_arc.clone(),
+                            &mut my_path,
+                        )
+                        .0
+                        .increase_mate_counter();
+                    //the thread is done with its subtree, so another split may use it
+                    *my_threads_left_arc.lock().unwrap() += 1;
+                    let mut shared = my_shared_bound.lock().unwrap();
+                    let improves = match maximizing_player {
+                        true => eval > *shared,
+                        false => eval < *shared,
+                    };
+                    if improves {
+                        *shared = eval.clone();
+                    }
+                    (index, eval, bound)
+                }));
+            } else {
+                drop(threads_left);
+                let bound = shared_bound.lock().unwrap().clone();
+                let eval = match maximizing_player {
+                    true => search_child(child, bound.clone(), beta.clone(), path),
+                    false => search_child(child, alpha.clone(), bound.clone(), path),
+                };
+                let mut shared = shared_bound.lock().unwrap();
+                let improves = match maximizing_player {
+                    true => eval > *shared,
+                    false => eval < *shared,
+                };
+                if improves {
+                    *shared = eval.clone();
+                }
+                drop(shared);
+                sub_evals[index] = Some((eval, bound));
+            }
+        }
+
+        for thread in threads {
+            let (index, eval, bound) = thread.join().unwrap();
+            sub_evals[index] = Some((eval, bound));
+        }
+
+        for (index, sub_eval) in sub_evals.into_iter().enumerate().skip(1) {
+            let (eval, bound) = sub_eval.expect("every younger brother is searched");
+            //a fail-low against a bound tighter than the serial one says nothing here
+            let stale = match maximizing_player {
+                true => eval <= bound && bound > alpha,
+                false => eval >= bound && bound < beta,
+            };
+            let eval = match stale {
+                true => search_child(&moves[index].1, alpha.clone(), beta.clone(), path),
+                false => eval,
+            };
+            if maximizing_player {
+                if eval > best_eval {
+                    best_eval = eval;
+                    best_move = Some(moves[index].0);
+                }
+                if best_eval >= beta {
+                    count(&self.search_counters.beta_cutoffs);
+                    break;
+                }
+                alpha = alpha.max(best_eval.clone());
+            } else {
+                if eval < best_eval {
+                    best_eval = eval;
+                    best_move = Some(moves[index].0);
+                }
+                if best_eval <= alpha {
+                    count(&self.search_counters.beta_cutoffs);
+                    break;
+                }
+                beta = beta.min(best_eval.clone());
+            }
+        }
+        path.pop();
+
+        (best_eval, best_move)
+    }
+}
+
+#[test]
+fn test_parallel_alpha_beta_matches_serial() {
+    let board =
+        make_board_from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3")
+            .unwrap();
+    let mut engine = Engine::new(
+        board,
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    for _ in 0..300 {
+        engine.think_next_move().unwrap();
+    }
+    let serial = engine.eval_and_best_move();
+    let engine = Arc::new(engine);
+    engine.reset_search_stats();
+    assert_eq!(engine.clone().split_parallel_eval_and_best_move(4), serial);
+    //finished threads hand their place back, so splits go on past the first three
+    assert!(engine.search_stats().split_threads > 3);
+    assert_eq!(engine.parallel_eval_and_best_move(4), serial);
+}
+
+#[test]
+fn test_interruptible_search() {
+    let mut engine = Engine::new(
+        Board::new(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    for _ in 0..100 {
+        engine.think_next_move().unwrap();
+    }
+    let engine = Arc::new(engine);
+    let expected = engine.eval_and_best_move();
+
+    //never stopped: same answer as the uninterrupted search
+    let (_sender, stop) = std::sync::mpsc::channel();
+    assert_eq!(
+        engine
+            .clone()
+            .parallel_eval_and_best_move_interruptible(2, stop),
+        expected
+    );
+
+    //stopped straight away: whatever comes back was searched to the end
+    let (sender, stop) = std::sync::mpsc::channel();
+    sender.send(()).unwrap();
+    let (eval, best_move) = engine
+        .clone()
+        .parallel_eval_and_best_move_interruptible(2, stop);
+    if let Some(chess_move) = best_move {
+        let complete = engine.clone().parallel_root_evals(1, None);
+        assert!(complete.contains(&(chess_move, eval)));
+    }
+    assert!(!engine.stop_search.load(AtomicOrdering::Relaxed));
+}
+
+#[test]
+fn test_go_limits() {
+    let mut engine = Engine::new(
+        Board::new(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    for _ in 0..100 {
+        engine.think_next_move().unwrap();
+    }
+    let (eval, best_move) = engine.eval_and_best_move();
+
+    //no limits: deepened until the whole tree is searched, same as a plain search
+    let (_sender, stop) = std::sync::mpsc::channel();
+    let (go_eval, go_move, pv) = engine.go(SearchLimits::default(), stop);
+    assert_eq!((go_eval, go_move), (eval, best_move));
+    assert_eq!(pv, engine.principal_variation());
+
+    //a node cap cuts deepening short, but the first ply still gives a move
+    let (_sender, stop) = std::sync::mpsc::channel();
+    let limits = SearchLimits {
+        nodes: Some(1),
+        ..SearchLimits::default()
+    };
+    let (_, capped_move, _) = engine.go(limits, stop);
+    assert!(capped_move.is_some());
+    assert!(!engine.stop_search.load(AtomicOrdering::Relaxed));
+
+    //and stops on exactly the node it was given, the same way every time
+    let (_sender, stop) = std::sync::mpsc::channel();
+    engine.go(SearchLimits::default(), stop);
+    let nodes = engine.nodes_searched() / 2;
+    let limits = SearchLimits {
+        nodes: Some(nodes),
+        ..SearchLimits::default()
+    };
+    let (_sender, stop) = std::sync::mpsc::channel();
+    let capped = engine.go(limits, stop);
+    assert_eq!(engine.nodes_searched(), nodes);
+    let (_sender, stop) = std::sync::mpsc::channel();
+    assert_eq!(engine.go(limits, stop), capped);
+
+    //infinite waits on stop even after the search is done
+    let (sender, stop) = std::sync::mpsc::channel();
+    let stopper = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        sender.send(()).unwrap();
+    });
+    let start = Instant::now();
+    let limits = SearchLimits {
+        depth: Some(1),
+        infinite: true,
+        ..SearchLimits::default()
+    };
+    engine.go(limits, stop);
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    stopper.join().unwrap();
+}
+
+#[test]
+fn test_iterations_record_best_move_changes() {
+    let mut engine = Engine::new(
+        make_board_from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3")
+            .unwrap(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    for _ in 0..300 {
+        engine.think_next_move().unwrap();
+    }
+    let iterations = engine.iterations(None);
+    let last = iterations.last().unwrap();
+    assert_eq!(
+        (last.eval.clone(), last.best_move),
+        engine.eval_and_best_move()
+    );
+    assert_eq!(iterations[0].best_move_changed_from, None);
+    for (depth, pair) in iterations.windows(2).enumerate() {
+        assert_eq!(pair[1].depth, depth as i32 + 2);
+        let changed = pair[0].best_move != pair[1].best_move;
+        assert_eq!(
+            pair[1].best_move_changed_from,
+            pair[0].best_move.filter(|_| changed)
+        );
+    }
+    assert_eq!(engine.iterations(Some(2)).len(), 2);
+}
+
+#[test]
+fn test_think_for() {
+    let engine = Engine::new(
+        Board::new(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    let start = std::time::Instant::now();
+    let (engine, pondered) = engine.think_for(2, Duration::from_millis(100));
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    assert!(pondered > 0);
+    assert!(engine.tree_size > 21);
+}
+
+#[test]
+fn test_think_for_mate_in_one() {
+    //the frontier empties as soon as the mate is found, well before the time is up
+    let engine = Engine::new(
+        make_board_from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    let (engine, _) = engine.think_for(2, Duration::from_millis(100));
+    let best_move = engine.search().best_move.unwrap();
+    assert_eq!(
+        engine.get_board().clone().interpret_move("Ra8").unwrap(),
+        best_move
+    );
+}
+
+#[test]
+fn test_analyze_reports_changes() {
+    let engine = Engine::new(
+        Board::new(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    let (sender, stop) = mpsc::channel();
+    let stopper = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(300));
+        sender.send(()).unwrap();
+    });
+    let mut reports = Vec::new();
+    let (engine, pondered) = engine.analyze(2, Duration::from_millis(20), stop, |_, iterations| {
+        let last = iterations.last().unwrap();
+        reports.push((last.eval.clone(), last.best_move))
+    });
+    stopper.join().unwrap();
+    assert!(pondered > 0);
+    assert!(!reports.is_empty());
+    assert!(reports.windows(2).all(|pair| pair[0] != pair[1]));
+    assert_eq!(engine.get_board(), &Board::new());
+}
+
+#[test]
+fn test_parallel_legal_moves_in_order() {
+    let board =
+        make_board_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+    let names = |moves: Vec<(ChessMove, Board)>| -> Vec<String> {
+        moves
+            .iter()
+            .map(|(chess_move, _)| chess_move.name(&board).unwrap())
+            .collect()
+    };
+    assert_eq!(
+        names(parallel_legal_moves(&board, 4)),
+        names(legal_moves(&board))
+    );
+    for thread_count in [1, 4] {
+        assert_eq!(
+            parallel_map(&(0..100).collect::<Vec<i32>>(), thread_count, |x| x * 2),
+            (0..100).map(|x| x * 2).collect::<Vec<i32>>()
+        );
+    }
+}
+
+#[test]
+fn test_ponder_keeps_tree_on_hit() {
+    let new_engine = || {
+        let mut engine = Engine::new(
+            Board::new(),
+            StaticEvaluationWeights::new(),
+            InterestEvaluationWeights::new(),
+            MinimaxSettings::new(),
+            SearchSettings::new(),
+        );
+        for _ in 0..200 {
+            engine.think_next_move().unwrap();
+        }
+        engine
+    };
+
+    let ponder = new_engine().ponder(2).unwrap();
+    let predicted = *ponder.predicted();
+    thread::sleep(Duration::from_millis(50));
+    let (engine, hit) = ponder.resolve(&predicted).unwrap();
+    assert!(hit);
+    assert_eq!(engine.move_history(), &[predicted]);
+    assert!(engine.tree_size > 21);
+
+    let ponder = new_engine().ponder(2).unwrap();
+    let predicted = *ponder.predicted();
+    let board = Board::new();
+    let (other, _) = legal_moves(&board)
+        .into_iter()
+        .find(|(chess_move, _)| *chess_move != predicted)
+        .unwrap();
+    //an illegal reply keeps the pondering going
+    let e2e5 = ChessMove::Normal(NormalChessMove {
+        initial_row: 1,
+        initial_col: 4,
+        destination_row: 4,
+        destination_col: 4,
+    });
+    let ponder = match ponder.resolve(&e2e5) {
+        Ok(_) => panic!("e2e5 isn't a legal reply"),
+        Err(ponder) => ponder,
+    };
+    let (engine, hit) = ponder.resolve(&other).unwrap();
+    assert!(!hit);
+    assert_eq!(engine.move_history(), &[other]);
+    assert_eq!(engine.position_history.len(), 2);
+    assert_eq!(engine.tree_size, engine.move_tree.size());
+
+    //cancelled, the engine is back before the reply with nothing played
+    let ponder = new_engine().ponder(2).unwrap();
+    assert_eq!(
+        ponder.try_interpret("e4").unwrap(),
+        board.interpret_move("e4").unwrap()
+    );
+    let engine = ponder.cancel();
+    assert_eq!(engine.get_board(), &board);
+    assert!(engine.move_history().is_empty());
+    assert_eq!(engine.position_history.len(), 1);
+}
+
+#[test]
+fn test_concurrent_expansion_keeps_tree_consistent() {
+    let mut engine = Engine::new(
+        make_board_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    for _ in 0..20 {
+        engine.think_next_move().unwrap();
+    }
+    //every frontier entry twice, as if the same node had been handed out to two workers
+    let entries: Vec<ValuedMoveLocation> = engine.moves.iter().cloned().collect();
+    engine.moves.extend(entries);
+
+    let engine = engine.multi_thread_think_next_num_moves(16, 300, None);
+    assert_eq!(engine.tree_size, engine.move_tree.size());
+    //the duplicates not popped yet may be stale, but none may point out of the tree
+    for entry in engine.moves.iter() {
+        let parent = engine.get_location(&entry.location).unwrap();
+        assert!(parent.moves.contains_key(&entry.valued_move.chess_move));
+    }
+    //an expanded node keeps the moves it was expanded from, whichever worker expanded it
+    fn assert_generated(tree: &MoveTree) {
+        if tree.is_leaf() {
+            return;
+        }
+        assert!(tree.moves_cache.is_generated());
+        tree.moves.values().for_each(assert_generated);
+    }
+    assert_generated(&engine.move_tree);
+}
+
+#[test]
+fn test_ordering_noise_per_worker() {
+    assert!(OrderingNoise::for_worker(&SearchSettings::new(), 1).is_none());
+    let settings = SearchSettings {
+        ordering_epsilon: 0.5,
+        ..SearchSettings::new()
+    };
+    assert!(OrderingNoise::for_worker(&settings, 0).is_none());
+    let values = [10.0, 9.9, 9.8, 5.0];
+    let frontier = || -> BinaryHeap<ValuedMoveLocation> {
+        legal_moves(&Board::new())
+            .into_iter()
+            .zip(values)
+            .map(|((chess_move, _), value)| ValuedMoveLocation {
+                valued_move: ValuedChessMove {
+                    value: NotNan::new(value).unwrap(),
+                    chess_move,
+                },
+                location: VecDeque::new(),
+                depth_cost: settings.frontier_depth_cost,
+                strategy: settings.frontier_strategy,
+            })
+            .collect()
+    };
+    let picks = |worker| {
+        let mut noise = OrderingNoise::for_worker(&settings, worker).unwrap();
+        (0..50)
+            .map(|_| {
+                let mut moves = frontier();
+                let picked = noise.pop(&mut moves).unwrap();
+                //the rest stay queued with their interest untouched
+                let mut left: Vec<f32> = moves
+                    .into_iter()
+                    .map(|entry| entry.valued_move.value.into_inner())
+                    .chain([picked.valued_move.value.into_inner()])
+                    .collect();
+                left.sort_by(|a, b| b.partial_cmp(a).unwrap());
+                assert_eq!(left, values);
+                picked.valued_move.value.into_inner()
+            })
+            .collect::<Vec<f32>>()
+    };
+    assert_eq!(picks(1), picks(1));
+    assert_ne!(picks(1), picks(2));
+    //only the entries within epsilon of the best are picked, and not always the best
+    assert!(picks(3).iter().all(|value| *value > 9.0));
+    assert!(picks(3).iter().any(|value| *value < 10.0));
+}
+
+#[test]
+fn test_best_n_moves_depths() {
+    let engine = Arc::new(
+        Engine::new(
+            Board::new(),
+            StaticEvaluationWeights::new(),
+            InterestEvaluationWeights::new(),
+            MinimaxSettings::new(),
+            SearchSettings::new(),
+        )
+        .multi_thread_think_next_num_moves(2, 100, None),
+    );
+    let lines = engine.clone().best_n_moves(2, 3);
+    assert_eq!(lines.len(), 3);
+    assert!(lines.windows(2).all(|pair| pair[0].0 >= pair[1].0));
+    let (depth, seldepth) = engine.depths_reached();
+    assert_eq!(depth, SearchSettings::new().min_depth as usize);
+    assert!(seldepth >= depth);
+}
@@ -0,0 +1,269 @@
+use std::cmp::Ordering;
+
+use ordered_float::NotNan;
+use rust_chess::chess::chess::*;
+
+#[cfg(test)]
+use super::board::mirror;
+use super::board::{attackers_of, find_king};
+use super::eval::Eval;
+
+//how a won KPK ending is scored, before progress is added. a queen up, so the engine
+//never trades the win away for material
+const KPK_WIN: f32 = 10.0;
+
+//king distance between two squares
+fn distance(a: (usize, usize), b: (usize, usize)) -> usize {
+    a.0.abs_diff(b.0).max(a.1.abs_diff(b.1))
+}
+
+//king and pawn against king, decided by rule: the pawn outrunning the defending king (the
+//rule of the square), the attacking king on a key square or taking the opposition in
+//front of its pawn, and the draws of a defending king in front of the pawn, in the corner
+//of a rook pawn, or about to take the pawn. None for other material, and for KPK
+//positions the rules don't settle, which are left to the heuristic eval
+pub(super) fn kpk_eval(board: &Board) -> Option<Eval> {
+    let mut pawn = None;
+    let mut kings = [None, None];
+    for i in 0..BOARD_SIZE {
+        for j in 0..BOARD_SIZE {
+            match board.get_piece(i, j).unwrap() {
+                None => {}
+                Some(piece) if piece.kind == PieceKind::Pawn && pawn.is_none() => {
+                    pawn = Some((piece.color, (i, j)));
+                }
+                Some(piece) if piece.kind == PieceKind::King => {
+                    kings[(piece.color == Color::Black) as usize] = Some((i, j));
+                }
+                Some(_) => return None,
+            }
+        }
+    }
+    let (strong, (pawn_row, pawn_col)) = pawn?;
+    let [white_king, black_king] = kings;
+    let (strong_king, weak_king) = match strong {
+        Color::White => (white_king?, black_king?),
+        Color::Black => (black_king?, white_king?),
+    };
+
+    //squares seen from the strong side, its pawn moving up the rows
+    let relative = |(row, col): (usize, usize)| match strong {
+        Color::White => (row, col),
+        Color::Black => (BOARD_SIZE - 1 - row, col),
+    };
+    let pawn = relative((pawn_row, pawn_col));
+    let strong_king = relative(strong_king);
+    let weak_king = relative(weak_king);
+    let strong_to_move = board.get_turn() == strong;
+    let queening = (BOARD_SIZE - 1, pawn.1);
+
+    let won = || {
+        let progress = pawn.0 as f32 - 0.1 * distance(strong_king, pawn) as f32;
+        let value = (KPK_WIN + progress)
+            * match strong {
+                Color::White => 1.0,
+                Color::Black => -1.0,
+            };
+        Some(Eval::Numeric(NotNan::new(value).unwrap()))
+    };
+    let drawn = || Some(Eval::Numeric(NotNan::new(0.0).unwrap()));
+
+    //the pawn is taken before anything else happens
+    if !strong_to_move && distance(weak_king, pawn) == 1 && distance(strong_king, pawn) > 1 {
+        return drawn();
+    }
+
+    //the rule of the square: the defending king catches the pawn if it can stand next to
+    //the queening square when the pawn gets there, with one more move when it's to move.
+    //a pawn on its starting row gets a double step
+    let pawn_moves = (BOARD_SIZE - 1 - pawn.0) - (pawn.0 == 1) as usize;
+    let path_clear = !(strong_king.1 == pawn.1 && strong_king.0 > pawn.0);
+    if path_clear && distance(weak_king, queening) > pawn_moves + !strong_to_move as usize {
+        return won();
+    }
+
+    let weak_king_in_front = weak_king.1 == pawn.1 && weak_king.0 > pawn.0;
+    //a rook pawn can't be forced through a king that reaches the corner
+    if pawn.1 == 0 || pawn.1 == BOARD_SIZE - 1 {
+        return match weak_king_in_front || distance(weak_king, queening) <= 1 {
+            true => drawn(),
+            false => None,
+        };
+    }
+
+    //key squares: two rows ahead of the pawn, and the row ahead as well once the pawn is
+    //past the middle
+    let key_rows = match pawn.0 {
+        row if row <= 3 => row + 2..=row + 2,
+        row => row + 1..=(row + 2).min(BOARD_SIZE - 1),
+    };
+    if key_rows.contains(&strong_king.0) && strong_king.1.abs_diff(pawn.1) <= 1 {
+        return won();
+    }
+    //ahead of its pawn with the opposition, the attacking king gets to a key square
+    let opposition = strong_king.1 == weak_king.1 && weak_king.0 == strong_king.0 + 2;
+    if strong_king.0 > pawn.0 && opposition && !strong_to_move {
+        return won();
+    }
+    if weak_king_in_front && strong_king.0 <= pawn.0 {
+        return drawn();
+    }
+    None
+}
+
+//how much of its eval a position keeps once it's recognized as a fortress
+pub(super) const FORTRESS_SCALE: f32 = 0.1;
+
+//every piece on the board with its square
+fn pieces_of(board: &Board) -> Vec<(Piece, (usize, usize))> {
+    let mut pieces = Vec::new();
+    for i in 0..BOARD_SIZE {
+        for j in 0..BOARD_SIZE {
+            if let Some(piece) = board.get_piece(i, j).unwrap() {
+                pieces.push((piece, (i, j)));
+            }
+        }
+    }
+    pieces
+}
+
+//textbook fortresses, drawn however far ahead one side is. far from every fortress there
+//is, only ones a few pieces decide
+pub(super) fn is_fortress(board: &Board) -> bool {
+    wrong_rook_pawn(board) || blocked_opposite_bishops(board)
+}
+
+//bishop and rook pawns against a bare king that has reached the queening corner, when the
+//bishop can never drive it out: the corner is of the other color
+fn wrong_rook_pawn(board: &Board) -> bool {
+    let pieces = pieces_of(board);
+    [Color::White, Color::Black].into_iter().any(|strong| {
+        let (own, other): (Vec<_>, Vec<_>) =
+            pieces.iter().partition(|(piece, _)| piece.color == strong);
+        if other.iter().any(|(piece, _)| piece.kind != PieceKind::King) {
+            return false;
+        }
+        let squares_of = |kind: PieceKind| -> Vec<(usize, usize)> {
+            own.iter()
+                .filter(|(piece, _)| piece.kind == kind)
+                .map(|(_, square)| *square)
+                .collect()
+        };
+        let (bishops, pawns) = (squares_of(PieceKind::Bishop), squares_of(PieceKind::Pawn));
+        //the king, the bishop and the pawns, nothing else
+        if bishops.len() != 1 || pawns.is_empty() || pawns.len() + 2 != own.len() {
+            return false;
+        }
+        let col = pawns[0].1;
+        if (col != 0 && col != BOARD_SIZE - 1) || pawns.iter().any(|pawn| pawn.1 != col) {
+            return false;
+        }
+        let queening = match strong {
+            Color::White => (BOARD_SIZE - 1, col),
+            Color::Black => (0, col),
+        };
+        let wrong_bishop = (bishops[0].0 + bishops[0].1) % 2 != (queening.0 + queening.1) % 2;
+        wrong_bishop
+            && find_king(board, strong.opposite()).is_some_and(|king| distance(king, queening) <= 1)
+    })
+}
+
+//bishops of opposite colors and pawns only, with every pawn of the side that has more of
+//them stopped: the square in front is taken, or the other side's bishop or king covers
+//it. a pawn covering it can be pushed past with an exchange, so it doesn't count
+fn blocked_opposite_bishops(board: &Board) -> bool {
+    let pieces = pieces_of(board);
+    let mut bishops = Vec::new();
+    let mut pawn_counts = [0, 0];
+    for (piece, square) in pieces.iter() {
+        match piece.kind {
+            PieceKind::Knight | PieceKind::Rook | PieceKind::Queen => return false,
+            PieceKind::Bishop => bishops.push((piece.color, (square.0 + square.1) % 2)),
+            PieceKind::Pawn => pawn_counts[(piece.color == Color::Black) as usize] += 1,
+            PieceKind::King => {}
+        }
+    }
+    let opposite = match bishops[..] {
+        [(color, shade), (other_color, other_shade)] => {
+            color != other_color && shade != other_shade
+        }
+        _ => false,
+    };
+    if !opposite {
+        return false;
+    }
+    let strong = match pawn_counts[0].cmp(&pawn_counts[1]) {
+        Ordering::Greater => Color::White,
+        Ordering::Less => Color::Black,
+        Ordering::Equal => return false,
+    };
+    pieces
+        .iter()
+        .filter(|(piece, _)| piece.kind == PieceKind::Pawn && piece.color == strong)
+        .all(|(_, (row, col))| {
+            let stop = match strong {
+                Color::White => row + 1,
+                Color::Black => row - 1,
+            };
+            board.get_piece(stop, *col).unwrap().is_some()
+                || attackers_of(board, stop, *col, strong.opposite())
+                    .into_iter()
+                    .any(|(row, col)| {
+                        board.get_piece(row, col).unwrap().is_some_and(|piece| {
+                            matches!(piece.kind, PieceKind::Bishop | PieceKind::King)
+                        })
+                    })
+        })
+}
+
+#[test]
+fn test_fortress() {
+    let fortress = |fen: &str| {
+        let board = make_board_from_fen(fen).unwrap();
+        let verdict = is_fortress(&board);
+        assert_eq!(is_fortress(&mirror(&board)), verdict);
+        verdict
+    };
+    //the light-squared bishop can't chase the king out of h8
+    assert!(fortress("7k/8/8/7P/8/8/4B3/4K3 w - - 0 1"));
+    assert!(!fortress("7k/8/8/7P/8/8/3B4/4K3 w - - 0 1"));
+    assert!(!fortress("8/8/8/7P/8/2k5/4B3/4K3 w - - 0 1"));
+    //two pawns up, neither of them able to move on
+    assert!(fortress("8/8/6k1/1b6/5PP1/4B3/6K1/8 w - - 0 1"));
+    assert!(!fortress("k7/8/8/1b6/5PP1/4B3/6K1/8 w - - 0 1"));
+    //b6 is only covered by the a7 pawn, and b6 axb6 cxb6 leaves two passers far apart
+    assert!(!fortress("4b3/p7/7k/1PP5/3B2P1/8/3K4/8 w - - 0 1"));
+}
+
+#[test]
+fn test_kpk() {
+    let verdict = |fen: &str| {
+        kpk_eval(&make_board_from_fen(fen).unwrap()).map(|eval| eval.centipawns().unwrap().signum())
+    };
+    //king in front of its pawn on the sixth
+    assert_eq!(verdict("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1"), Some(1));
+    //the black king is outside the square of the pawn
+    assert_eq!(verdict("8/8/8/P7/8/8/7k/K7 w - - 0 1"), Some(1));
+    //on the edge of the square, the side to move decides it
+    assert_eq!(verdict("8/8/4k3/P7/8/8/8/K7 w - - 0 1"), Some(1));
+    assert_ne!(verdict("8/8/3k4/P7/8/8/8/K7 w - - 0 1"), Some(1));
+    assert_eq!(verdict("8/8/5k2/P7/8/8/8/K7 b - - 0 1"), Some(1));
+    assert_ne!(verdict("8/8/4k3/P7/8/8/8/K7 b - - 0 1"), Some(1));
+    //a pawn on its starting row outruns a king one step further away
+    assert_eq!(verdict("8/8/8/8/8/6k1/P7/1K6 w - - 0 1"), Some(1));
+    assert_ne!(verdict("8/8/8/8/8/5k2/P7/1K6 w - - 0 1"), Some(1));
+    //black on a key square of its own pawn
+    assert_eq!(verdict("8/8/8/8/4p3/4k3/8/4K3 b - - 0 1"), Some(-1));
+    //black to move gives up the opposition
+    assert_eq!(verdict("8/8/4k3/8/4K3/4P3/8/8 b - - 0 1"), Some(1));
+
+    //the defending king holds the square in front of the pawn
+    assert_eq!(verdict("8/8/4k3/8/4P3/4K3/8/8 w - - 0 1"), Some(0));
+    //rook pawn with the defending king in the corner
+    assert_eq!(verdict("k7/8/1K6/P7/8/8/8/8 w - - 0 1"), Some(0));
+    //the pawn is lost
+    assert_eq!(verdict("8/8/8/8/8/3k4/4P3/7K b - - 0 1"), Some(0));
+
+    //other material is left to the heuristic eval
+    assert_eq!(verdict("4k3/8/8/8/8/8/4P3/3QK3 w - - 0 1"), None);
+}
@@ -0,0 +1,1839 @@
+#[cfg(test)]
+use std::collections::VecDeque;
+use std::{cmp::Ordering, f32::INFINITY, fmt};
+
+use ordered_float::NotNan;
+use rust_chess::chess::chess::*;
+
+use super::board::{
+    attackers_of, defenders, find_king, game_phase, generate_captures, generate_checks, is_mate,
+    is_past_pawn, pawn_attacks, static_exchange, BoardMoves,
+};
+#[cfg(test)]
+use super::board::{board_fen, legal_moves, mirror, random_positions};
+use super::endgame::{is_fortress, kpk_eval, FORTRESS_SCALE};
+use super::tree::{DrawReason, Engine, Material, MoveTree};
+#[cfg(test)]
+use super::weights::SearchSettings;
+use super::weights::{InterestEvaluationWeights, MinimaxSettings, StaticEvaluationWeights};
+
+//pawns a numeric eval is shown as at most, either way. the infinite leaf evals and
+//king-capturing material swings go well past it
+const DISPLAY_BOUND: f32 = 100.0;
+
+pub(super) fn piece_worth_king_inf(piece: PieceKind) -> NotNan<f32> {
+    match piece {
+        PieceKind::Pawn => NotNan::new(1.0).unwrap(),
+        PieceKind::Knight => NotNan::new(3.0).unwrap(),
+        PieceKind::Bishop => NotNan::new(3.0).unwrap(),
+        PieceKind::Rook => NotNan::new(5.0).unwrap(),
+        PieceKind::Queen => NotNan::new(9.0).unwrap(),
+        PieceKind::King => NotNan::new(INFINITY).unwrap(),
+    }
+}
+
+pub(super) fn piece_worth_king_zero(piece: PieceKind) -> NotNan<f32> {
+    match piece {
+        PieceKind::Pawn => NotNan::new(1.0).unwrap(),
+        PieceKind::Knight => NotNan::new(3.0).unwrap(),
+        PieceKind::Bishop => NotNan::new(3.0).unwrap(),
+        PieceKind::Rook => NotNan::new(5.0).unwrap(),
+        PieceKind::Queen => NotNan::new(9.0).unwrap(),
+        PieceKind::King => NotNan::new(0.0).unwrap(),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Eval {
+    Numeric(NotNan<f32>),
+    MateIn(Color, i32),
+}
+
+impl Eval {
+    pub(super) fn increase_mate_counter(self) -> Eval {
+        match self {
+            Eval::Numeric(_) => self,
+            Eval::MateIn(color, counter) => Eval::MateIn(color, counter + 1),
+        }
+    }
+
+    //inverse of increase_mate_counter. moves a parent's bound into the child's frame
+    pub(super) fn decrease_mate_counter(self) -> Eval {
+        match self {
+            Eval::Numeric(_) => self,
+            Eval::MateIn(color, counter) => Eval::MateIn(color, counter - 1),
+        }
+    }
+
+    //the worst possible eval for the side picking, below any real result
+    pub(super) fn worst_for(maximizing_player: bool) -> Eval {
+        Eval::MateIn(
+            match maximizing_player {
+                true => Color::Black,
+                false => Color::White,
+            },
+            -1,
+        )
+    }
+
+    //the eval as side sees it, for output that's relative to the side to move: for black
+    //numbers are negated and mates swap sides, so centipawns and mate_in_moves come out
+    //from side's point of view. search keeps working from white's
+    pub fn relative_to(self, side: Color) -> Eval {
+        match (self, side) {
+            (eval, Color::White) => eval,
+            (Eval::Numeric(value), Color::Black) => Eval::Numeric(-value),
+            (Eval::MateIn(color, plies), Color::Black) => Eval::MateIn(color.opposite(), plies),
+        }
+    }
+
+    //numeric evals in centipawns from white's point of view, None for mates
+    pub fn centipawns(&self) -> Option<i32> {
+        match self {
+            Eval::Numeric(value) => Some((value.into_inner() * 100.0) as i32),
+            Eval::MateIn(_, _) => None,
+        }
+    }
+
+    //full moves to mate, positive when white is the one mating, None for numeric evals
+    pub fn mate_in_moves(&self) -> Option<i32> {
+        match self {
+            Eval::Numeric(_) => None,
+            Eval::MateIn(color, plies) => {
+                let moves = (plies + 1) / 2;
+                match color {
+                    Color::White => Some(moves),
+                    Color::Black => Some(-moves),
+                }
+            }
+        }
+    }
+
+    //numeric evals capped at DISPLAY_BOUND pawns either way, for showing to users. mates
+    //are left alone, and the search keeps working with the full value
+    pub fn clamp_for_display(&self) -> Eval {
+        match self {
+            Eval::Numeric(value) => Eval::Numeric((*value).clamp(
+                NotNan::new(-DISPLAY_BOUND).unwrap(),
+                NotNan::new(DISPLAY_BOUND).unwrap(),
+            )),
+            Eval::MateIn(_, _) => self.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Eval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Eval::Numeric(value) => write!(f, "{}", value),
+            Eval::MateIn(color, value) => {
+                write!(
+                    f,
+                    "{}M{}",
+                    match color {
+                        Color::Black => '-',
+                        Color::White => '+',
+                    },
+                    value
+                )
+            }
+        }
+    }
+}
+
+impl Ord for Eval {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Eval::Numeric(value1), Eval::Numeric(value2)) => value1.cmp(value2),
+            (Eval::MateIn(color1, value1), Eval::MateIn(color2, value2)) => {
+                match (color1, color2) {
+                    (Color::Black, Color::White) => Ordering::Less,
+                    (Color::White, Color::Black) => Ordering::Greater,
+                    (Color::White, Color::White) => value1.cmp(value2).reverse(),
+                    (Color::Black, Color::Black) => value1.cmp(value2),
+                }
+            }
+            (Eval::Numeric(_), Eval::MateIn(color, _)) => match color {
+                Color::Black => Ordering::Greater,
+                Color::White => Ordering::Less,
+            },
+            (Eval::MateIn(_, _), Eval::Numeric(_)) => other.cmp(self).reverse(),
+        }
+    }
+}
+
+impl PartialOrd for Eval {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+//the terms of the static eval, each already weighted and signed from white's point of view
+#[derive(Debug, Clone)]
+pub struct EvalBreakdown {
+    pub material: f32,
+    pub square_control: f32,
+    pub check: f32,
+    pub passed_pawns: f32,
+    pub center_control: f32,
+    pub knight_outposts: f32,
+    pub castled_king: f32,
+    pub tempo: f32,
+    pub mobility: f32,
+    pub trapped_pieces: f32,
+    pub pawn_majority: f32,
+    pub king_tropism: f32,
+    pub lost_castling: f32,
+    pub rook_behind_passer: f32,
+    //what scaling toward a draw takes off the rest in opposite-colored bishop endgames
+    pub opposite_bishops: f32,
+    //the same for a recognized fortress, taking off nearly all of it
+    pub fortress: f32,
+}
+
+impl EvalBreakdown {
+    pub fn total(&self) -> f32 {
+        self.material
+            + self.square_control
+            + self.check
+            + self.passed_pawns
+            + self.center_control
+            + self.knight_outposts
+            + self.castled_king
+            + self.tempo
+            + self.mobility
+            + self.trapped_pieces
+            + self.pawn_majority
+            + self.king_tropism
+            + self.lost_castling
+            + self.rook_behind_passer
+            + self.opposite_bishops
+            + self.fortress
+    }
+
+    //every term with its name, in the order they're printed
+    pub fn terms(&self) -> [(&'static str, f32); 16] {
+        [
+            ("material", self.material),
+            ("square control", self.square_control),
+            ("check", self.check),
+            ("passed pawns", self.passed_pawns),
+            ("center control", self.center_control),
+            ("knight outposts", self.knight_outposts),
+            ("castled king", self.castled_king),
+            ("tempo", self.tempo),
+            ("mobility", self.mobility),
+            ("trapped pieces", self.trapped_pieces),
+            ("pawn majority", self.pawn_majority),
+            ("king tropism", self.king_tropism),
+            ("lost castling", self.lost_castling),
+            ("rook behind passer", self.rook_behind_passer),
+            ("opposite bishops", self.opposite_bishops),
+            ("fortress", self.fortress),
+        ]
+    }
+}
+
+impl fmt::Display for EvalBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in self.terms() {
+            writeln!(f, "{:<20}{:+.2}", format!("{}:", name), value)?;
+        }
+        write!(f, "{:<20}{:+.2}", "total:", self.total())
+    }
+}
+
+//the tactics open to the side to move, as Engine::threats finds them
+#[derive(Debug, Clone)]
+pub struct Threats {
+    //captures of enemy pieces nothing defends
+    pub hanging: Vec<ChessMove>,
+    //captures that come out ahead once the exchange is played out, with what they win,
+    //the best first
+    pub winning_captures: Vec<(ChessMove, f32)>,
+    pub checks: Vec<ChessMove>,
+}
+
+//how much work the static evaluation of a leaf puts in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvalProfile {
+    //every term of EvalBreakdown
+    #[default]
+    Full,
+    //material and piece-square tables only, for very fast play. skips the move generation
+    //and attack walks the other terms need
+    Fast,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Adjudication {
+    Resign(Color),
+    Draw,
+    ClaimDraw(DrawReason),
+    //a long stretch of a balanced position going nowhere
+    AgreedDraw,
+}
+
+impl Adjudication {
+    //why the game ended, for the PGN comment before the result
+    pub fn description(&self) -> &'static str {
+        match self {
+            Adjudication::Resign(Color::White) => "White resigns",
+            Adjudication::Resign(Color::Black) => "Black resigns",
+            Adjudication::Draw => "draw claimed",
+            Adjudication::ClaimDraw(reason) => reason.description(),
+            Adjudication::AgreedDraw => "draw agreed, balanced with no progress",
+        }
+    }
+
+    //the PGN result token
+    pub fn result(&self) -> &'static str {
+        match self {
+            Adjudication::Resign(Color::White) => "0-1",
+            Adjudication::Resign(Color::Black) => "1-0",
+            Adjudication::Draw | Adjudication::ClaimDraw(_) | Adjudication::AgreedDraw => "1/2-1/2",
+        }
+    }
+}
+
+//decides when the engine gives up or settles for a draw in games it plays itself
+#[derive(Debug, Clone)]
+pub struct Adjudicator {
+    settings: MinimaxSettings,
+    //consecutive evals below the resign threshold, for white and black
+    losing_streaks: [usize; 2],
+    //consecutive evals within agreement_draw_band, whoever they were for
+    balanced_streak: usize,
+}
+
+impl Adjudicator {
+    pub fn new(settings: MinimaxSettings) -> Adjudicator {
+        Adjudicator {
+            settings,
+            losing_streaks: [0, 0],
+            balanced_streak: 0,
+        }
+    }
+
+    //forgets the streaks of the previous game
+    pub fn reset(&mut self) {
+        self.losing_streaks = [0, 0];
+        self.balanced_streak = 0;
+    }
+
+    //call once per engine move with the eval it searched, before the move is played
+    pub fn adjudicate(&mut self, engine: &Engine, eval: &Eval) -> Option<Adjudication> {
+        let side = engine.get_board().get_turn();
+        let score = match eval {
+            Eval::Numeric(value) => match side {
+                Color::White => value.into_inner(),
+                Color::Black => -value.into_inner(),
+            },
+            Eval::MateIn(color, _) if *color == side => f32::INFINITY,
+            Eval::MateIn(_, _) => f32::NEG_INFINITY,
+        };
+        let streak = &mut self.losing_streaks[match side {
+            Color::White => 0,
+            Color::Black => 1,
+        }];
+        *streak = match score < self.settings.resign_threshold {
+            true => *streak + 1,
+            false => 0,
+        };
+        if *streak >= self.settings.resign_moves {
+            return Some(Adjudication::Resign(side));
+        }
+        self.balanced_streak = match score.abs() <= self.settings.agreement_draw_band {
+            true => self.balanced_streak + 1,
+            false => 0,
+        };
+        if let Some(reason) = engine.can_claim_draw() {
+            if score <= self.settings.claim_draw_threshold {
+                return Some(Adjudication::ClaimDraw(reason));
+            }
+        }
+        if score.abs() <= self.settings.draw_threshold && engine.draw_rule_imminent() {
+            return Some(Adjudication::Draw);
+        }
+        let moves = self.settings.agreement_draw_moves;
+        if moves > 0 && self.balanced_streak >= moves && engine.halfmove_clock() >= moves {
+            return Some(Adjudication::AgreedDraw);
+        }
+        None
+    }
+}
+
+//centipawns for a white piece on each square, rank 1 first, as in the widely used
+//simplified evaluation function. black reads them with the ranks flipped
+const PAWN_SQUARES: [[i32; BOARD_SIZE]; BOARD_SIZE] = [
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [5, 10, 10, -20, -20, 10, 10, 5],
+    [5, -5, -10, 0, 0, -10, -5, 5],
+    [0, 0, 0, 20, 20, 0, 0, 0],
+    [5, 5, 10, 25, 25, 10, 5, 5],
+    [10, 10, 20, 30, 30, 20, 10, 10],
+    [50, 50, 50, 50, 50, 50, 50, 50],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+];
+const KNIGHT_SQUARES: [[i32; BOARD_SIZE]; BOARD_SIZE] = [
+    [-50, -40, -30, -30, -30, -30, -40, -50],
+    [-40, -20, 0, 5, 5, 0, -20, -40],
+    [-30, 5, 10, 15, 15, 10, 5, -30],
+    [-30, 0, 15, 20, 20, 15, 0, -30],
+    [-30, 5, 15, 20, 20, 15, 5, -30],
+    [-30, 0, 10, 15, 15, 10, 0, -30],
+    [-40, -20, 0, 0, 0, 0, -20, -40],
+    [-50, -40, -30, -30, -30, -30, -40, -50],
+];
+const BISHOP_SQUARES: [[i32; BOARD_SIZE]; BOARD_SIZE] = [
+    [-20, -10, -10, -10, -10, -10, -10, -20],
+    [-10, 5, 0, 0, 0, 0, 5, -10],
+    [-10, 10, 10, 10, 10, 10, 10, -10],
+    [-10, 0, 10, 10, 10, 10, 0, -10],
+    [-10, 5, 5, 10, 10, 5, 5, -10],
+    [-10, 0, 5, 10, 10, 5, 0, -10],
+    [-10, 0, 0, 0, 0, 0, 0, -10],
+    [-20, -10, -10, -10, -10, -10, -10, -20],
+];
+const ROOK_SQUARES: [[i32; BOARD_SIZE]; BOARD_SIZE] = [
+    [0, 0, 0, 5, 5, 0, 0, 0],
+    [-5, 0, 0, 0, 0, 0, 0, -5],
+    [-5, 0, 0, 0, 0, 0, 0, -5],
+    [-5, 0, 0, 0, 0, 0, 0, -5],
+    [-5, 0, 0, 0, 0, 0, 0, -5],
+    [-5, 0, 0, 0, 0, 0, 0, -5],
+    [5, 10, 10, 10, 10, 10, 10, 5],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+];
+const QUEEN_SQUARES: [[i32; BOARD_SIZE]; BOARD_SIZE] = [
+    [-20, -10, -10, -5, -5, -10, -10, -20],
+    [-10, 0, 5, 0, 0, 0, 0, -10],
+    [-10, 5, 5, 5, 5, 5, 0, -10],
+    [0, 0, 5, 5, 5, 5, 0, -5],
+    [-5, 0, 5, 5, 5, 5, 0, -5],
+    [-10, 0, 5, 5, 5, 5, 0, -10],
+    [-10, 0, 0, 0, 0, 0, 0, -10],
+    [-20, -10, -10, -5, -5, -10, -10, -20],
+];
+const KING_SQUARES: [[i32; BOARD_SIZE]; BOARD_SIZE] = [
+    [20, 30, 10, 0, 0, 10, 30, 20],
+    [20, 20, 0, 0, 0, 0, 20, 20],
+    [-10, -20, -20, -20, -20, -20, -20, -10],
+    [-20, -30, -30, -40, -40, -30, -30, -20],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+];
+
+//the piece-square bonus of every piece on the board, in pawns from white's point of view
+fn piece_square_total(board: &Board) -> f32 {
+    let mut centipawns = 0;
+    for i in 0..BOARD_SIZE {
+        for j in 0..BOARD_SIZE {
+            let piece = match board.get_piece(i, j).unwrap() {
+                Some(piece) => piece,
+                None => continue,
+            };
+            let table = match piece.kind {
+                PieceKind::Pawn => &PAWN_SQUARES,
+                PieceKind::Knight => &KNIGHT_SQUARES,
+                PieceKind::Bishop => &BISHOP_SQUARES,
+                PieceKind::Rook => &ROOK_SQUARES,
+                PieceKind::Queen => &QUEEN_SQUARES,
+                PieceKind::King => &KING_SQUARES,
+            };
+            centipawns += match piece.color {
+                Color::White => table[i][j],
+                Color::Black => -table[BOARD_SIZE - 1 - i][j],
+            };
+        }
+    }
+    centipawns as f32 / 100.0
+}
+
+//how far into game_phase opposite-colored bishops start to make the game drawish. past
+//the two bishops this leaves room for a rook each and nothing more
+const OCB_PHASE: f32 = 0.75;
+
+fn home_row(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => BOARD_SIZE - 1,
+    }
+}
+
+//whether a knight, bishop or queen stands where it started the game
+fn on_start_square(piece: &Piece, row: usize, col: usize) -> bool {
+    row == home_row(piece.color)
+        && match piece.kind {
+            PieceKind::Knight => col == 1 || col == BOARD_SIZE - 2,
+            PieceKind::Bishop => col == 2 || col == BOARD_SIZE - 3,
+            PieceKind::Queen => col == 3,
+            _ => false,
+        }
+}
+
+impl Engine {
+    pub(super) fn evaluate_interest(
+        interest_eval_weights: &InterestEvaluationWeights,
+        chess_move: &ChessMove,
+        starting_board: &Board,
+        ending_board: &Board,
+        starting_moves: &BoardMoves,
+        ending_moves: &BoardMoves,
+    ) -> Result<NotNan<f32>, BoardError> {
+        use ChessMove::*;
+        //a mating move is expanded before anything else
+        if is_mate(ending_board) {
+            return Ok(NotNan::new(f32::INFINITY).unwrap());
+        }
+        //temporary
+        let res = match chess_move {
+            Normal(normal_move) => Engine::evaluate_normal_move_interest(
+                &interest_eval_weights,
+                normal_move,
+                starting_board,
+                ending_board,
+                starting_moves,
+                ending_moves,
+            )?,
+            Promotion(normal_move, piece_kind) => {
+                Engine::evaluate_normal_move_interest(
+                    &interest_eval_weights,
+                    normal_move,
+                    starting_board,
+                    ending_board,
+                    starting_moves,
+                    ending_moves,
+                )? + piece_worth_king_inf(*piece_kind)
+                    //an underpromotion with check is usually a fork or a mate the queen
+                    //can't deliver, so don't let its lower material value bury it
+                    + match (piece_kind, ending_board.is_check.is_some()) {
+                        (PieceKind::Queen, _) | (_, false) => 0.0,
+                        _ => interest_eval_weights.checking_underpromotion_bonus,
+                    }
+            }
+            Castling(_) => match find_king(ending_board, starting_board.get_turn()) {
+                Some((_, col)) if col >= BOARD_SIZE / 2 => {
+                    NotNan::new(interest_eval_weights.kingside_castling_bonus).unwrap()
+                }
+                _ => NotNan::new(interest_eval_weights.queenside_castling_bonus).unwrap(),
+            },
+        };
+
+        Ok(res)
+    }
+
+    fn evaluate_normal_move_interest(
+        interest_eval_weights: &InterestEvaluationWeights,
+        normal_move: &NormalChessMove,
+        starting_board: &Board,
+        ending_board: &Board,
+        starting_moves: &BoardMoves,
+        ending_moves: &BoardMoves,
+    ) -> Result<NotNan<f32>, BoardError> {
+        Ok(
+            match starting_board.get_piece(normal_move.initial_row, normal_move.initial_col)? {
+                None => return Err(BoardError::NoPieceError),
+                Some(Piece {
+                    kind: PieceKind::Pawn,
+                    color,
+                }) => {
+                    let past = is_past_pawn(
+                        normal_move.destination_row,
+                        normal_move.destination_col,
+                        ending_board,
+                        color,
+                    );
+                    let value = match past {
+                        true => NotNan::new(
+                            normal_move.destination_row as f32
+                                * interest_eval_weights.past_pawn_push_weight,
+                        )
+                        .unwrap(),
+                        false => NotNan::new(0.0).unwrap(),
+                    } + match normal_move.initial_row
+                        == match color {
+                            Color::Black => 6,
+                            Color::White => 1,
+                        } {
+                        true => interest_eval_weights.home_row_pawn_weight,
+                        false => 0.0,
+                    };
+                    value
+                }
+                Some(Piece {
+                    kind: PieceKind::Bishop | PieceKind::Knight,
+                    color: _,
+                }) => NotNan::new(interest_eval_weights.minor_piece_moving_bouns).unwrap(),
+                Some(Piece {
+                    kind: PieceKind::Rook,
+                    color: _,
+                }) => NotNan::new(interest_eval_weights.rook_moving_bonus).unwrap(),
+                Some(Piece {
+                    kind: PieceKind::Queen,
+                    color: _,
+                }) => NotNan::new(interest_eval_weights.queen_moving_bonus).unwrap(),
+                Some(Piece {
+                    kind: PieceKind::King,
+                    color: _,
+                }) => NotNan::new(interest_eval_weights.king_moving_bonus).unwrap(),
+            } + match starting_board
+                .get_piece(normal_move.destination_row, normal_move.destination_col)?
+            {
+                Some(piece) => piece_worth_king_inf(piece.kind),
+                None => NotNan::new(0.0).unwrap(),
+            } + match ending_board.is_check.is_some() {
+                true => match ending_board.is_checkmate.is_some() {
+                    true => INFINITY,
+                    false => interest_eval_weights.check_weight,
+                },
+                false => 0.0,
+            } + match starting_board.is_check.is_some() {
+                true => interest_eval_weights.check_evasion_weight,
+                false => 0.0,
+            } + match starting_board
+                .get_piece(normal_move.destination_row, normal_move.destination_col)
+            {
+                Ok(Some(piece)) => piece_worth_king_inf(piece.kind),
+                Ok(None) => NotNan::new(0.0).unwrap(),
+                Err(_) => panic!(),
+            } * interest_eval_weights.capture_weight
+                + ((Engine::controlling_squares(
+                    ending_board,
+                    ending_moves,
+                    starting_board.get_turn(),
+                ) - Engine::controlling_squares(
+                    starting_board,
+                    starting_moves,
+                    starting_board.get_turn(),
+                )) as f32)
+                    * interest_eval_weights.square_control_weight
+                + match interest_eval_weights.attack_by_defenders {
+                    true => Engine::evaluate_threats(ending_board),
+                    false => Engine::evaluate_total_attack(ending_board),
+                } * interest_eval_weights.attack_weight
+                - Engine::repeat_move_penalty(interest_eval_weights, normal_move, starting_board),
+        )
+    }
+
+    //a knight, bishop or queen off its start square is taken to have moved already,
+    //which the boards in the tree show even where the game's move history doesn't reach
+    fn repeat_move_penalty(
+        interest_eval_weights: &InterestEvaluationWeights,
+        normal_move: &NormalChessMove,
+        board: &Board,
+    ) -> f32 {
+        let (row, col) = (normal_move.initial_row, normal_move.initial_col);
+        let color = match board.get_piece(row, col).unwrap() {
+            Some(piece)
+                if matches!(
+                    piece.kind,
+                    PieceKind::Knight | PieceKind::Bishop | PieceKind::Queen
+                ) && !on_start_square(&piece, row, col) =>
+            {
+                piece.color
+            }
+            _ => return 0.0,
+        };
+        let undeveloped = (0..BOARD_SIZE).any(|col| match board.get_piece(home_row(color), col) {
+            Ok(Some(piece)) => {
+                matches!(piece.kind, PieceKind::Knight | PieceKind::Bishop)
+                    && piece.color == color
+                    && on_start_square(&piece, home_row(color), col)
+            }
+            _ => false,
+        });
+        match undeveloped {
+            true => interest_eval_weights.repeat_move_penalty,
+            false => 0.0,
+        }
+    }
+
+    //the worth of every piece of the side to move, once for each enemy piece attacking it
+    fn evaluate_total_attack(board: &Board) -> NotNan<f32> {
+        let defending = board.get_turn();
+        let mut sum = NotNan::new(0.0).unwrap();
+        for i in 0..BOARD_SIZE {
+            for j in 0..BOARD_SIZE {
+                let target = match board.get_piece(i, j).unwrap() {
+                    Some(piece) if piece.color == defending => piece,
+                    _ => continue,
+                };
+                let attackers = attackers_of(board, i, j, defending.opposite()).len();
+                sum += piece_worth_king_zero(target.kind) * attackers as f32;
+            }
+        }
+        sum
+    }
+
+    //evaluate_total_attack with each attacked piece counted by how well it's covered: in
+    //full when nothing defends it, half when it has fewer defenders than attackers, and a
+    //quarter when it's defended as often as it's attacked
+    fn evaluate_threats(board: &Board) -> NotNan<f32> {
+        let defending = board.get_turn();
+        let mut sum = NotNan::new(0.0).unwrap();
+        for i in 0..BOARD_SIZE {
+            for j in 0..BOARD_SIZE {
+                let target = match board.get_piece(i, j).unwrap() {
+                    Some(piece) if piece.color == defending => piece,
+                    _ => continue,
+                };
+                let attackers = attackers_of(board, i, j, defending.opposite()).len();
+                if attackers == 0 {
+                    continue;
+                }
+                let coverage = match defenders(board, (i, j)) {
+                    0 => 1.0,
+                    defenders if defenders < attackers => 0.5,
+                    _ => 0.25,
+                };
+                sum += piece_worth_king_zero(target.kind) * coverage * attackers as f32;
+            }
+        }
+        sum
+    }
+
+    fn controlling_squares(board: &Board, moves: &BoardMoves, color: Color) -> i32 {
+        let mut squares = [[false; BOARD_SIZE]; BOARD_SIZE];
+        for i in 0..BOARD_SIZE {
+            for j in 0..BOARD_SIZE {
+                match board.get_piece(i, j).expect("Cant error always in bounds") {
+                    Some(piece) if piece.color == color => {}
+                    _ => continue,
+                }
+                for (row, col) in moves.squares_from(i, j) {
+                    squares[row][col] = true;
+                }
+            }
+        }
+        let count = squares.iter().flatten().filter(|&&x| x).count();
+        count as i32
+    }
+
+    //squares attacked in the center, d4/e4/d5/e5 counting fully and the ring around them half.
+    //pawns count the squares they capture on rather than the ones they push to
+    fn center_control(board: &Board, moves: &BoardMoves, color: Color) -> f32 {
+        let mut squares = [[false; BOARD_SIZE]; BOARD_SIZE];
+        for i in 0..BOARD_SIZE {
+            for j in 0..BOARD_SIZE {
+                let piece = match board.get_piece(i, j).unwrap() {
+                    Some(piece) if piece.color == color => piece,
+                    _ => continue,
+                };
+                if piece.kind == PieceKind::Pawn {
+                    for (row, col) in pawn_attacks(i, j, color) {
+                        squares[row][col] = true;
+                    }
+                    continue;
+                }
+                for (row, col) in moves.squares_from(i, j) {
+                    squares[row][col] = true;
+                }
+            }
+        }
+        let mut sum = 0.0;
+        for (i, row) in squares.iter().enumerate() {
+            for (j, controlled) in row.iter().enumerate() {
+                if *controlled {
+                    sum += match (i, j) {
+                        (3..=4, 3..=4) => 1.0,
+                        (2..=5, 2..=5) => 0.5,
+                        _ => 0.0,
+                    };
+                }
+            }
+        }
+        sum
+    }
+
+    //knights on the opponent's half that no enemy pawn can ever challenge.
+    //each counts 0.5, or 1.0 when a friendly pawn defends it
+    fn knight_outposts(board: &Board, color: Color) -> f32 {
+        let knight = Piece {
+            kind: PieceKind::Knight,
+            color,
+        };
+        let friendly_pawn = Piece {
+            kind: PieceKind::Pawn,
+            color,
+        };
+        let enemy_pawn = Piece {
+            kind: PieceKind::Pawn,
+            color: color.opposite(),
+        };
+        let mut sum = 0.0;
+        for i in 0..BOARD_SIZE {
+            for j in 0..BOARD_SIZE {
+                if board.get_piece(i, j).unwrap() != Some(knight.clone()) {
+                    continue;
+                }
+                let (enemy_half, rows_ahead) = match color {
+                    Color::White => (i >= BOARD_SIZE / 2, i + 1..BOARD_SIZE),
+                    Color::Black => (i < BOARD_SIZE / 2, 0..i),
+                };
+                if !enemy_half {
+                    continue;
+                }
+                let challengeable = rows_ahead.into_iter().any(|row| {
+                    [j.checked_sub(1), Some(j + 1)]
+                        .into_iter()
+                        .flatten()
+                        .filter(|&col| col < BOARD_SIZE)
+                        .any(|col| board.get_piece(row, col).unwrap() == Some(enemy_pawn.clone()))
+                });
+                if challengeable {
+                    continue;
+                }
+                //a friendly pawn defends the square from where an enemy pawn on it would capture
+                let defended =
+                    pawn_attacks(i, j, color.opposite())
+                        .into_iter()
+                        .any(|(row, col)| {
+                            board.get_piece(row, col).unwrap() == Some(friendly_pawn.clone())
+                        });
+                sum += match defended {
+                    true => 1.0,
+                    false => 0.5,
+                };
+            }
+        }
+        sum
+    }
+
+    //moves available to the pieces of each color (pawns and king aside), and how many of
+    //those pieces are trapped: deep in the opponent's half with at most one square to go
+    //that no cheaper enemy piece covers. white's first, then black's, from a single walk
+    //over the board and one over each piece's moves
+    fn mobility_and_trapped(board: &Board, moves: &BoardMoves) -> [(i32, i32); 2] {
+        let side = |color| match color {
+            Color::White => 0,
+            Color::Black => 1,
+        };
+        //the value of the cheapest piece of each color covering each square
+        let mut cheapest_attacker = [[[f32::INFINITY; BOARD_SIZE]; BOARD_SIZE]; 2];
+        let mut pieces = Vec::new();
+        for i in 0..BOARD_SIZE {
+            for j in 0..BOARD_SIZE {
+                let piece = match board.get_piece(i, j).unwrap() {
+                    Some(piece) => piece,
+                    None => continue,
+                };
+                if piece.kind != PieceKind::Pawn && piece.kind != PieceKind::King {
+                    pieces.push((i, j, piece.clone()));
+                }
+                let value = *piece_worth_king_zero(piece.kind);
+                let attacked = match piece.kind {
+                    PieceKind::Pawn => pawn_attacks(i, j, piece.color),
+                    _ => moves.squares_from(i, j).collect(),
+                };
+                let covered = &mut cheapest_attacker[side(piece.color)];
+                for (row, col) in attacked {
+                    covered[row][col] = covered[row][col].min(value);
+                }
+            }
+        }
+
+        let mut counts = [(0, 0); 2];
+        for (i, j, piece) in pieces {
+            let enemy_cover = &cheapest_attacker[side(piece.color.opposite())];
+            let value = *piece_worth_king_zero(piece.kind);
+            let mut mobility = 0;
+            let mut safe_squares = 0;
+            for (row, col) in moves.squares_from(i, j) {
+                mobility += 1;
+                if enemy_cover[row][col] >= value {
+                    safe_squares += 1;
+                }
+            }
+            let in_enemy_half = match piece.color {
+                Color::White => i >= BOARD_SIZE / 2,
+                Color::Black => i < BOARD_SIZE / 2,
+            };
+            let (total_mobility, trapped) = &mut counts[side(piece.color)];
+            *total_mobility += mobility;
+            if in_enemy_half && safe_squares <= 1 {
+                *trapped += 1;
+            }
+        }
+        counts
+    }
+
+    //how closely the pieces of color crowd the enemy king: each piece adds its
+    //attacking strength over its distance (in king moves) to that king
+    fn king_tropism(board: &Board, color: Color) -> f32 {
+        let (king_row, king_col) = match find_king(board, color.opposite()) {
+            Some(square) => square,
+            None => return 0.0,
+        };
+        let mut sum = 0.0;
+        for i in 0..BOARD_SIZE {
+            for j in 0..BOARD_SIZE {
+                let piece = match board.get_piece(i, j).unwrap() {
+                    Some(piece) if piece.color == color => piece,
+                    _ => continue,
+                };
+                let strength = match piece.kind {
+                    PieceKind::Queen => 2.0,
+                    PieceKind::Knight | PieceKind::Rook => 1.0,
+                    PieceKind::Bishop => 0.5,
+                    PieceKind::Pawn | PieceKind::King => continue,
+                };
+                let distance = i.abs_diff(king_row).max(j.abs_diff(king_col));
+                sum += strength / distance as f32;
+            }
+        }
+        sum
+    }
+
+    //whether each side is down to one bishop, the two on squares of different colors,
+    //with no knights or queens and little else left besides pawns
+    fn opposite_colored_bishops(board: &Board) -> bool {
+        if game_phase(board) < OCB_PHASE {
+            return false;
+        }
+        let mut bishop_squares = Vec::new();
+        for i in 0..BOARD_SIZE {
+            for j in 0..BOARD_SIZE {
+                match board.get_piece(i, j).unwrap() {
+                    Some(piece) if piece.kind == PieceKind::Bishop => {
+                        bishop_squares.push((piece.color, (i + j) % 2))
+                    }
+                    Some(piece) if matches!(piece.kind, PieceKind::Knight | PieceKind::Queen) => {
+                        return false
+                    }
+                    _ => {}
+                }
+            }
+        }
+        match bishop_squares[..] {
+            [(color, square), (other_color, other_square)] => {
+                color != other_color && square != other_square
+            }
+            _ => false,
+        }
+    }
+
+    //+1 for every flank (a-c or f-h files) where white has more pawns, -1 where black does
+    fn flank_majorities(board: &Board) -> i32 {
+        let mut res = 0;
+        for files in [0..3, BOARD_SIZE - 3..BOARD_SIZE] {
+            let mut balance: i32 = 0;
+            for i in 0..BOARD_SIZE {
+                for j in files.clone() {
+                    match board.get_piece(i, j).unwrap() {
+                        Some(Piece {
+                            kind: PieceKind::Pawn,
+                            color: Color::White,
+                        }) => balance += 1,
+                        Some(Piece {
+                            kind: PieceKind::Pawn,
+                            color: Color::Black,
+                        }) => balance -= 1,
+                        _ => {}
+                    }
+                }
+            }
+            res += balance.signum();
+        }
+        res
+    }
+
+    //the king's column when it sits on a wing of its home rank with a rook on its center
+    //side, which is where castling leaves it
+    fn castled_king_col(board: &Board, color: Color) -> Option<usize> {
+        let home_row = match color {
+            Color::White => 0,
+            Color::Black => BOARD_SIZE - 1,
+        };
+        let king_col = match find_king(board, color) {
+            Some((row, col)) if row == home_row => col,
+            _ => return None,
+        };
+        let rook_cols = match king_col {
+            6 | 7 => 3..king_col,
+            0..=2 => king_col + 1..5,
+            _ => return None,
+        };
+        let rook = Piece {
+            kind: PieceKind::Rook,
+            color,
+        };
+        rook_cols
+            .into_iter()
+            .any(|col| board.get_piece(home_row, col).unwrap() == Some(rook.clone()))
+            .then_some(king_col)
+    }
+
+    //the board keeps no castling rights, so like fen_with_turn this reads them off the
+    //king and rooks still being home. a king that castled hasn't lost anything
+    fn lost_castling(board: &Board, color: Color) -> bool {
+        let home_row = match color {
+            Color::White => 0,
+            Color::Black => BOARD_SIZE - 1,
+        };
+        let is = |col: usize, kind: PieceKind| {
+            board.get_piece(home_row, col).unwrap() == Some(Piece { kind, color })
+        };
+        let can_castle = is(4, PieceKind::King)
+            && (is(0, PieceKind::Rook) || is(BOARD_SIZE - 1, PieceKind::Rook));
+        !can_castle && Engine::castled_king_col(board, color).is_none()
+    }
+
+    //1.0 for a castled king (see castled_king_col) with an intact pawn shield;
+    //scaled down per missing shield pawn, 0.0 if not castled
+    fn castled_king_shelter(board: &Board, color: Color) -> f32 {
+        let shield_rows = match color {
+            Color::White => [1, 2],
+            Color::Black => [BOARD_SIZE - 2, BOARD_SIZE - 3],
+        };
+        let king_col = match Engine::castled_king_col(board, color) {
+            Some(col) => col,
+            None => return 0.0,
+        };
+        let pawn = Piece {
+            kind: PieceKind::Pawn,
+            color,
+        };
+        let shield_cols = king_col.saturating_sub(1)..(king_col + 2).min(BOARD_SIZE);
+        let shield_size = shield_cols.len();
+        let shield_pawns = shield_cols
+            .filter(|&col| {
+                shield_rows
+                    .iter()
+                    .any(|&row| board.get_piece(row, col).unwrap() == Some(pawn.clone()))
+            })
+            .count();
+        shield_pawns as f32 / shield_size as f32
+    }
+
+    //the color of the rook that is the first piece behind the pawn on (row, col), looking
+    //back toward the pawn's own side
+    fn rook_behind(board: &Board, row: usize, col: usize) -> Option<Color> {
+        let pawn_color = board.get_piece(row, col).unwrap()?.color;
+        let behind: Vec<usize> = match pawn_color {
+            Color::White => (0..row).rev().collect(),
+            Color::Black => (row + 1..BOARD_SIZE).collect(),
+        };
+        let piece = behind
+            .into_iter()
+            .find_map(|i| board.get_piece(i, col).unwrap())?;
+        match piece.kind {
+            PieceKind::Rook => Some(piece.color),
+            _ => None,
+        }
+    }
+
+    //a position whose static eval can be taken at face value: the side to move isn't in
+    //check and has no legal capture that comes out at least even (SEE >= 0) once the
+    //exchange is played out
+    pub fn is_quiet(board: &Board) -> bool {
+        board.is_check.is_none()
+            && generate_captures(board)
+                .iter()
+                .all(|chess_move| static_exchange(board, chess_move) < 0.0)
+    }
+
+    //what the side to move can go for right now: hanging enemy pieces, captures the static
+    //exchange says win material, and checks. read off the board alone, nothing is searched
+    pub fn threats(&self) -> Threats {
+        let board = self.get_board();
+        let captures = generate_captures(board);
+        let hanging = captures
+            .iter()
+            .filter(|chess_move| match chess_move {
+                ChessMove::Normal(normal_move) | ChessMove::Promotion(normal_move, _) => {
+                    let square = (normal_move.destination_row, normal_move.destination_col);
+                    //an empty destination is en passant or a quiet promotion
+                    board.get_piece(square.0, square.1).unwrap().is_some()
+                        && defenders(board, square) == 0
+                }
+                ChessMove::Castling(_) => false,
+            })
+            .copied()
+            .collect();
+        let mut winning_captures: Vec<(ChessMove, f32)> = captures
+            .into_iter()
+            .map(|chess_move| (chess_move, static_exchange(board, &chess_move)))
+            .filter(|(_, gain)| *gain > 0.0)
+            .collect();
+        winning_captures.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        Threats {
+            hanging,
+            winning_captures,
+            checks: generate_checks(board),
+        }
+    }
+
+    //the static eval split into its terms, each signed from white's point of view
+    pub fn evaluation_breakdown(&self, board: &Board) -> EvalBreakdown {
+        Engine::breakdown_with(
+            &self.static_eval_weights,
+            board,
+            &Material::of(board),
+            &BoardMoves::of(board),
+        )
+    }
+
+    fn breakdown_with(
+        weights: &StaticEvaluationWeights,
+        board: &Board,
+        material: &Material,
+        moves: &BoardMoves,
+    ) -> EvalBreakdown {
+        debug_assert_eq!(*material, Material::of(board));
+        let mut breakdown = EvalBreakdown {
+            material: material.pawns as f32 + material.pieces as f32 * weights.value_weight,
+            square_control: (Engine::controlling_squares(board, moves, Color::White)
+                - Engine::controlling_squares(board, moves, Color::Black))
+                as f32
+                * weights.square_control_weight,
+            check: match board.is_check {
+                Some(Color::Black) => weights.giving_check_weight + weights.in_check_weight,
+                None => 0.0,
+                Some(Color::White) => -(weights.giving_check_weight + weights.in_check_weight),
+            },
+            passed_pawns: 0.0,
+            center_control: (Engine::center_control(board, moves, Color::White)
+                - Engine::center_control(board, moves, Color::Black))
+                * weights.center_control_weight,
+            knight_outposts: (Engine::knight_outposts(board, Color::White)
+                - Engine::knight_outposts(board, Color::Black))
+                * weights.knight_outpost_weight,
+            castled_king: (Engine::castled_king_shelter(board, Color::White)
+                - Engine::castled_king_shelter(board, Color::Black))
+                * weights.castled_bonus,
+            tempo: match board.get_turn() {
+                Color::White => weights.tempo_weight,
+                Color::Black => -weights.tempo_weight,
+            },
+            mobility: 0.0,
+            trapped_pieces: 0.0,
+            pawn_majority: Engine::flank_majorities(board) as f32
+                * weights.pawn_majority_weight
+                * game_phase(board),
+            king_tropism: (Engine::king_tropism(board, Color::White)
+                - Engine::king_tropism(board, Color::Black))
+                * weights.king_tropism_weight,
+            lost_castling: (Engine::lost_castling(board, Color::Black) as i32
+                - Engine::lost_castling(board, Color::White) as i32)
+                as f32
+                * weights.lost_castling_penalty
+                * (1.0 - game_phase(board)),
+            rook_behind_passer: 0.0,
+            opposite_bishops: 0.0,
+            fortress: 0.0,
+        };
+        let [(white_mobility, white_trapped), (black_mobility, black_trapped)] =
+            Engine::mobility_and_trapped(board, moves);
+        breakdown.mobility = (white_mobility - black_mobility) as f32 * weights.mobility_weight;
+        breakdown.trapped_pieces =
+            -((white_trapped - black_trapped) as f32) * weights.trapped_piece_weight;
+        for i in 0..BOARD_SIZE {
+            for j in 0..BOARD_SIZE {
+                let piece = match board.get_piece(i, j).unwrap() {
+                    Some(piece) if piece.kind == PieceKind::Pawn => piece,
+                    _ => continue,
+                };
+                if is_past_pawn(i, j, board, piece.color) {
+                    breakdown.passed_pawns += match piece.color {
+                        Color::Black => -((BOARD_SIZE - 1 - i) as f32),
+                        Color::White => i as f32,
+                    } * weights.past_pawn_weight;
+                    breakdown.rook_behind_passer += match Engine::rook_behind(board, i, j) {
+                        Some(Color::White) => weights.rook_behind_passer_weight,
+                        Some(Color::Black) => -weights.rook_behind_passer_weight,
+                        None => 0.0,
+                    } * game_phase(board);
+                }
+            }
+        }
+        if Engine::opposite_colored_bishops(board) {
+            breakdown.opposite_bishops = -breakdown.total() * (1.0 - weights.ocb_draw_factor);
+        }
+        if is_fortress(board) {
+            breakdown.fortress = -breakdown.total() * (1.0 - FORTRESS_SCALE);
+        }
+        breakdown
+    }
+
+    pub(super) fn static_evaluation(&self, board_state: &Board) -> Eval {
+        let material = Material::of(board_state);
+        match self.eval_profile {
+            EvalProfile::Full => {
+                self.static_evaluation_with(board_state, &material, &BoardMoves::of(board_state))
+            }
+            EvalProfile::Fast => self.static_evaluation_fast(board_state, &material),
+        }
+    }
+
+    //the static evaluation of a node in the tree, by the engine's profile. the fast one
+    //doesn't touch the node's moves, so they aren't generated for it
+    pub(super) fn static_evaluation_of_tree(&self, tree: &MoveTree) -> Eval {
+        match self.eval_profile {
+            EvalProfile::Full => {
+                self.static_evaluation_with(&tree.board_state, &tree.material, tree.board_moves())
+            }
+            EvalProfile::Fast => self.static_evaluation_fast(&tree.board_state, &tree.material),
+        }
+    }
+
+    //material and piece-square tables, with none of the terms that need the moves or the
+    //attacks worked out. whether the game is over is taken from the board's own flags
+    pub(super) fn static_evaluation_fast(&self, board_state: &Board, material: &Material) -> Eval {
+        match board_state.is_checkmate {
+            None => {}
+            Some(GameEnd::Mated(Color::White)) => return Eval::MateIn(Color::Black, 0),
+            Some(GameEnd::Mated(Color::Black)) => return Eval::MateIn(Color::White, 0),
+            Some(GameEnd::StaleMate) => return Eval::Numeric(NotNan::new(0.0).unwrap()),
+        };
+        if let Some(eval) = self.tablebase_eval(board_state) {
+            return eval;
+        }
+        let weights = &self.static_eval_weights;
+        Eval::Numeric(
+            NotNan::new(
+                material.pawns as f32
+                    + material.pieces as f32 * weights.value_weight
+                    + piece_square_total(board_state),
+            )
+            .unwrap(),
+        )
+    }
+
+    //static_evaluation for a board whose material and moves are already known
+    pub(super) fn static_evaluation_with(
+        &self,
+        board_state: &Board,
+        material: &Material,
+        moves: &BoardMoves,
+    ) -> Eval {
+        //finished games keep their mate or stalemate score
+        match self.tablebase_eval(board_state) {
+            Some(eval) if moves.game_end(board_state).is_none() => return eval,
+            _ => {}
+        }
+        Engine::static_evaluation_of(&self.static_eval_weights, board_state, material, moves)
+    }
+
+    fn static_evaluation_of(
+        weights: &StaticEvaluationWeights,
+        board_state: &Board,
+        material: &Material,
+        moves: &BoardMoves,
+    ) -> Eval {
+        match moves.game_end(board_state) {
+            None => {}
+            Some(GameEnd::Mated(Color::White)) => return Eval::MateIn(Color::Black, 0),
+            Some(GameEnd::Mated(Color::Black)) => return Eval::MateIn(Color::White, 0),
+            Some(GameEnd::StaleMate) => return Eval::Numeric(NotNan::new(0.0).unwrap()),
+        };
+        if let Some(eval) = kpk_eval(board_state) {
+            return eval;
+        }
+        Eval::Numeric(
+            NotNan::new(Engine::breakdown_with(weights, board_state, material, moves).total())
+                .unwrap(),
+        )
+    }
+}
+
+impl StaticEvaluationWeights {
+    //the static eval of a board under these weights, with no engine to build. for scoring
+    //many unrelated positions, as the tuner does
+    pub fn evaluate(&self, board: &Board) -> Eval {
+        Engine::static_evaluation_of(self, board, &Material::of(board), &BoardMoves::of(board))
+    }
+}
+
+#[test]
+fn test_eval() {
+    let _m5 = Eval::MateIn(Color::Black, 5);
+    let _m1 = Eval::MateIn(Color::Black, 1);
+    let m5 = Eval::MateIn(Color::White, 5);
+    let m1 = Eval::MateIn(Color::White, 1);
+    let one = Eval::Numeric(NotNan::new(1.0).unwrap());
+    let _one = Eval::Numeric(NotNan::new(-1.0).unwrap());
+    assert!(_m5 > _m1);
+    assert!(m5 < m1);
+    assert!(_m5 < m1);
+    assert!(m5 > _m1);
+    assert!(m1 > one);
+    assert!(one > _one);
+    assert!(_one > _m1);
+}
+
+#[test]
+fn test_center_control_symmetry() {
+    let control =
+        |board: &Board, color| Engine::center_control(board, &BoardMoves::of(board), color);
+    let board = make_board_from_fen("rnbqkbnr/pppppppp/8/8/3PP3/2N5/PPP2PPP/R1BQKBNR b KQkq - 0 3")
+        .unwrap();
+    let mirrored =
+        make_board_from_fen("r1bqkbnr/ppp2ppp/2n5/3pp3/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 3")
+            .unwrap();
+    assert_eq!(
+        control(&board, Color::White),
+        control(&mirrored, Color::Black)
+    );
+    assert_eq!(
+        control(&board, Color::Black),
+        control(&mirrored, Color::White)
+    );
+    assert!(control(&board, Color::White) > control(&board, Color::Black));
+}
+
+#[test]
+fn test_knight_outpost() {
+    let board =
+        make_board_from_fen("r1bqkb1r/pp3ppp/3p4/3Np3/4P3/8/PPP2PPP/R2QKB1R w KQkq - 0 1").unwrap();
+    assert_eq!(Engine::knight_outposts(&board, Color::White), 1.0);
+    assert_eq!(Engine::knight_outposts(&board, Color::Black), 0.0);
+
+    //a pawn on c7 can still kick the knight with ...c6
+    let board = make_board_from_fen("r1bqkb1r/ppp2ppp/3p4/3Np3/4P3/8/PPP2PPP/R2QKB1R w KQkq - 0 1")
+        .unwrap();
+    assert_eq!(Engine::knight_outposts(&board, Color::White), 0.0);
+}
+
+#[test]
+fn test_knight_underpromotion() {
+    //e8=Q loses to Rxe8, e8=N+ forks the king and queen
+    let board = make_board_from_fen("8/4P1k1/3q4/8/8/8/4r3/7K w - - 0 1").unwrap();
+    let promotions: Vec<PieceKind> = legal_moves(&board)
+        .into_iter()
+        .filter_map(|(chess_move, _)| match chess_move {
+            ChessMove::Promotion(_, piece_kind) => Some(piece_kind),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(promotions.len(), 4);
+
+    let mut engine = Engine::new(
+        board,
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    for _ in 0..300 {
+        engine.think_next_move().unwrap();
+    }
+    let (_, best_move) = engine.eval_and_best_move();
+    assert_eq!(
+        best_move.unwrap().name(engine.get_board()).unwrap(),
+        engine
+            .get_board()
+            .interpret_move("e8=N+")
+            .unwrap()
+            .name(engine.get_board())
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_relative_to() {
+    //black is a queen up
+    let engine = Engine::new(
+        make_board_from_fen("3qk3/8/8/8/8/8/8/4K3 b - - 0 1").unwrap(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    let eval = engine.static_evaluation(engine.get_board());
+    assert!(eval.centipawns().unwrap() < 0);
+    assert_eq!(eval.clone().relative_to(Color::White), eval);
+    assert!(eval.relative_to(Color::Black).centipawns().unwrap() > 0);
+
+    let mate = Eval::MateIn(Color::Black, 3);
+    assert_eq!(
+        mate.clone().relative_to(Color::White).mate_in_moves(),
+        Some(-2)
+    );
+    assert_eq!(mate.relative_to(Color::Black).mate_in_moves(), Some(2));
+}
+
+#[test]
+fn test_is_quiet() {
+    let quiet = |fen: &str| Engine::is_quiet(&make_board_from_fen(fen).unwrap());
+    //the queen on h4 hangs to the knight
+    assert!(!quiet(
+        "rnb1kbnr/pppp1ppp/8/4p3/4P2q/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3"
+    ));
+    //locked pawn chains, nothing to take
+    assert!(quiet("4k3/8/3p4/2pPp3/2P1P3/8/8/4K3 w - - 0 1"));
+    //the knight can take a pawn, but the pawn is defended
+    assert!(quiet("4k3/5p2/4p3/8/3N4/8/8/4K3 w - - 0 1"));
+    //an even trade of knights is still a capture worth looking at
+    assert!(!quiet("4k3/5p2/4n3/8/3N4/8/8/4K3 w - - 0 1"));
+}
+
+#[test]
+fn test_resign_after_streak() {
+    let board = make_board_from_fen("qq2k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let engine = Engine::new(
+        board,
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    let mut adjudicator = Adjudicator::new(MinimaxSettings::new());
+    let eval = engine.static_evaluation(engine.get_board());
+    assert_eq!(adjudicator.adjudicate(&engine, &eval), None);
+    assert_eq!(adjudicator.adjudicate(&engine, &eval), None);
+    assert_eq!(
+        adjudicator.adjudicate(&engine, &eval),
+        Some(Adjudication::Resign(Color::White))
+    );
+}
+
+#[test]
+fn test_agreed_draw() {
+    let mut settings = MinimaxSettings::new();
+    settings.agreement_draw_moves = 4;
+    let mut engine = Engine::new(
+        Board::new(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        settings.clone(),
+        SearchSettings::new(),
+    );
+    let level = Eval::Numeric(NotNan::new(0.1).unwrap());
+    let ahead = Eval::Numeric(NotNan::new(1.0).unwrap());
+
+    //balanced, but nothing has been played without progress yet
+    let mut adjudicator = Adjudicator::new(settings.clone());
+    for _ in 0..4 {
+        assert_eq!(adjudicator.adjudicate(&engine, &level), None);
+    }
+
+    for san in ["Nf3", "Nf6", "Nc3", "Nc6"] {
+        engine.interpret_and_make_move(san).unwrap();
+    }
+    let mut adjudicator = Adjudicator::new(settings);
+    for eval in [&level, &level, &ahead, &level, &level, &level] {
+        assert_eq!(adjudicator.adjudicate(&engine, eval), None);
+    }
+    assert_eq!(
+        adjudicator.adjudicate(&engine, &level),
+        Some(Adjudication::AgreedDraw)
+    );
+    assert_eq!(Adjudication::AgreedDraw.result(), "1/2-1/2");
+
+    //left at its default, the same game goes on
+    let mut adjudicator = Adjudicator::new(MinimaxSettings::new());
+    for _ in 0..8 {
+        assert_eq!(adjudicator.adjudicate(&engine, &level), None);
+    }
+}
+
+#[test]
+fn test_claim_threefold() {
+    let mut engine = Engine::new(
+        Board::new(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    for _ in 0..2 {
+        for san in ["Nf3", "Nf6", "Ng1", "Ng8"] {
+            assert_eq!(engine.can_claim_draw(), None);
+            engine.interpret_and_make_move(san).unwrap();
+        }
+    }
+    assert_eq!(
+        engine.can_claim_draw(),
+        Some(DrawReason::ThreefoldRepetition)
+    );
+
+    let mut adjudicator = Adjudicator::new(MinimaxSettings::new());
+    let level = Eval::Numeric(NotNan::new(0.2).unwrap());
+    assert_eq!(
+        adjudicator.adjudicate(&engine, &level),
+        Some(Adjudication::ClaimDraw(DrawReason::ThreefoldRepetition))
+    );
+    //white to move and clearly better plays on
+    let winning = Eval::Numeric(NotNan::new(3.0).unwrap());
+    assert_eq!(adjudicator.adjudicate(&engine, &winning), None);
+}
+
+#[test]
+fn test_pawn_majority_symmetry() {
+    let engine = Engine::new(
+        Board::new(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    //white has three against two on the queenside, the kingside is level
+    let board = make_board_from_fen("4k3/pp3pp1/8/8/8/8/PPP2PP1/4K3 w - - 0 1").unwrap();
+    let mirrored = make_board_from_fen("4k3/ppp2pp1/8/8/8/8/PP3PP1/4K3 b - - 0 1").unwrap();
+    let majority = engine.evaluation_breakdown(&board).pawn_majority;
+    assert!(majority > 0.0);
+    assert_eq!(
+        engine.evaluation_breakdown(&mirrored).pawn_majority,
+        -majority
+    );
+    assert_eq!(
+        engine.evaluation_breakdown(&Board::new()).pawn_majority,
+        0.0
+    );
+}
+
+#[test]
+fn test_lost_castling() {
+    let engine = Engine::new(
+        Board::new(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    //white walked the king to e2, black castled short: only white pays
+    let board =
+        make_board_from_fen("rnbq1rk1/ppppbppp/5n2/4p3/4P3/5N2/PPPPKPPP/RNBQ1B1R w - - 6 5")
+            .unwrap();
+    assert!(Engine::lost_castling(&board, Color::White));
+    assert!(!Engine::lost_castling(&board, Color::Black));
+    assert!(engine.evaluation_breakdown(&board).lost_castling < 0.0);
+    assert_eq!(
+        engine.evaluation_breakdown(&Board::new()).lost_castling,
+        0.0
+    );
+
+    //with only kings and pawns left the penalty is gone
+    let board = make_board_from_fen("4k3/pppp4/8/8/8/8/4PPPP/3K4 w - - 0 40").unwrap();
+    assert!(Engine::lost_castling(&board, Color::White));
+    assert_eq!(engine.evaluation_breakdown(&board).lost_castling, 0.0);
+}
+
+#[test]
+fn test_unflagged_terminal_positions() {
+    let engine = Engine::new(
+        Board::new(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    //back rank mate, white to move and mated
+    let mut mated = make_board_from_fen("6k1/8/8/8/8/8/5PPP/3r2K1 w - - 0 1").unwrap();
+    mated.is_checkmate = None;
+    assert_eq!(
+        engine.static_evaluation(&mated),
+        Eval::MateIn(Color::Black, 0)
+    );
+
+    //black king boxed in by the queen without being in check
+    let mut stalemate = make_board_from_fen("k7/8/1Q6/8/8/8/8/7K b - - 0 1").unwrap();
+    stalemate.is_checkmate = None;
+    assert_eq!(
+        engine.static_evaluation(&stalemate),
+        Eval::Numeric(NotNan::new(0.0).unwrap())
+    );
+}
+
+#[test]
+fn test_fast_eval_profile() {
+    let mut engine = Engine::new(
+        Board::new(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    engine.set_eval_profile(EvalProfile::Fast);
+    assert_eq!(
+        engine.static_evaluation(&Board::new()),
+        Eval::Numeric(NotNan::new(0.0).unwrap())
+    );
+    //a pawn up, and a center pawn forward is worth more than one at home
+    let up =
+        make_board_from_fen("rnbqkbnr/ppp1pppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2").unwrap();
+    let value = match engine.static_evaluation(&up) {
+        Eval::Numeric(value) => value.into_inner(),
+        eval => panic!("{}", eval),
+    };
+    assert!(value > 1.0);
+    //the board's flags say the game is over
+    let mated = make_board_from_fen("6k1/8/8/8/8/8/5PPP/3r2K1 w - - 0 1").unwrap();
+    assert_eq!(
+        engine.static_evaluation(&mated),
+        Eval::MateIn(Color::Black, 0)
+    );
+}
+
+#[test]
+fn test_rook_behind_passer() {
+    let engine = Engine::new(
+        Board::new(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    //lucena-like: the b-pawn is one step from queening with the white rook behind it
+    let board = make_board_from_fen("3k4/1P6/8/8/8/8/r7/1R4K1 w - - 0 1").unwrap();
+    assert_eq!(Engine::rook_behind(&board, 6, 1), Some(Color::White));
+    let supported = engine.evaluation_breakdown(&board).rook_behind_passer;
+    assert!(supported > 0.0);
+
+    //the same with the rooks swapped, so black's rook hounds the pawn from behind
+    let board = make_board_from_fen("3k4/1P6/8/8/8/8/R5K1/1r6 w - - 0 1").unwrap();
+    assert_eq!(Engine::rook_behind(&board, 6, 1), Some(Color::Black));
+    assert_eq!(
+        engine.evaluation_breakdown(&board).rook_behind_passer,
+        -supported
+    );
+}
+
+#[test]
+fn test_king_tropism() {
+    //queen, knight and bishop swung over to the castled king versus still at home
+    let attack = make_board_from_fen("5rk1/5ppp/8/6NQ/8/3B4/5PPP/6K1 w - - 0 1").unwrap();
+    let quiet = make_board_from_fen("5rk1/5ppp/8/8/8/8/5PPP/1NBQ2K1 w - - 0 1").unwrap();
+    assert!(
+        Engine::king_tropism(&attack, Color::White)
+            > 2.0 * Engine::king_tropism(&quiet, Color::White)
+    );
+    assert_eq!(
+        Engine::king_tropism(&attack, Color::Black),
+        Engine::king_tropism(&quiet, Color::Black)
+    );
+}
+
+#[test]
+fn test_trapped_bishop() {
+    let trapped = |board: &Board| {
+        let [(_, white), (_, black)] = Engine::mobility_and_trapped(board, &BoardMoves::of(board));
+        (white, black)
+    };
+    //Bxa7 b6 shuts the bishop in: b8 is covered by the king and b6 by the c7 pawn
+    let board = make_board_from_fen("2k5/B1p5/1p6/8/8/8/8/4K3 w - - 0 1").unwrap();
+    assert_eq!(trapped(&board), (1, 0));
+
+    let board = make_board_from_fen("2k5/B1p5/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    assert_eq!(trapped(&board), (0, 0));
+}
+
+#[test]
+fn test_threats_by_defenders() {
+    let threats = |fen: &str| {
+        let board = make_board_from_fen(fen).unwrap();
+        (
+            Engine::evaluate_threats(&board),
+            Engine::evaluate_total_attack(&board),
+        )
+    };
+    let rook = piece_worth_king_zero(PieceKind::Rook);
+    //the knight on e3 attacks a hanging rook
+    assert_eq!(threats("4k3/8/8/3r4/8/4N3/8/4K3 b - - 0 1"), (rook, rook));
+    //the pawn on e6 defends it
+    assert_eq!(
+        threats("4k3/8/4p3/3r4/8/4N3/8/4K3 b - - 0 1"),
+        (rook * 0.25, rook)
+    );
+    //the rook on d8 is shut off by its own pawn, which doesn't defend d5 either
+    assert_eq!(
+        threats("3rk3/8/3p4/3r4/8/4N3/8/4K3 b - - 0 1"),
+        (rook, rook)
+    );
+}
+
+#[test]
+fn test_repeat_move_penalty() {
+    let interest = |fen: &str, san: &str, weights: &InterestEvaluationWeights| {
+        let board = make_board_from_fen(fen).unwrap();
+        let chess_move = board.interpret_move(san).unwrap();
+        let mut ending_board = board.clone();
+        ending_board.make_legal_move(chess_move).unwrap();
+        Engine::evaluate_interest(
+            weights,
+            &chess_move,
+            &board,
+            &ending_board,
+            &BoardMoves::of(&board),
+            &BoardMoves::of(&ending_board),
+        )
+        .unwrap()
+        .into_inner()
+    };
+    let weights = InterestEvaluationWeights::new();
+    let unpenalized = InterestEvaluationWeights {
+        repeat_move_penalty: 0.0,
+        ..InterestEvaluationWeights::new()
+    };
+    //the knight on f3 moving again with the queenside still at home
+    let opening = "rnbqkbnr/pppp1ppp/8/4p3/8/5N2/PPPPPPPP/RNBQKB1R w KQkq - 0 2";
+    assert_eq!(
+        interest(opening, "Ng5", &unpenalized) - interest(opening, "Ng5", &weights),
+        weights.repeat_move_penalty
+    );
+    //a first move is never penalized
+    assert_eq!(
+        interest(opening, "Nc3", &unpenalized),
+        interest(opening, "Nc3", &weights)
+    );
+    //every white minor piece is out
+    let developed = "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/2NP1N2/PPPB1PPP/R2QK2R w KQkq - 0 6";
+    assert_eq!(
+        interest(developed, "Ng5", &unpenalized),
+        interest(developed, "Ng5", &weights)
+    );
+}
+
+#[test]
+fn test_check_evasions_prioritized() {
+    let best_reply = |san: &str| {
+        let mut engine = Engine::new(
+            make_board_from_fen("4k3/8/8/8/8/8/3Q4/4K3 w - - 0 1").unwrap(),
+            StaticEvaluationWeights::new(),
+            InterestEvaluationWeights::new(),
+            MinimaxSettings::new(),
+            SearchSettings::new(),
+        );
+        let chess_move = engine.get_board().interpret_move(san).unwrap();
+        let location = VecDeque::from([chess_move]);
+        engine.generate_all_moves(location.clone()).unwrap();
+        engine
+            .moves
+            .iter()
+            .filter(|entry| entry.location == location)
+            .map(|entry| entry.valued_move.value)
+            .max()
+            .unwrap()
+    };
+    //the answers to a check are expanded ahead of the answers to a quiet queen move
+    assert!(best_reply("Qe2+") > best_reply("Qd3"));
+}
+
+#[test]
+fn test_opposite_colored_bishops() {
+    let breakdown = |fen: &str, weights: &StaticEvaluationWeights| {
+        let board = make_board_from_fen(fen).unwrap();
+        Engine::breakdown_with(
+            weights,
+            &board,
+            &Material::of(&board),
+            &BoardMoves::of(&board),
+        )
+    };
+    let weights = StaticEvaluationWeights::new();
+    let unscaled = StaticEvaluationWeights {
+        ocb_draw_factor: 1.0,
+        ..StaticEvaluationWeights::new()
+    };
+    //two pawns up, but the dark squares in front of them are black's for good
+    let fortress = "8/8/3bk3/1P6/P1B5/8/5K2/8 w - - 0 1";
+    let full = breakdown(fortress, &unscaled);
+    let scaled = breakdown(fortress, &weights);
+    assert!(full.total() > 1.5);
+    assert_eq!(full.opposite_bishops, 0.0);
+    assert!((scaled.total() - full.total() * weights.ocb_draw_factor).abs() < 1e-4);
+    let summed: f32 = scaled.terms().iter().map(|(_, value)| value).sum();
+    assert!((summed - scaled.total()).abs() < 1e-4);
+
+    //same-colored bishops are left alone
+    let same_colored = breakdown("8/3b4/4k3/1P6/P1B5/8/5K2/8 w - - 0 1", &weights);
+    assert_eq!(same_colored.opposite_bishops, 0.0);
+    assert_eq!(same_colored.fortress, 0.0);
+
+    //with both pawns stopped, a fortress takes off nearly all of what's left
+    let blocked = breakdown("8/8/6k1/1b6/5PP1/4B3/6K1/8 w - - 0 1", &weights);
+    let unscaled_total = blocked.total() - blocked.fortress;
+    assert!(blocked.fortress < 0.0);
+    assert!((blocked.total() - unscaled_total * FORTRESS_SCALE).abs() < 1e-4);
+}
+
+#[test]
+fn test_clamp_for_display() {
+    let numeric = |value: f32| Eval::Numeric(NotNan::new(value).unwrap());
+    assert_eq!(numeric(3.5).clamp_for_display(), numeric(3.5));
+    assert_eq!(
+        numeric(DISPLAY_BOUND).clamp_for_display(),
+        numeric(DISPLAY_BOUND)
+    );
+    assert_eq!(
+        numeric(DISPLAY_BOUND + 0.5).clamp_for_display(),
+        numeric(DISPLAY_BOUND)
+    );
+    assert_eq!(
+        numeric(f32::NEG_INFINITY).clamp_for_display(),
+        numeric(-DISPLAY_BOUND)
+    );
+    assert_eq!(
+        numeric(f32::INFINITY).clamp_for_display().centipawns(),
+        Some(10000)
+    );
+    let mate = Eval::MateIn(Color::Black, 250);
+    assert_eq!(mate.clamp_for_display(), mate);
+}
+
+#[test]
+fn test_threats() {
+    //the queen on d5 hangs to the knight, the b5 pawn is defended
+    let board = make_board_from_fen("4k3/8/p7/1p1q4/8/2N5/8/6KR w - - 0 1").unwrap();
+    let mut engine = Engine::new(
+        Board::new(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    engine.set_position(board.clone());
+    let threats = engine.threats();
+    let take_queen = board.interpret_move("Nxd5").unwrap();
+    assert_eq!(threats.hanging, vec![take_queen]);
+    assert_eq!(threats.winning_captures, vec![(take_queen, 9.0)]);
+    assert!(threats
+        .checks
+        .contains(&board.interpret_move("Rh8").unwrap()));
+    assert!(!threats.checks.contains(&take_queen));
+}
+
+#[test]
+fn test_evaluation_symmetry() {
+    let engine = Engine::new(
+        Board::new(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    //positions along a pseudo-random game, each scored against its mirror image, which
+    //every term has to see exactly the other way around
+    for board in random_positions(0x7e405, 80) {
+        //rebuilt from its FEN, as the mirror is. right after a double push the board still
+        //offers the square passed over to the pawns of the side not to move, which square
+        //control counts and a FEN can't carry
+        let position = make_board_from_fen(&board_fen(&board)).unwrap();
+        let mirrored = mirror(&board);
+        let terms = engine.evaluation_breakdown(&position).terms();
+        let mirrored_terms = engine.evaluation_breakdown(&mirrored).terms();
+        for ((name, value), (_, mirrored_value)) in terms.iter().zip(mirrored_terms) {
+            assert!(
+                (value + mirrored_value).abs() < 1e-4,
+                "{} is asymmetric in {}: {} against {}",
+                name,
+                board_fen(&board),
+                value,
+                mirrored_value
+            );
+        }
+        if let (Eval::Numeric(value), Eval::Numeric(mirrored_value)) = (
+            engine.static_evaluation(&position),
+            engine.static_evaluation(&mirrored),
+        ) {
+            assert!(
+                (*value + *mirrored_value).abs() < 1e-4,
+                "{}",
+                board_fen(&board)
+            );
+        }
+    }
+}
@@ -0,0 +1,1063 @@
+#[cfg(test)]
+use std::collections::VecDeque;
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+        mpsc::{Receiver, TryRecvError},
+    },
+    time::Duration,
+};
+
+use ordered_float::NotNan;
+use rust_chess::chess::chess::*;
+
+use super::board::{capture_moves, legal_moves, position_key, CastlingRights};
+use super::concurrency::SEARCH_ABORTED;
+use super::eval::Eval;
+use super::tree::{Engine, EngineError, MoveTree};
+use super::weights::{
+    InterestEvaluationWeights, MinimaxSettings, SearchSettings, StaticEvaluationWeights,
+};
+
+//what a search settled on and how deep it looked to get there
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    pub eval: Eval,
+    pub best_move: Option<ChessMove>,
+    //plies every line was searched to before being scored, mates and repetitions aside
+    pub depth: usize,
+    //plies the deepest line reached, on-the-fly expansion and quiescence included
+    pub seldepth: usize,
+}
+
+//one iteration of iterative deepening: the eval, best move and principal variation with
+//every line scored at ply depth, and the best move of the iteration before when this one
+//changed its mind
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Iteration {
+    pub depth: i32,
+    pub eval: Eval,
+    pub best_move: Option<ChessMove>,
+    pub principal_variation: Vec<ChessMove>,
+    pub best_move_changed_from: Option<ChessMove>,
+}
+
+//what ends a go: whichever of the set limits is hit first, or a message on its stop
+//channel. infinite holds the answer back until that message even once the tree has been
+//searched through, as UCI's go infinite asks. nodes counts what SearchStats::nodes does
+//and ends the search the moment it's reached, so a node limit always cuts at the same spot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub depth: Option<i32>,
+    pub movetime: Option<Duration>,
+    pub nodes: Option<usize>,
+    pub infinite: bool,
+}
+
+//a search of board by an engine of its own, for callers that bring their own threads.
+//the engine ponders on the calling thread for the settings' expansion_budget, or until
+//a message on stop (or the sender being dropped, or the tree running out of moves), then
+//searches what it built the way go does, so a stop arriving then cuts the search short
+//too. the engine is dropped after, so any number of these can run at once on
+//independent positions
+pub fn search_position(board: Board, settings: SearchSettings, stop: Receiver<()>) -> SearchResult {
+    let budget = settings.expansion_budget;
+    let mut engine = Engine::new(
+        board,
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        settings,
+    );
+    for _ in 0..budget {
+        if !matches!(stop.try_recv(), Err(TryRecvError::Empty)) {
+            break;
+        }
+        if engine.think_next_move().is_err() {
+            break;
+        }
+    }
+    let (eval, best_move, _) = engine.go(SearchLimits::default(), stop);
+    engine.search_result(eval, best_move)
+}
+
+//how often each kind of cutoff and prune fired since the stats were last reset
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    pub nodes: usize,
+    pub beta_cutoffs: usize,
+    pub futility_prunes: usize,
+    //nodes near the horizon handed to quiescence without searching their moves
+    pub razor_prunes: usize,
+    //leaves searched through the expanded node they transpose into
+    pub transposition_hits: usize,
+    pub repetitions: usize,
+    pub on_the_fly_expansions: usize,
+    pub stand_pat_cutoffs: usize,
+    pub delta_prunes: usize,
+    //beta cutoffs among captures, past the stand pat
+    pub quiescence_cutoffs: usize,
+    //tablebase probes that came back with a verdict
+    pub tablebase_hits: usize,
+    //threads the tree split started for younger brothers
+    pub split_threads: usize,
+}
+
+impl fmt::Display for SearchStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = [
+            ("nodes", self.nodes),
+            ("beta cutoffs", self.beta_cutoffs),
+            ("futility prunes", self.futility_prunes),
+            ("razor prunes", self.razor_prunes),
+            ("transposition hits", self.transposition_hits),
+            ("repetitions", self.repetitions),
+            ("on the fly", self.on_the_fly_expansions),
+            ("stand pat cutoffs", self.stand_pat_cutoffs),
+            ("delta prunes", self.delta_prunes),
+            ("quiescence cutoffs", self.quiescence_cutoffs),
+            ("tablebase hits", self.tablebase_hits),
+            ("split threads", self.split_threads),
+        ];
+        for (label, count) in rows {
+            writeln!(f, "{:<20}{}", label, count)?;
+        }
+        Ok(())
+    }
+}
+
+//the engine's running counts behind SearchStats, shared by every search thread
+#[derive(Debug, Default)]
+pub(super) struct SearchCounters {
+    pub(super) beta_cutoffs: AtomicUsize,
+    futility_prunes: AtomicUsize,
+    razor_prunes: AtomicUsize,
+    transposition_hits: AtomicUsize,
+    repetitions: AtomicUsize,
+    on_the_fly_expansions: AtomicUsize,
+    stand_pat_cutoffs: AtomicUsize,
+    delta_prunes: AtomicUsize,
+    quiescence_cutoffs: AtomicUsize,
+    pub(super) tablebase_hits: AtomicUsize,
+    pub(super) split_threads: AtomicUsize,
+}
+
+impl SearchCounters {
+    fn all(&self) -> [&AtomicUsize; 11] {
+        [
+            &self.beta_cutoffs,
+            &self.futility_prunes,
+            &self.razor_prunes,
+            &self.transposition_hits,
+            &self.repetitions,
+            &self.on_the_fly_expansions,
+            &self.stand_pat_cutoffs,
+            &self.delta_prunes,
+            &self.quiescence_cutoffs,
+            &self.tablebase_hits,
+            &self.split_threads,
+        ]
+    }
+}
+
+pub(super) fn count(counter: &AtomicUsize) {
+    counter.fetch_add(1, AtomicOrdering::Relaxed);
+}
+
+impl Engine {
+    pub fn search_stats(&self) -> SearchStats {
+        let counters = &self.search_counters;
+        let load = |counter: &AtomicUsize| counter.load(AtomicOrdering::Relaxed);
+        SearchStats {
+            nodes: self.nodes_searched(),
+            beta_cutoffs: load(&counters.beta_cutoffs),
+            futility_prunes: load(&counters.futility_prunes),
+            razor_prunes: load(&counters.razor_prunes),
+            transposition_hits: load(&counters.transposition_hits),
+            repetitions: load(&counters.repetitions),
+            on_the_fly_expansions: load(&counters.on_the_fly_expansions),
+            stand_pat_cutoffs: load(&counters.stand_pat_cutoffs),
+            delta_prunes: load(&counters.delta_prunes),
+            quiescence_cutoffs: load(&counters.quiescence_cutoffs),
+            tablebase_hits: load(&counters.tablebase_hits),
+            split_threads: load(&counters.split_threads),
+        }
+    }
+
+    //zeroes the stats, node count included
+    pub fn reset_search_stats(&self) {
+        self.reset_nodes_searched();
+        for counter in self.search_counters.all() {
+            counter.store(0, AtomicOrdering::Relaxed);
+        }
+    }
+
+    pub fn eval_and_best_move(&self) -> (Eval, Option<ChessMove>) {
+        let result = self.search();
+        (result.eval, result.best_move)
+    }
+
+    pub fn search(&self) -> SearchResult {
+        self.reset_depths_reached();
+        if let Some((eval, chess_move)) = self.tablebase_root_move() {
+            return self.search_result(eval, Some(chess_move));
+        }
+        let maximizing_player = self.move_tree.board_state.get_turn() == Color::White;
+        let (eval, best_move) = Engine::minimax(
+            self,
+            &self.move_tree,
+            0,
+            self.search_settings.min_depth,
+            self.search_settings.max_depth,
+            maximizing_player,
+            Eval::worst_for(true),
+            Eval::worst_for(false),
+            &mut Vec::new(),
+        );
+        self.search_result(eval, best_move)
+    }
+
+    pub(super) fn reset_depths_reached(&self) {
+        self.shallowest_leaf
+            .store(usize::MAX, AtomicOrdering::Relaxed);
+        self.deepest_ply.store(0, AtomicOrdering::Relaxed);
+    }
+
+    fn reach_ply(&self, ply: i32) {
+        self.deepest_ply
+            .fetch_max(ply.max(0) as usize, AtomicOrdering::Relaxed);
+    }
+
+    pub(super) fn search_result(&self, eval: Eval, best_move: Option<ChessMove>) -> SearchResult {
+        let (depth, seldepth) = self.depths_reached();
+        SearchResult {
+            eval,
+            best_move,
+            depth,
+            seldepth,
+        }
+    }
+
+    //depth and seldepth of the last search, as SearchResult has them, for the searches
+    //that don't return one
+    pub fn depths_reached(&self) -> (usize, usize) {
+        let seldepth = self.deepest_ply.load(AtomicOrdering::Relaxed);
+        let depth = self
+            .shallowest_leaf
+            .load(AtomicOrdering::Relaxed)
+            .min(seldepth);
+        (depth, seldepth)
+    }
+
+    //the eval of each of moves, and of nothing else from the root, like UCI's
+    //searchmoves. every line is searched depth plies deep, the root move being the
+    //first, the tree's nodes where it has them and on the fly where it doesn't. fails
+    //if any of the moves isn't legal here
+    pub fn eval_among(
+        &self,
+        moves: &[ChessMove],
+        depth: i32,
+    ) -> Result<Vec<(ChessMove, Eval)>, EngineError> {
+        let maximizing_player = self.move_tree.board_state.get_turn() == Color::White;
+        let root_key = self.move_tree.key();
+        let children = moves
+            .iter()
+            .map(|chess_move| {
+                self.move_tree
+                    .moves
+                    .get(chess_move)
+                    .ok_or(EngineError::IllegalMoveError)
+            })
+            .collect::<Result<Vec<&MoveTree>, EngineError>>()?;
+        Ok(moves
+            .iter()
+            .zip(children)
+            .map(|(chess_move, child)| {
+                let eval = self
+                    .minimax(
+                        child,
+                        1,
+                        depth,
+                        depth,
+                        !maximizing_player,
+                        Eval::worst_for(true),
+                        Eval::worst_for(false),
+                        &mut vec![root_key],
+                    )
+                    .0
+                    .increase_mate_counter();
+                (*chess_move, eval)
+            })
+            .collect())
+    }
+
+    //the line both sides are expected to play, following the best move at every node
+    pub fn principal_variation(&self) -> Vec<ChessMove> {
+        self.line_to_depth(
+            self.search_settings.min_depth,
+            self.search_settings.max_depth,
+        )
+        .2
+    }
+
+    //counts the node about to be searched, or marks the search aborted and returns false
+    //once it has been stopped. a node limit is then never gone past, not even in
+    //quiescence
+    fn enter_node(&self) -> bool {
+        if self.stop_search.load(AtomicOrdering::Relaxed) {
+            SEARCH_ABORTED.with(|aborted| aborted.set(true));
+            return false;
+        }
+        self.count_node();
+        true
+    }
+
+    //the root's eval and best move, and the principal variation they start, with every
+    //line scored statically at ply max_depth. the line is the one the search itself
+    //settled on, so it takes a single search
+    pub(super) fn line_to_depth(
+        &self,
+        min_depth: i32,
+        max_depth: i32,
+    ) -> (Eval, Option<ChessMove>, Vec<ChessMove>) {
+        let (eval, line) = self.minimax_line(
+            &self.move_tree,
+            0,
+            min_depth,
+            max_depth,
+            self.move_tree.board_state.get_turn() == Color::White,
+            Eval::worst_for(true),
+            Eval::worst_for(false),
+            &mut Vec::new(),
+        );
+        (eval, line.first().copied(), line)
+    }
+
+    //fail-soft alpha-beta. results at or below alpha (maximizing) or at or above beta
+    //(minimizing) are only bounds
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn minimax(
+        &self,
+        tree: &MoveTree,
+        depth: i32,
+        min_depth: i32,
+        max_depth: i32,
+        maximizing_player: bool,
+        alpha: Eval,
+        beta: Eval,
+        path: &mut Vec<u64>,
+    ) -> (Eval, Option<ChessMove>) {
+        let (eval, line) = self.minimax_line(
+            tree,
+            depth,
+            min_depth,
+            max_depth,
+            maximizing_player,
+            alpha,
+            beta,
+            path,
+        );
+        (eval, line.first().copied())
+    }
+
+    //minimax with the line it settled on: the best move, followed by the line of the
+    //child it leads to, as each node is searched
+    #[allow(clippy::too_many_arguments)]
+    fn minimax_line(
+        &self,
+        tree: &MoveTree,
+        depth: i32,
+        min_depth: i32,
+        max_depth: i32,
+        maximizing_player: bool,
+        mut alpha: Eval,
+        mut beta: Eval,
+        path: &mut Vec<u64>,
+    ) -> (Eval, Vec<ChessMove>) {
+        if !self.enter_node() {
+            return (Eval::worst_for(maximizing_player), Vec::new());
+        }
+        self.reach_ply(depth);
+        //a line that walks back into a position already on the path is a repetition
+        let key = tree.key();
+        if path.contains(&key) {
+            count(&self.search_counters.repetitions);
+            return (Eval::Numeric(NotNan::new(0.0).unwrap()), Vec::new());
+        }
+        let linked = self.follow_transposition(tree, path);
+        if !std::ptr::eq(linked, tree) {
+            count(&self.search_counters.transposition_hits);
+        }
+        let tree = linked;
+        if depth == max_depth || tree.is_leaf() {
+            let eval = self.static_evaluation_of_tree(tree);
+            if let Eval::Numeric(_) = eval {
+                if depth < min_depth && self.minimax_settings.on_the_fly_budget > 0 {
+                    count(&self.search_counters.on_the_fly_expansions);
+                    let mut budget = self.minimax_settings.on_the_fly_budget;
+                    let eval = self.expand_on_the_fly(
+                        &tree.board_state,
+                        tree.castling,
+                        depth,
+                        min_depth,
+                        maximizing_player,
+                        path,
+                        &mut budget,
+                    );
+                    return (eval, Vec::new());
+                }
+                if depth < min_depth {
+                    return (
+                        Eval::Numeric(
+                            NotNan::new(f32::INFINITY).unwrap()
+                                * match maximizing_player {
+                                    true => 1.0,
+                                    false => -1.0,
+                                },
+                        ),
+                        Vec::new(),
+                    );
+                }
+                return (
+                    self.quiet_evaluation(&tree.board_state, maximizing_player, depth),
+                    Vec::new(),
+                );
+            }
+            return (eval, Vec::new());
+        }
+
+        //razoring: a node this far behind the window, even granted the margin, is taken
+        //to stay there unless captures say otherwise
+        let razor_margins = &self.minimax_settings.razor_margins;
+        let razor_eval =
+            self.optimistic_eval(tree, depth, min_depth, maximizing_player, razor_margins);
+        if let Some(razor_eval) = razor_eval {
+            let hopeless = |eval: &Eval| match maximizing_player {
+                true => *eval <= alpha,
+                false => *eval >= beta,
+            };
+            if hopeless(&razor_eval) {
+                //with quiescence off it would only stand pat on the eval razor_eval came from
+                let eval = match self.minimax_settings.quiescence_depth {
+                    0 => {
+                        self.shallowest_leaf
+                            .fetch_min(depth as usize, AtomicOrdering::Relaxed);
+                        self.static_evaluation_of_tree(tree)
+                    }
+                    _ => self.quiet_evaluation(&tree.board_state, maximizing_player, depth),
+                };
+                if hopeless(&eval) {
+                    count(&self.search_counters.razor_prunes);
+                    return (eval, Vec::new());
+                }
+            }
+        }
+
+        let futility_margins = &self.minimax_settings.futility_margins;
+        let futility_eval =
+            self.optimistic_eval(tree, depth, min_depth, maximizing_player, futility_margins);
+
+        path.push(key);
+        let mut best_eval = Eval::worst_for(maximizing_player);
+        let mut best_move = None;
+        let mut best_line = Vec::new();
+        for (chess_move, child) in tree.moves.iter() {
+            //the optimistic bound stands in for the quiet moves futility skips
+            let futile = futility_eval.as_ref().filter(|futility_eval| {
+                let hopeless = match maximizing_player {
+                    true => **futility_eval <= alpha,
+                    false => **futility_eval >= beta,
+                };
+                hopeless && child.material == tree.material && child.board_state.is_check.is_none()
+            });
+            let (eval, line) = match futile {
+                Some(futility_eval) => {
+                    count(&self.search_counters.futility_prunes);
+                    (futility_eval.clone(), Vec::new())
+                }
+                None => {
+                    let (eval, line) = self.minimax_line(
+                        child,
+                        depth + 1,
+                        min_depth,
+                        max_depth,
+                        !maximizing_player,
+                        alpha.clone().decrease_mate_counter(),
+                        beta.clone().decrease_mate_counter(),
+                        path,
+                    );
+                    (eval.increase_mate_counter(), line)
+                }
+            };
+            if maximizing_player {
+                if eval > best_eval {
+                    best_eval = eval;
+                    best_move = Some(*chess_move);
+                    best_line = line;
+                }
+                if best_eval >= beta {
+                    count(&self.search_counters.beta_cutoffs);
+                    break;
+                }
+                alpha = alpha.max(best_eval.clone());
+            } else {
+                if eval < best_eval {
+                    best_eval = eval;
+                    best_move = Some(*chess_move);
+                    best_line = line;
+                }
+                if best_eval <= alpha {
+                    count(&self.search_counters.beta_cutoffs);
+                    break;
+                }
+                beta = beta.min(best_eval.clone());
+            }
+        }
+        path.pop();
+        match best_move {
+            Some(chess_move) => {
+                best_line.insert(0, chess_move);
+                (best_eval, best_line)
+            }
+            None => (best_eval, Vec::new()),
+        }
+    }
+
+    //searches a position the frontier never reached by generating its moves here,
+    //without storing them in the tree. once the budget runs out the remaining
+    //nodes are scored statically
+    #[allow(clippy::too_many_arguments)]
+    fn expand_on_the_fly(
+        &self,
+        board: &Board,
+        castling: CastlingRights,
+        depth: i32,
+        target_depth: i32,
+        maximizing_player: bool,
+        path: &mut Vec<u64>,
+        budget: &mut usize,
+    ) -> Eval {
+        if !self.enter_node() {
+            return Eval::worst_for(maximizing_player);
+        }
+        self.reach_ply(depth);
+        let key = position_key(board, castling);
+        if path.contains(&key) {
+            return Eval::Numeric(NotNan::new(0.0).unwrap());
+        }
+        if board.is_checkmate.is_some() {
+            return self.static_evaluation(board);
+        }
+        if depth >= target_depth || *budget == 0 {
+            return self.quiet_evaluation(board, maximizing_player, depth);
+        }
+        let children = legal_moves(board);
+        if children.is_empty() {
+            return self.static_evaluation(board);
+        }
+        *budget = budget.saturating_sub(children.len());
+
+        path.push(key);
+        let mut best_eval = Eval::MateIn(
+            match maximizing_player {
+                true => Color::Black,
+                false => Color::White,
+            },
+            -1,
+        );
+        for (chess_move, child) in children {
+            let eval = self
+                .expand_on_the_fly(
+                    &child,
+                    castling.after(board, &chess_move),
+                    depth + 1,
+                    target_depth,
+                    !maximizing_player,
+                    path,
+                    budget,
+                )
+                .increase_mate_counter();
+            let better = match maximizing_player {
+                true => eval > best_eval,
+                false => eval < best_eval,
+            };
+            if better {
+                best_eval = eval;
+            }
+        }
+        path.pop();
+        best_eval
+    }
+
+    //the static eval of a leaf once the pending captures on it have been played out.
+    //ply is how deep in the search the leaf is
+    fn quiet_evaluation(&self, board: &Board, maximizing_player: bool, ply: i32) -> Eval {
+        self.shallowest_leaf
+            .fetch_min(ply.max(0) as usize, AtomicOrdering::Relaxed);
+        self.quiescence(
+            board,
+            Eval::MateIn(Color::Black, 0),
+            Eval::MateIn(Color::White, 0),
+            maximizing_player,
+            self.minimax_settings.quiescence_depth,
+            ply,
+        )
+    }
+
+    //alpha-beta over captures and promotions only, standing pat on the static eval
+    fn quiescence(
+        &self,
+        board: &Board,
+        mut alpha: Eval,
+        mut beta: Eval,
+        maximizing_player: bool,
+        depth_left: i32,
+        ply: i32,
+    ) -> Eval {
+        if !self.enter_node() {
+            return Eval::worst_for(maximizing_player);
+        }
+        self.reach_ply(ply);
+        let stand_pat = self.static_evaluation(board);
+        let stand_pat_value = match stand_pat {
+            Eval::Numeric(value) => value,
+            Eval::MateIn(_, _) => return stand_pat,
+        };
+        if depth_left <= 0 {
+            return stand_pat;
+        }
+        if maximizing_player {
+            if stand_pat >= beta {
+                count(&self.search_counters.stand_pat_cutoffs);
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat.clone());
+        } else {
+            if stand_pat <= alpha {
+                count(&self.search_counters.stand_pat_cutoffs);
+                return stand_pat;
+            }
+            beta = beta.min(stand_pat.clone());
+        }
+
+        let mut best_eval = stand_pat;
+        for (chess_move, gain) in capture_moves(board) {
+            //delta pruning: skip captures that can't get back to the window even
+            //if they win their material for free
+            let optimistic = Eval::Numeric(
+                stand_pat_value
+                    + (gain + self.minimax_settings.delta_margin)
+                        * match maximizing_player {
+                            true => 1.0,
+                            false => -1.0,
+                        },
+            );
+            let hopeless = match maximizing_player {
+                true => optimistic <= alpha,
+                false => optimistic >= beta,
+            };
+            if hopeless {
+                count(&self.search_counters.delta_prunes);
+                continue;
+            }
+
+            let mut child = board.clone();
+            if child.make_legal_move(chess_move).is_err() {
+                continue;
+            }
+            let eval = self
+                .quiescence(
+                    &child,
+                    alpha.clone(),
+                    beta.clone(),
+                    !maximizing_player,
+                    depth_left - 1,
+                    ply + 1,
+                )
+                .increase_mate_counter();
+            if maximizing_player {
+                if eval > best_eval {
+                    best_eval = eval;
+                }
+                if best_eval >= beta {
+                    count(&self.search_counters.quiescence_cutoffs);
+                    break;
+                }
+                alpha = alpha.max(best_eval.clone());
+            } else {
+                if eval < best_eval {
+                    best_eval = eval;
+                }
+                if best_eval <= alpha {
+                    count(&self.search_counters.quiescence_cutoffs);
+                    break;
+                }
+                beta = beta.min(best_eval.clone());
+            }
+        }
+        best_eval
+    }
+
+    //the best a quiet move could hope for at a node close to the horizon: the static eval
+    //pushed toward the side to move by the margin (of futility's or razoring's) for its
+    //distance from the horizon. None where pruning by margin doesn't apply
+    fn optimistic_eval(
+        &self,
+        tree: &MoveTree,
+        depth: i32,
+        min_depth: i32,
+        maximizing_player: bool,
+        margins: &[f32],
+    ) -> Option<Eval> {
+        //never at the root, in check, or where leaves still get expanded on the fly
+        if margins.is_empty()
+            || depth == 0
+            || depth + 1 < min_depth
+            || tree.board_state.is_check.is_some()
+        {
+            return None;
+        }
+        let plies_left = plies_to_horizon(tree, margins.len());
+        let margin = *margins.get(plies_left.checked_sub(1)?)?;
+        match self.static_evaluation_of_tree(tree) {
+            Eval::Numeric(value) => Some(Eval::Numeric(
+                value
+                    + match maximizing_player {
+                        true => margin,
+                        false => -margin,
+                    },
+            )),
+            Eval::MateIn(_, _) => None,
+        }
+    }
+}
+
+//plies from tree down to its deepest leaf, counting no further than cap
+fn plies_to_horizon(tree: &MoveTree, cap: usize) -> usize {
+    if tree.is_leaf() || cap == 0 {
+        return 0;
+    }
+    1 + tree
+        .moves
+        .values()
+        .map(|child| plies_to_horizon(child, cap - 1))
+        .max()
+        .unwrap_or(0)
+}
+
+#[test]
+fn test_repetition_in_search() {
+    let board =
+        make_board_from_fen("rnbqkbnr/1ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+    let mut engine = Engine::new(
+        board,
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    //shuffle the knights out and back so the line returns to the root position
+    let mut location = VecDeque::new();
+    for san in ["Nf3", "Nf6", "Ng1", "Ng8"] {
+        if engine.get_location(&location).unwrap().is_leaf() {
+            engine.generate_all_moves(location.clone()).unwrap();
+        }
+        let chess_move = engine
+            .get_location(&location)
+            .unwrap()
+            .board_state
+            .interpret_move(san)
+            .unwrap();
+        location.push_back(chess_move);
+    }
+    let root_key = engine.move_tree.key();
+    let repeated = engine.get_location(&location).unwrap();
+    let draw = Eval::Numeric(NotNan::new(0.0).unwrap());
+
+    let (alpha, beta) = (Eval::worst_for(true), Eval::worst_for(false));
+    let naive = engine.minimax(
+        repeated,
+        4,
+        0,
+        1000,
+        true,
+        alpha.clone(),
+        beta.clone(),
+        &mut Vec::new(),
+    );
+    assert!(naive.0 > draw);
+    let aware = engine.minimax(repeated, 4, 0, 1000, true, alpha, beta, &mut vec![root_key]);
+    assert_eq!(aware.0, draw);
+}
+
+#[test]
+fn test_delta_pruning() {
+    //white is two queens and a rook down; winning the e5 pawn can't matter
+    let board = make_board_from_fen("qr2k2q/8/8/4p3/8/5N2/8/4K3 w - - 0 1").unwrap();
+    let engine = Engine::new(
+        board.clone(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    let alpha = Eval::Numeric(NotNan::new(-5.0).unwrap());
+    let beta = Eval::Numeric(NotNan::new(5.0).unwrap());
+    let eval = engine.quiescence(&board, alpha.clone(), beta, true, 4, 0);
+    assert!(eval < alpha);
+    assert_eq!(engine.nodes_searched(), 1);
+    assert_eq!(engine.search_stats().delta_prunes, 1);
+}
+
+#[test]
+fn test_mate_in_one_expanded_first() {
+    let board = make_board_from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+    let mut engine = Engine::new(
+        board,
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    let mate = engine.get_board().interpret_move("Ra8#").unwrap();
+    assert_eq!(engine.moves.len(), 1);
+    assert_eq!(engine.moves.peek().unwrap().valued_move.chess_move, mate);
+    engine.think_next_move().unwrap();
+    assert_eq!(
+        engine.eval_and_best_move(),
+        (Eval::MateIn(Color::White, 1), Some(mate))
+    );
+    //lines other than the mate were never expanded and get searched on the fly to min_depth
+    let result = engine.search();
+    assert_eq!(result.depth, SearchSettings::new().min_depth as usize);
+    assert!(result.seldepth >= result.depth);
+    assert_eq!(engine.principal_variation(), vec![mate]);
+}
+
+#[test]
+fn test_every_root_line_reaches_min_depth() {
+    let search_settings = SearchSettings {
+        min_depth: 3,
+        ..SearchSettings::new()
+    };
+    let mut engine = Engine::new(
+        make_board_from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3")
+            .unwrap(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        search_settings.clone(),
+    );
+    //some lines expanded past the root, the rest left for the search to expand on the fly
+    for _ in 0..40 {
+        engine.think_next_move().unwrap();
+    }
+    for (chess_move, child) in engine.move_tree.moves.iter() {
+        engine.reset_depths_reached();
+        engine.minimax(
+            child,
+            1,
+            search_settings.min_depth,
+            search_settings.max_depth,
+            false,
+            Eval::worst_for(true),
+            Eval::worst_for(false),
+            &mut vec![engine.move_tree.key()],
+        );
+        let shallowest = engine.shallowest_leaf.load(AtomicOrdering::Relaxed);
+        assert!(
+            shallowest >= search_settings.min_depth as usize,
+            "{} ended a line at ply {}",
+            chess_move.name(engine.get_board()).unwrap(),
+            shallowest
+        );
+    }
+    //and with the window the search narrows as it goes
+    assert!(engine.search().depth >= search_settings.min_depth as usize);
+}
+
+#[test]
+fn test_futility_pruning() {
+    let search = |fen: &str, futility_margins: Vec<f32>| {
+        let mut minimax_settings = MinimaxSettings::new();
+        minimax_settings.futility_margins = futility_margins;
+        let mut engine = Engine::new(
+            make_board_from_fen(fen).unwrap(),
+            StaticEvaluationWeights::new(),
+            InterestEvaluationWeights::new(),
+            minimax_settings,
+            SearchSettings::new(),
+        );
+        for _ in 0..300 {
+            engine.think_next_move().unwrap();
+        }
+        engine.reset_search_stats();
+        let (_, best_move) = engine.eval_and_best_move();
+        (best_move, engine.search_stats())
+    };
+    let margins = MinimaxSettings::new().futility_margins;
+
+    //the queen on h4 hangs to the knight
+    let tactical = "rnb1kbnr/pppp1ppp/8/4p3/4P2q/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+    let (pruned_move, _) = search(tactical, margins.clone());
+    assert_eq!(pruned_move, search(tactical, Vec::new()).0);
+    assert_eq!(
+        pruned_move
+            .unwrap()
+            .name(&make_board_from_fen(tactical).unwrap())
+            .unwrap(),
+        "Nxh4"
+    );
+
+    let quiet = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+    let (pruned_move, pruned_stats) = search(quiet, margins);
+    let (full_move, full_stats) = search(quiet, Vec::new());
+    assert_eq!(pruned_move, full_move);
+    assert!(pruned_stats.futility_prunes > 0);
+    assert_eq!(full_stats.futility_prunes, 0);
+    assert!(pruned_stats.nodes < full_stats.nodes);
+}
+
+#[test]
+fn test_search_position_from_many_threads() {
+    let searches: Vec<_> = [
+        ("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "Ra8#"),
+        ("r5k1/8/8/8/8/8/5PPP/6K1 b - - 0 1", "Ra1#"),
+    ]
+    .into_iter()
+    .map(|(fen, mate)| {
+        std::thread::spawn(move || {
+            let board = make_board_from_fen(fen).unwrap();
+            let mate = board.interpret_move(mate).unwrap();
+            let (sender, stop) = std::sync::mpsc::channel();
+            let stopper = std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(100));
+                let _ = sender.send(());
+            });
+            let result = search_position(board, SearchSettings::new(), stop);
+            stopper.join().unwrap();
+            (result.best_move, mate)
+        })
+    })
+    .collect();
+    for search in searches {
+        let (best_move, mate) = search.join().unwrap();
+        assert_eq!(best_move, Some(mate));
+    }
+}
+
+#[test]
+fn test_search_position_budget() {
+    let mut settings = SearchSettings::new();
+    settings.expansion_budget = 50;
+    //the sender is kept alive and never sends, so only the budget ends the pondering
+    let (_sender, stop) = std::sync::mpsc::channel();
+    let result = search_position(Board::new(), settings, stop);
+    assert!(result.best_move.is_some());
+}
+
+#[test]
+fn test_razoring() {
+    let search = |fen: &str, razor_margins: Vec<f32>| {
+        let mut minimax_settings = MinimaxSettings::new();
+        minimax_settings.razor_margins = razor_margins;
+        let mut engine = Engine::new(
+            make_board_from_fen(fen).unwrap(),
+            StaticEvaluationWeights::new(),
+            InterestEvaluationWeights::new(),
+            minimax_settings,
+            SearchSettings::new(),
+        );
+        for _ in 0..300 {
+            engine.think_next_move().unwrap();
+        }
+        engine.reset_search_stats();
+        let (_, best_move) = engine.eval_and_best_move();
+        (best_move, engine.search_stats())
+    };
+    let margins = MinimaxSettings::new().razor_margins;
+
+    //the queen on h4 hangs to the knight
+    let tactical = "rnb1kbnr/pppp1ppp/8/4p3/4P2q/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+    let (razored_move, _) = search(tactical, margins.clone());
+    assert_eq!(razored_move, search(tactical, Vec::new()).0);
+
+    let quiet = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+    let (razored_move, razored_stats) = search(quiet, margins);
+    let (full_move, full_stats) = search(quiet, Vec::new());
+    assert_eq!(razored_move, full_move);
+    assert!(razored_stats.razor_prunes > 0);
+    assert_eq!(full_stats.razor_prunes, 0);
+    assert!(razored_stats.nodes < full_stats.nodes);
+}
+
+#[test]
+fn test_eval_among() {
+    let board = make_board_from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+    let mut engine = Engine::new(
+        board.clone(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    //only the mate is on the frontier, the rest is searched on the fly
+    engine.think_next_move().unwrap();
+    let mate = board.interpret_move("Ra8#").unwrap();
+    let quiet = board.interpret_move("h3").unwrap();
+    let evals = engine.eval_among(&[quiet, mate], 3).unwrap();
+    assert_eq!(evals[0].0, quiet);
+    assert_eq!(evals[1], (mate, Eval::MateIn(Color::White, 1)));
+
+    //among every root move, the best is what a full search to the same depth finds
+    let all: Vec<ChessMove> = engine.move_tree.moves.keys().copied().collect();
+    let best = engine
+        .eval_among(&all, 3)
+        .unwrap()
+        .into_iter()
+        .map(|(_, eval)| eval)
+        .max()
+        .unwrap();
+    assert_eq!(best, engine.line_to_depth(3, 3).0);
+
+    //the rook sliding diagonally to b8
+    let illegal = ChessMove::Normal(NormalChessMove {
+        initial_row: 0,
+        initial_col: 0,
+        destination_row: 7,
+        destination_col: 1,
+    });
+    assert!(matches!(
+        engine.eval_among(&[quiet, illegal], 3),
+        Err(EngineError::IllegalMoveError)
+    ));
+}
+
+#[test]
+fn test_principal_variation_single_search() {
+    let mut engine = Engine::new(
+        Board::new(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    for _ in 0..200 {
+        engine.think_next_move().unwrap();
+    }
+    engine.reset_nodes_searched();
+    let result = engine.search();
+    let searched = engine.nodes_searched();
+
+    //the line costs what the search does, not a search per ply
+    engine.reset_nodes_searched();
+    let line = engine.principal_variation();
+    assert_eq!(engine.nodes_searched(), searched);
+    assert!(line.len() > 1);
+    assert_eq!(line.first().copied(), result.best_move);
+    let mut board = engine.get_board().clone();
+    for chess_move in line {
+        board.make_legal_move(chess_move).unwrap();
+    }
+}
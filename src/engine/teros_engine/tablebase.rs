@@ -0,0 +1,207 @@
+use std::fmt::Debug;
+#[cfg(test)]
+use std::sync::Arc;
+
+use ordered_float::NotNan;
+use rust_chess::chess::chess::*;
+
+use super::eval::Eval;
+use super::search::count;
+use super::tree::Engine;
+#[cfg(test)]
+use super::weights::{
+    InterestEvaluationWeights, MinimaxSettings, SearchSettings, StaticEvaluationWeights,
+};
+
+//how a tablebase eval is scored, from the winner's point of view. well past anything the
+//heuristic eval reaches, short of a mate
+const TABLEBASE_WIN: f32 = 500.0;
+
+//win/draw/loss for the side to move. cursed wins and blessed losses are the ones the
+//fifty-move rule turns into draws
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Wdl {
+    Loss,
+    BlessedLoss,
+    Draw,
+    CursedWin,
+    Win,
+}
+
+impl Wdl {
+    //the same verdict for the other side
+    pub fn flipped(self) -> Wdl {
+        match self {
+            Wdl::Loss => Wdl::Win,
+            Wdl::BlessedLoss => Wdl::CursedWin,
+            Wdl::Draw => Wdl::Draw,
+            Wdl::CursedWin => Wdl::BlessedLoss,
+            Wdl::Win => Wdl::Loss,
+        }
+    }
+
+    //as an eval from white's point of view, for a position with side to move
+    fn eval(self, side: Color) -> Eval {
+        let value = match self {
+            Wdl::Win => TABLEBASE_WIN,
+            Wdl::Loss => -TABLEBASE_WIN,
+            Wdl::CursedWin | Wdl::Draw | Wdl::BlessedLoss => 0.0,
+        };
+        Eval::Numeric(NotNan::new(value).unwrap()).relative_to(side)
+    }
+}
+
+//an endgame tablebase backend. probes answer for the side to move and return None for
+//positions the tables don't cover
+pub trait Tablebase: Debug + Send + Sync {
+    //the most pieces, kings included, of any position the tables hold
+    fn max_pieces(&self) -> usize;
+    fn probe_wdl(&self, board: &Board) -> Option<Wdl>;
+    //distance to the next capture or pawn move under best play, in plies. positive when
+    //the side to move wins, negative when it loses
+    fn probe_dtz(&self, board: &Board) -> Option<i32>;
+}
+
+//no tables loaded: every probe misses
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoTablebase;
+
+impl Tablebase for NoTablebase {
+    fn max_pieces(&self) -> usize {
+        0
+    }
+
+    fn probe_wdl(&self, _board: &Board) -> Option<Wdl> {
+        None
+    }
+
+    fn probe_dtz(&self, _board: &Board) -> Option<i32> {
+        None
+    }
+}
+
+fn piece_count(board: &Board) -> usize {
+    (0..BOARD_SIZE)
+        .flat_map(|i| (0..BOARD_SIZE).map(move |j| (i, j)))
+        .filter(|&(i, j)| board.get_piece(i, j).unwrap().is_some())
+        .count()
+}
+
+impl Engine {
+    fn in_tablebase(&self, board: &Board) -> bool {
+        let max_pieces = self.tablebase.max_pieces();
+        max_pieces > 0 && piece_count(board) <= max_pieces
+    }
+
+    //probe_wdl, counted in the search stats when it answers
+    fn probe_wdl(&self, board: &Board) -> Option<Wdl> {
+        let wdl = self.tablebase.probe_wdl(board);
+        if wdl.is_some() {
+            count(&self.search_counters.tablebase_hits);
+        }
+        wdl
+    }
+
+    //the tablebase verdict on a position, when the tables cover it
+    pub(super) fn tablebase_eval(&self, board: &Board) -> Option<Eval> {
+        if !self.in_tablebase(board) {
+            return None;
+        }
+        self.probe_wdl(board).map(|wdl| wdl.eval(board.get_turn()))
+    }
+
+    //the root move the tablebase picks, when it covers every position the root leads to:
+    //the best verdict, then the quickest progress toward a win or the slowest toward a loss
+    pub(super) fn tablebase_root_move(&self) -> Option<(Eval, ChessMove)> {
+        let root = &self.move_tree.board_state;
+        if !self.in_tablebase(root) || self.move_tree.moves.is_empty() {
+            return None;
+        }
+        let mut best: Option<(Wdl, i32, ChessMove)> = None;
+        for (chess_move, child) in self.move_tree.moves.iter() {
+            let board = &child.board_state;
+            let wdl = self.probe_wdl(board)?.flipped();
+            let plies = self.tablebase.probe_dtz(board).unwrap_or(0).abs();
+            let progress = match wdl {
+                Wdl::Win | Wdl::CursedWin => -plies,
+                Wdl::Draw => 0,
+                Wdl::BlessedLoss | Wdl::Loss => plies,
+            };
+            if best.is_none_or(|(best_wdl, best_progress, _)| {
+                (wdl, progress) > (best_wdl, best_progress)
+            }) {
+                best = Some((wdl, progress, *chess_move));
+            }
+        }
+        best.map(|(wdl, _, chess_move)| (wdl.eval(root.get_turn()), chess_move))
+    }
+}
+
+//a stand-in for real tables: whoever has the only queen wins, anything else is drawn
+#[cfg(test)]
+#[derive(Debug)]
+struct QueenTablebase;
+
+#[cfg(test)]
+impl Tablebase for QueenTablebase {
+    fn max_pieces(&self) -> usize {
+        3
+    }
+
+    fn probe_wdl(&self, board: &Board) -> Option<Wdl> {
+        let queen_of = |color: Color| Piece {
+            kind: PieceKind::Queen,
+            color,
+        };
+        let has = |piece: Piece| {
+            (0..BOARD_SIZE).any(|i| {
+                (0..BOARD_SIZE).any(|j| board.get_piece(i, j).unwrap() == Some(piece.clone()))
+            })
+        };
+        let turn = board.get_turn();
+        Some(
+            match (has(queen_of(turn)), has(queen_of(turn.opposite()))) {
+                (true, false) => Wdl::Win,
+                (false, true) => Wdl::Loss,
+                _ => Wdl::Draw,
+            },
+        )
+    }
+
+    fn probe_dtz(&self, _board: &Board) -> Option<i32> {
+        None
+    }
+}
+
+#[test]
+fn test_tablebase_overrides() {
+    //black is in check from an undefended queen it can take
+    let mut engine = Engine::new(
+        make_board_from_fen("8/8/4k3/3Q4/8/8/8/K7 b - - 0 1").unwrap(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    assert_eq!(engine.tablebase_root_move(), None);
+    engine.set_tablebase(Arc::new(QueenTablebase));
+
+    assert_eq!(
+        engine.static_evaluation(engine.get_board()),
+        Eval::Numeric(NotNan::new(TABLEBASE_WIN).unwrap())
+    );
+    let capture = engine.get_board().interpret_move("Kxd5").unwrap();
+    engine.reset_search_stats();
+    let result = engine.search();
+    assert_eq!(result.best_move, Some(capture));
+    assert_eq!(result.eval, Eval::Numeric(NotNan::new(0.0).unwrap()));
+    //a verdict for each of the king's moves
+    assert_eq!(
+        engine.search_stats().tablebase_hits,
+        engine
+            .get_location(&Default::default())
+            .unwrap()
+            .moves
+            .len()
+    );
+}
@@ -0,0 +1,1389 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BTreeMap, BinaryHeap, HashMap, VecDeque},
+    hash::Hash,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering},
+        Arc,
+    },
+};
+
+use ordered_float::NotNan;
+use rust_chess::chess::chess::*;
+
+#[cfg(test)]
+use super::board::{board_fen, PositionError};
+use super::board::{
+    game_end, game_phase, interpret_san, is_capture_or_pawn_move, is_mate, parse_fen, position_key,
+    pretty_board, refresh_status, same_position, san, BoardMoves, BoardMovesCache, CastlingRights,
+    FenError, InterpretIccf, InterpretLan, MoveParseError,
+};
+use super::concurrency::{parallel_legal_moves, parallel_map};
+use super::eval::{piece_worth_king_zero, EvalProfile};
+use super::search::SearchCounters;
+use super::tablebase::{NoTablebase, Tablebase};
+use super::weights::{
+    FrontierStrategy, InterestEvaluationWeights, MinimaxSettings, SearchSettings,
+    StaticEvaluationWeights,
+};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(super) struct ValuedMoveLocation {
+    pub(super) valued_move: ValuedChessMove,
+    pub(super) location: VecDeque<ChessMove>,
+    pub(super) depth_cost: NotNan<f32>,
+    pub(super) strategy: FrontierStrategy,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash, PartialOrd, Ord)]
+pub(super) struct ValuedChessMove {
+    pub(super) value: NotNan<f32>, // probably precise enough
+    pub(super) chess_move: ChessMove,
+}
+
+impl ValuedMoveLocation {
+    pub(super) fn value_accounted_for_distance(&self) -> NotNan<f32> {
+        self.valued_move.value - self.depth_cost * self.location.len() as f32
+    }
+
+    //entries picked breadth-first come before every other, the shallowest first. taken
+    //from the location as it is now, so it follows the root as moves are made
+    pub(super) fn breadth_rank(&self) -> Option<Reverse<usize>> {
+        let ply = self.location.len() + 1;
+        self.strategy.breadth_first_at(ply).then_some(Reverse(ply))
+    }
+}
+
+impl Ord for ValuedMoveLocation {
+    //ties fall through every field, so entries only compare equal when they are equal
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.breadth_rank()
+            .cmp(&other.breadth_rank())
+            .then_with(|| {
+                self.value_accounted_for_distance()
+                    .cmp(&other.value_accounted_for_distance())
+            })
+            .then_with(|| self.valued_move.cmp(&other.valued_move))
+            .then_with(|| self.location.cmp(&other.location))
+            .then_with(|| self.depth_cost.cmp(&other.depth_cost))
+            .then_with(|| self.strategy.cmp(&other.strategy))
+    }
+}
+
+impl PartialOrd for ValuedMoveLocation {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub(super) struct MoveTree {
+    pub(super) board_state: Board,
+    pub(super) moves: BTreeMap<ChessMove, MoveTree>,
+    pub(super) material: Material,
+    pub(super) castling: CastlingRights,
+    pub(super) moves_cache: BoardMovesCache,
+}
+
+//material kept up to date move by move, so leaves don't rescan the board for it.
+//balances are white minus black, in whole pawns
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub(super) struct Material {
+    pub(super) pawns: i32,
+    pub(super) pieces: i32,
+    //bishops of white and black, for bishop pair terms
+    bishops: [i32; 2],
+}
+
+impl Material {
+    pub(super) fn of(board: &Board) -> Material {
+        let mut material = Material {
+            pawns: 0,
+            pieces: 0,
+            bishops: [0, 0],
+        };
+        for i in 0..BOARD_SIZE {
+            for j in 0..BOARD_SIZE {
+                if let Some(piece) = board.get_piece(i, j).unwrap() {
+                    material.add(piece.kind, piece.color, 1);
+                }
+            }
+        }
+        material
+    }
+
+    fn add(&mut self, kind: PieceKind, color: Color, count: i32) {
+        if kind == PieceKind::Bishop {
+            self.bishops[match color {
+                Color::White => 0,
+                Color::Black => 1,
+            }] += count;
+        }
+        let count = match color {
+            Color::White => count,
+            Color::Black => -count,
+        };
+        match kind {
+            PieceKind::Pawn => self.pawns += count,
+            kind => self.pieces += *piece_worth_king_zero(kind) as i32 * count,
+        }
+    }
+
+    //the material once chess_move is played on board
+    pub(super) fn after(&self, board: &Board, chess_move: &ChessMove) -> Material {
+        let mut material = *self;
+        let (normal_move, promotion) = match chess_move {
+            ChessMove::Normal(normal_move) => (normal_move, None),
+            ChessMove::Promotion(normal_move, piece_kind) => (normal_move, Some(*piece_kind)),
+            ChessMove::Castling(_) => return material,
+        };
+        let moving = match board
+            .get_piece(normal_move.initial_row, normal_move.initial_col)
+            .unwrap()
+        {
+            Some(piece) => piece,
+            None => return material,
+        };
+        match board
+            .get_piece(normal_move.destination_row, normal_move.destination_col)
+            .unwrap()
+        {
+            Some(captured) => material.add(captured.kind, captured.color, -1),
+            //a pawn moving diagonally onto an empty square takes en passant
+            None if moving.kind == PieceKind::Pawn
+                && normal_move.initial_col != normal_move.destination_col =>
+            {
+                material.add(PieceKind::Pawn, moving.color.opposite(), -1)
+            }
+            None => {}
+        }
+        if let Some(piece_kind) = promotion {
+            material.add(PieceKind::Pawn, moving.color, -1);
+            material.add(piece_kind, moving.color, 1);
+        }
+        material
+    }
+}
+
+impl MoveTree {
+    pub fn is_leaf(&self) -> bool {
+        return self.moves.len() == 0;
+    }
+
+    //the position_key of the node's position
+    pub(super) fn key(&self) -> u64 {
+        position_key(&self.board_state, self.castling)
+    }
+
+    //whether the two nodes hold the same position, castling rights included
+    pub(super) fn same_position(&self, other: &MoveTree) -> bool {
+        self.castling == other.castling && same_position(&self.board_state, &other.board_state)
+    }
+
+    //a node for the board chess_move leads to from this one
+    pub(super) fn child(&self, chess_move: &ChessMove, board: Board) -> MoveTree {
+        MoveTree {
+            material: self.material.after(&self.board_state, chess_move),
+            castling: self.castling.after(&self.board_state, chess_move),
+            board_state: board,
+            moves: BTreeMap::new(),
+            moves_cache: BoardMovesCache::default(),
+        }
+    }
+
+    //the node's moves, generated on first use and kept for every later eval of the node
+    pub(super) fn board_moves(&self) -> &BoardMoves {
+        self.moves_cache.get(&self.board_state)
+    }
+
+    //number of nodes in the tree, this one included
+    pub(super) fn size(&self) -> usize {
+        1 + self.moves.values().map(MoveTree::size).sum::<usize>()
+    }
+
+    pub fn print_tree(&self, depth: i32, max_depth: i32) {
+        if depth > max_depth {
+            return;
+        }
+        for (chess_move, tree) in self.moves.iter() {
+            for _ in 0..depth {
+                print!("  |");
+            }
+            print!("-");
+            println!("{}", chess_move.name(&self.board_state).unwrap());
+            tree.print_tree(depth + 1, max_depth);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Engine {
+    pub(super) moves: BinaryHeap<ValuedMoveLocation>,
+    pub(super) move_tree: MoveTree,
+    pub(super) static_eval_weights: StaticEvaluationWeights,
+    //the interest weights at the start of the game. each expansion mixes them with
+    //endgame_interest_weights by the game_phase of the position expanded
+    pub(super) interest_eval_weights: InterestEvaluationWeights,
+    pub(super) endgame_interest_weights: InterestEvaluationWeights,
+    pub(super) minimax_settings: MinimaxSettings,
+    pub(super) search_settings: SearchSettings,
+    pub(super) nodes_searched: AtomicUsize,
+    pub(super) search_counters: SearchCounters,
+    //consulted for positions with few enough pieces, NoTablebase unless one is set
+    pub(super) tablebase: Arc<dyn Tablebase>,
+    pub(super) eval_profile: EvalProfile,
+    //shallowest ply a line ended on and deepest ply any line got to, for the current search
+    pub(super) shallowest_leaf: AtomicUsize,
+    pub(super) deepest_ply: AtomicUsize,
+    //raised to make running searches return early
+    pub(super) stop_search: AtomicBool,
+    //nodes_searched at which stop_search is raised, for a go with a node limit.
+    //usize::MAX when there's none
+    pub(super) node_limit: AtomicUsize,
+    //position key -> location of the first expanded node holding that position
+    pub(super) transpositions: HashMap<u64, VecDeque<ChessMove>>,
+    //frontier entries that were not expanded because they transpose into an expanded node
+    pub(super) linked_moves: Vec<ValuedMoveLocation>,
+    //keys of every position the game went through, the current one last
+    pub(super) position_history: Vec<u64>,
+    pub(super) move_history: Vec<ChessMove>,
+    //the position and castling rights the game started from, which move_history is
+    //played from
+    pub(super) game_start: (Board, CastlingRights),
+    //plies since the last capture or pawn move
+    pub(super) halfmove_clock: usize,
+    pub(super) tree_size: usize,
+    //nodes evict_if_needed dropped since take_evicted was last called
+    pub(super) evicted: usize,
+    //workers the last pondering ran with, which the root is regenerated with too once a
+    //move empties the tree. 1 until the engine ponders
+    pub(super) thread_count: usize,
+}
+
+//a draw the rules let the side to move claim
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrawReason {
+    ThreefoldRepetition,
+    FiftyMoveRule,
+}
+
+impl DrawReason {
+    pub fn description(&self) -> &'static str {
+        match self {
+            DrawReason::ThreefoldRepetition => "threefold repetition",
+            DrawReason::FiftyMoveRule => "fifty-move rule",
+        }
+    }
+}
+
+//how the game on the board ended by the rules alone, without anyone claiming anything
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameOver {
+    //by the winner
+    Checkmate(Color),
+    Stalemate,
+    FivefoldRepetition,
+    SeventyFiveMoveRule,
+}
+
+impl GameOver {
+    pub fn description(&self) -> &'static str {
+        match self {
+            GameOver::Checkmate(Color::White) => "Checkmate, White wins",
+            GameOver::Checkmate(Color::Black) => "Checkmate, Black wins",
+            GameOver::Stalemate => "Stalemate, draw",
+            GameOver::FivefoldRepetition => "Fivefold repetition, draw",
+            GameOver::SeventyFiveMoveRule => "Seventy-five-move rule, draw",
+        }
+    }
+
+    //the PGN result token
+    pub fn result(&self) -> &'static str {
+        match self {
+            GameOver::Checkmate(Color::White) => "1-0",
+            GameOver::Checkmate(Color::Black) => "0-1",
+            _ => "1/2-1/2",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum EngineError {
+    InvalidLocationError,
+    NoValidMovesErrror,
+    IllegalMoveError,
+}
+
+//Engine::try_interpret on any board
+pub(super) fn interpret_on(board: &Board, move_string: &str) -> Result<ChessMove, MoveParseError> {
+    interpret_san(board, move_string)
+        .or_else(|err| board.interpret_lan(move_string).map_err(|_| err))
+        .or_else(|err| board.interpret_iccf(move_string).map_err(|_| err))
+}
+
+impl<'a> Engine {
+    pub fn new(
+        board: Board,
+        static_eval_weights: StaticEvaluationWeights,
+        interest_eval_weights: InterestEvaluationWeights,
+        minimax_settings: MinimaxSettings,
+        search_settings: SearchSettings,
+    ) -> Engine {
+        Engine::with_weights(
+            board,
+            None,
+            0,
+            static_eval_weights,
+            interest_eval_weights,
+            InterestEvaluationWeights::endgame(),
+            minimax_settings,
+            search_settings,
+        )
+    }
+
+    //Engine::new on the position a FEN describes, with what's wrong with it otherwise.
+    //unlike a board, the FEN tells the castling rights exactly, and the draw rules count
+    //on from its halfmove clock
+    pub fn from_fen(
+        fen: &str,
+        static_eval_weights: StaticEvaluationWeights,
+        interest_eval_weights: InterestEvaluationWeights,
+        minimax_settings: MinimaxSettings,
+        search_settings: SearchSettings,
+    ) -> Result<Engine, FenError> {
+        let board = parse_fen(fen)?;
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let castling = fields.get(2).copied().map(CastlingRights::from_fen);
+        //parse_fen already made sure the clock is a number when it's there
+        let halfmove_clock = fields.get(4).map_or(0, |clock| clock.parse().unwrap());
+        Ok(Engine::with_weights(
+            board,
+            castling,
+            halfmove_clock,
+            static_eval_weights,
+            interest_eval_weights,
+            InterestEvaluationWeights::endgame(),
+            minimax_settings,
+            search_settings,
+        ))
+    }
+
+    //castling rights are taken from where the kings and rooks stand when they aren't known
+    #[allow(clippy::too_many_arguments)]
+    fn with_weights(
+        mut board: Board,
+        castling: Option<CastlingRights>,
+        halfmove_clock: usize,
+        static_eval_weights: StaticEvaluationWeights,
+        interest_eval_weights: InterestEvaluationWeights,
+        endgame_interest_weights: InterestEvaluationWeights,
+        minimax_settings: MinimaxSettings,
+        search_settings: SearchSettings,
+    ) -> Engine {
+        //the root may come from a FEN rather than a move
+        refresh_status(&mut board);
+        let castling = castling.unwrap_or_else(|| CastlingRights::of(&board));
+        let mut res = Engine {
+            moves: BinaryHeap::new(),
+            move_tree: MoveTree {
+                moves: BTreeMap::new(),
+                material: Material::of(&board),
+                castling,
+                moves_cache: BoardMovesCache::default(),
+                board_state: board.clone(),
+            },
+            interest_eval_weights,
+            endgame_interest_weights,
+            static_eval_weights,
+            minimax_settings,
+            search_settings,
+            nodes_searched: AtomicUsize::new(0),
+            search_counters: SearchCounters::default(),
+            tablebase: Arc::new(NoTablebase),
+            eval_profile: EvalProfile::Full,
+            shallowest_leaf: AtomicUsize::new(usize::MAX),
+            deepest_ply: AtomicUsize::new(0),
+            stop_search: AtomicBool::new(false),
+            node_limit: AtomicUsize::new(usize::MAX),
+            transpositions: HashMap::new(),
+            linked_moves: Vec::new(),
+            position_history: vec![position_key(&board, castling)],
+            move_history: Vec::new(),
+            game_start: (board, castling),
+            halfmove_clock,
+            tree_size: 1,
+            evicted: 0,
+            thread_count: 1,
+        };
+        res.generate_all_moves(VecDeque::new()).unwrap();
+        res
+    }
+
+    //back to the starting position with nothing analysed and no game history
+    pub fn reset(&mut self) {
+        self.set_position(Board::new());
+    }
+
+    //drops the tree, frontier and history of the previous game and starts over from
+    //board, keeping the weights and settings
+    pub fn set_position(&mut self, board: Board) {
+        self.reset_to(board, None);
+    }
+
+    //set_position with the castling rights, when they're known
+    pub(super) fn reset_to(&mut self, board: Board, castling: Option<CastlingRights>) {
+        let tablebase = self.tablebase.clone();
+        let eval_profile = self.eval_profile;
+        *self = Engine::with_weights(
+            board,
+            castling,
+            0,
+            self.static_eval_weights.clone(),
+            self.interest_eval_weights.clone(),
+            self.endgame_interest_weights.clone(),
+            self.minimax_settings.clone(),
+            self.search_settings.clone(),
+        );
+        self.tablebase = tablebase;
+        self.eval_profile = eval_profile;
+    }
+
+    //a copy of the tree and everything a search of it reads, without the frontier, so it
+    //can be searched while the engine itself goes on pondering
+    pub(super) fn snapshot(&self) -> Engine {
+        Engine {
+            moves: BinaryHeap::new(),
+            move_tree: self.move_tree.clone(),
+            static_eval_weights: self.static_eval_weights.clone(),
+            interest_eval_weights: self.interest_eval_weights.clone(),
+            endgame_interest_weights: self.endgame_interest_weights.clone(),
+            minimax_settings: self.minimax_settings.clone(),
+            search_settings: self.search_settings.clone(),
+            nodes_searched: AtomicUsize::new(0),
+            search_counters: SearchCounters::default(),
+            tablebase: self.tablebase.clone(),
+            eval_profile: self.eval_profile,
+            shallowest_leaf: AtomicUsize::new(usize::MAX),
+            deepest_ply: AtomicUsize::new(0),
+            stop_search: AtomicBool::new(false),
+            node_limit: AtomicUsize::new(usize::MAX),
+            transpositions: self.transpositions.clone(),
+            linked_moves: Vec::new(),
+            position_history: self.position_history.clone(),
+            move_history: self.move_history.clone(),
+            game_start: self.game_start.clone(),
+            halfmove_clock: self.halfmove_clock,
+            tree_size: self.tree_size,
+            evicted: 0,
+            thread_count: self.thread_count,
+        }
+    }
+
+    //which static evaluation leaves get from the next search on
+    pub fn set_eval_profile(&mut self, eval_profile: EvalProfile) {
+        self.eval_profile = eval_profile;
+    }
+
+    pub fn eval_profile(&self) -> EvalProfile {
+        self.eval_profile
+    }
+
+    //tables whose verdicts replace the static eval, and pick the root move, wherever
+    //they cover the position
+    pub fn set_tablebase(&mut self, tablebase: Arc<dyn Tablebase>) {
+        self.tablebase = tablebase;
+    }
+
+    //the interest weights the engine shifts to as the game nears its end. nodes already
+    //expanded keep the interests they were given
+    pub fn set_endgame_interest_weights(&mut self, weights: InterestEvaluationWeights) {
+        self.endgame_interest_weights = weights;
+    }
+
+    //the interest weights for the moves out of board
+    pub(super) fn interest_weights_at(&self, board: &Board) -> InterestEvaluationWeights {
+        self.interest_eval_weights
+            .at_phase(&self.endgame_interest_weights, game_phase(board))
+    }
+
+    pub fn search_settings(&self) -> &SearchSettings {
+        &self.search_settings
+    }
+
+    //takes effect from the next expansion and search. moves already on the frontier keep
+    //the depth cost they were queued with
+    pub fn set_search_settings(&mut self, search_settings: SearchSettings) {
+        self.search_settings = search_settings;
+    }
+
+    pub fn get_board(&'a self) -> &'a Board {
+        &self.move_tree.board_state
+    }
+
+    //go to a branch specified by the list of moves in location.
+    pub(super) fn go_to_location(
+        &'a mut self,
+        location: &VecDeque<ChessMove>,
+    ) -> Result<&'a mut MoveTree, EngineError> {
+        let mut current_tree = &mut self.move_tree;
+        for chess_move in location {
+            match current_tree.moves.get_mut(chess_move) {
+                Some(child_tree) => {
+                    current_tree = child_tree;
+                }
+                None => {
+                    return Err(EngineError::InvalidLocationError);
+                }
+            }
+        }
+        Ok(current_tree)
+    }
+
+    pub(super) fn get_location(
+        &'a self,
+        location: &VecDeque<ChessMove>,
+    ) -> Result<&'a MoveTree, EngineError> {
+        let mut current_tree = &self.move_tree;
+        for chess_move in location {
+            current_tree = current_tree
+                .moves
+                .get(chess_move)
+                .ok_or(EngineError::InvalidLocationError)?;
+        }
+        Ok(current_tree)
+    }
+
+    //if the node at location holds a position that was already expanded elsewhere
+    //(and not on its own path from the root), returns where that analysis lives
+    pub(super) fn transposition_of(
+        &self,
+        location: &VecDeque<ChessMove>,
+    ) -> Result<Option<VecDeque<ChessMove>>, EngineError> {
+        let node = self.get_location(location)?;
+        let canonical = match self.transpositions.get(&node.key()) {
+            Some(canonical) => canonical,
+            None => return Ok(None),
+        };
+        let is_ancestor = canonical.len() <= location.len()
+            && canonical.iter().zip(location.iter()).all(|(a, b)| a == b);
+        if is_ancestor || !self.get_location(canonical)?.same_position(node) {
+            return Ok(None);
+        }
+        Ok(Some(canonical.clone()))
+    }
+
+    //a leaf that transposes into an expanded node is searched through that node instead
+    pub(super) fn follow_transposition<'b>(
+        &'b self,
+        tree: &'b MoveTree,
+        path: &[u64],
+    ) -> &'b MoveTree {
+        if !tree.is_leaf() {
+            return tree;
+        }
+        let key = tree.key();
+        if path.contains(&key) {
+            return tree;
+        }
+        match self
+            .transpositions
+            .get(&key)
+            .and_then(|canonical| self.get_location(canonical).ok())
+        {
+            Some(linked) if !linked.is_leaf() && linked.same_position(tree) => linked,
+            _ => tree,
+        }
+    }
+
+    fn rebuild_transpositions(&mut self) {
+        let mut transpositions = HashMap::new();
+        let mut queue = VecDeque::from([(&self.move_tree, VecDeque::new())]);
+        while let Some((tree, location)) = queue.pop_front() {
+            if tree.is_leaf() {
+                continue;
+            }
+            transpositions.entry(tree.key()).or_insert(location.clone());
+            for (chess_move, child) in tree.moves.iter() {
+                let mut child_location = location.clone();
+                child_location.push_back(*chess_move);
+                queue.push_back((child, child_location));
+            }
+        }
+        self.transpositions = transpositions;
+    }
+
+    pub fn interpret_and_make_move(&mut self, move_string: &str) -> Result<(), EngineError> {
+        let chess_move = self
+            .try_interpret(move_string)
+            .map_err(|_| EngineError::IllegalMoveError)?;
+        self.make_move(&chess_move)?;
+        Ok(())
+    }
+
+    //interpret_and_make_move's parsing, saying what was wrong with the input. SAN comes
+    //first, then long algebraic, then ICCF numeric when neither can make sense of the move
+    pub fn try_interpret(&self, move_string: &str) -> Result<ChessMove, MoveParseError> {
+        interpret_on(&self.move_tree.board_state, move_string)
+    }
+
+    //the board chess_move leads to from the current position, copied out of the tree
+    //without playing it, for showing a move before it's made
+    pub fn peek_after(&self, chess_move: &ChessMove) -> Result<Board, EngineError> {
+        self.move_tree
+            .moves
+            .get(chess_move)
+            .map(|child| child.board_state.clone())
+            .ok_or(EngineError::IllegalMoveError)
+    }
+
+    pub fn make_move(&mut self, chess_move: &ChessMove) -> Result<(), EngineError> {
+        let progress = is_capture_or_pawn_move(&self.move_tree.board_state, chess_move);
+        self.move_tree = self
+            .move_tree
+            .moves
+            .get_mut(chess_move)
+            .ok_or(EngineError::IllegalMoveError)?
+            .clone();
+        self.moves
+            .retain(|x| x.location.len() > 0 && x.location[0] == *chess_move);
+
+        let mut new_moves = self.moves.clone().into_vec();
+
+        new_moves.iter_mut().for_each(|x| {
+            x.location.pop_front();
+        });
+
+        self.moves = BinaryHeap::from(new_moves);
+
+        self.linked_moves
+            .retain(|x| x.location.len() > 0 && x.location[0] == *chess_move);
+        self.linked_moves.iter_mut().for_each(|x| {
+            x.location.pop_front();
+        });
+
+        if self.move_tree.moves.is_empty() {
+            self.generate_all_moves(VecDeque::new()).unwrap();
+        }
+
+        self.tree_size = self.move_tree.size();
+        self.position_history.push(self.move_tree.key());
+        self.move_history.push(*chess_move);
+        self.halfmove_clock = match progress {
+            true => 0,
+            false => self.halfmove_clock + 1,
+        };
+
+        //the analysis a linked node pointed at may have been cut away with the old root
+        self.rebuild_transpositions();
+        let linked_moves = std::mem::take(&mut self.linked_moves);
+        for linked_move in linked_moves {
+            let mut location = linked_move.location.clone();
+            location.push_back(linked_move.valued_move.chess_move);
+            match self.transposition_of(&location)? {
+                Some(_) => self.linked_moves.push(linked_move),
+                None => self.moves.push(linked_move),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn move_history(&self) -> &[ChessMove] {
+        &self.move_history
+    }
+
+    //the current position through pretty_board, with the last move played marked
+    pub fn pretty_board(&self, ansi: bool) -> String {
+        pretty_board(&self.move_tree.board_state, self.move_history.last(), ansi)
+    }
+
+    pub fn halfmove_clock(&self) -> usize {
+        self.halfmove_clock
+    }
+
+    //how many times the current position has occurred in the game, this time included
+    pub fn times_seen(&self) -> usize {
+        let key = self.move_tree.key();
+        self.position_history
+            .iter()
+            .filter(|seen| **seen == key)
+            .count()
+    }
+
+    //one more repetition or a few more quiet moves would make the game drawable
+    pub fn draw_rule_imminent(&self) -> bool {
+        self.times_seen() >= 2 || self.halfmove_clock >= 90
+    }
+
+    //a draw the side to move could claim right now. unlike the repetition scoring in the
+    //search this only looks at the game actually played
+    pub fn can_claim_draw(&self) -> Option<DrawReason> {
+        if self.times_seen() >= 3 {
+            return Some(DrawReason::ThreefoldRepetition);
+        }
+        if self.halfmove_clock >= 100 {
+            return Some(DrawReason::FiftyMoveRule);
+        }
+        None
+    }
+
+    //whether the game is over. mate and stalemate are worked out from the moves left rather
+    //than trusted to the board, which FEN positions don't always have them filled in on
+    pub fn game_over(&self) -> Option<GameOver> {
+        match game_end(&self.move_tree.board_state) {
+            Some(GameEnd::Mated(color)) => return Some(GameOver::Checkmate(color.opposite())),
+            Some(GameEnd::StaleMate) => return Some(GameOver::Stalemate),
+            None => {}
+        }
+        if self.times_seen() >= 5 {
+            return Some(GameOver::FivefoldRepetition);
+        }
+        if self.halfmove_clock >= 150 {
+            return Some(GameOver::SeventyFiveMoveRule);
+        }
+        None
+    }
+
+    pub(super) fn generate_all_moves(
+        &mut self,
+        location: VecDeque<ChessMove>,
+    ) -> Result<(), EngineError> {
+        let interest_weights = self.interest_weights_at(&self.get_location(&location)?.board_state);
+        let depth_cost = self.search_settings.frontier_depth_cost;
+        let strategy = self.search_settings.frontier_strategy;
+        let thread_count = self.thread_count;
+
+        //the root is regenerated whenever a move empties the tree, with nothing else to
+        //run alongside it, so its moves are worked out in parallel
+        let at_root = location.is_empty();
+        let tree_mut = self.go_to_location(&location)?;
+        let key = tree_mut.key();
+        let children = match at_root {
+            true => parallel_legal_moves(&tree_mut.board_state, thread_count),
+            false => tree_mut.board_moves().legal_moves(&tree_mut.board_state),
+        };
+        for (chess_move, new_board) in children {
+            let child = tree_mut.child(&chess_move, new_board);
+            tree_mut.moves.insert(chess_move, child);
+        }
+        //once a move mates, its siblings aren't worth pondering
+        let mate_found = tree_mut
+            .moves
+            .values()
+            .any(|child| is_mate(&child.board_state));
+
+        let to_value: Vec<(&ChessMove, &MoveTree)> = tree_mut
+            .moves
+            .iter()
+            .filter(|(_, ending_board)| !mate_found || is_mate(&ending_board.board_state))
+            .collect();
+        let value_of = |(chess_move, ending_board): &(&ChessMove, &MoveTree)| ValuedMoveLocation {
+            valued_move: ValuedChessMove {
+                chess_move: **chess_move,
+                value: Engine::evaluate_interest(
+                    &interest_weights,
+                    chess_move,
+                    &tree_mut.board_state,
+                    &ending_board.board_state,
+                    tree_mut.board_moves(),
+                    ending_board.board_moves(),
+                )
+                .unwrap(),
+            },
+            location: location.clone(),
+            depth_cost,
+            strategy,
+        };
+        let new_moves: Vec<ValuedMoveLocation> = match at_root {
+            true => parallel_map(&to_value, thread_count, value_of),
+            false => to_value.iter().map(value_of).collect(),
+        };
+        self.tree_size += tree_mut.moves.len();
+        for new_move in new_moves {
+            self.moves.push(new_move);
+        }
+        self.transpositions.entry(key).or_insert(location);
+        Ok(())
+    }
+
+    pub fn print_tree(&self, depth: i32) {
+        self.move_tree.print_tree(0, depth);
+    }
+
+    //number of minimax nodes visited since the last reset
+    pub fn nodes_searched(&self) -> usize {
+        self.nodes_searched.load(AtomicOrdering::Relaxed)
+    }
+
+    pub fn reset_nodes_searched(&self) {
+        self.nodes_searched.store(0, AtomicOrdering::Relaxed);
+    }
+
+    //counts a visited node, stopping the search right where it reaches the node limit
+    //rather than whenever a poll happens to notice
+    pub(super) fn count_node(&self) {
+        let searched = self.nodes_searched.fetch_add(1, AtomicOrdering::Relaxed) + 1;
+        if searched >= self.node_limit.load(AtomicOrdering::Relaxed) {
+            self.stop_search.store(true, AtomicOrdering::Relaxed);
+        }
+    }
+
+    //the interest of every move from the current position, most interesting first.
+    //this is what the frontier started from, whether or not those moves were expanded since
+    pub fn root_move_interests(&self) -> Vec<(ChessMove, NotNan<f32>)> {
+        let interest_weights = self.interest_weights_at(&self.move_tree.board_state);
+        let mut interests: Vec<(ChessMove, NotNan<f32>)> = self
+            .move_tree
+            .moves
+            .iter()
+            .map(|(chess_move, child)| {
+                (
+                    *chess_move,
+                    Engine::evaluate_interest(
+                        &interest_weights,
+                        chess_move,
+                        &self.move_tree.board_state,
+                        &child.board_state,
+                        self.move_tree.board_moves(),
+                        child.board_moves(),
+                    )
+                    .unwrap(),
+                )
+            })
+            .collect();
+        interests.sort_by_key(|(_, interest)| std::cmp::Reverse(*interest));
+        interests
+    }
+
+    //the interest of every move from the current position, with how much it changes per
+    //unit of each interest weight, in the order of InterestEvaluationWeights::named_weights_mut.
+    //mating moves, whose interest is infinite whatever the weights, are left out
+    pub fn root_interest_gradients(&self) -> Vec<(ChessMove, NotNan<f32>, Vec<f32>)> {
+        let board = &self.move_tree.board_state;
+        let phase = game_phase(board);
+        let nudged: Vec<InterestEvaluationWeights> =
+            (0..self.interest_eval_weights.clone().named_weights_mut().len())
+                .map(|index| {
+                    let mut weights = self.interest_eval_weights.clone();
+                    *weights.named_weights_mut()[index].1 += 1.0;
+                    weights.at_phase(&self.endgame_interest_weights, phase)
+                })
+                .collect();
+        let interest_weights = self.interest_weights_at(board);
+        self.move_tree
+            .moves
+            .iter()
+            .filter_map(|(chess_move, child)| {
+                let interest = |weights: &InterestEvaluationWeights| {
+                    Engine::evaluate_interest(
+                        weights,
+                        chess_move,
+                        board,
+                        &child.board_state,
+                        self.move_tree.board_moves(),
+                        child.board_moves(),
+                    )
+                    .unwrap()
+                };
+                let value = interest(&interest_weights);
+                if value.is_infinite() {
+                    return None;
+                }
+                //interest is linear in the weights, so a unit nudge gives the slope
+                let gradient = nudged
+                    .iter()
+                    .map(|weights| *(interest(weights) - value))
+                    .collect();
+                Some((*chess_move, value, gradient))
+            })
+            .collect()
+    }
+
+    pub fn print_moves(&mut self) {
+        let mut temp_heap = self.moves.clone();
+
+        while let Some(valued_move) = temp_heap.pop() {
+            println!("{:#?}", valued_move);
+        }
+    }
+
+    //the n frontier entries that would be expanded next, best first
+    pub fn print_frontier(&self, n: usize) {
+        for line in self.frontier_lines(n) {
+            println!("{}", line);
+        }
+    }
+
+    //each entry as its priority, its interest before the depth cost, and the SAN of the
+    //moves leading to it followed by its own move
+    pub(super) fn frontier_lines(&self, n: usize) -> Vec<String> {
+        let mut temp_heap = self.moves.clone();
+        let mut lines = Vec::new();
+        while let Some(valued_move) = temp_heap.pop() {
+            if lines.len() == n {
+                break;
+            }
+            let mut tree = &self.move_tree;
+            let mut names = Vec::new();
+            for chess_move in valued_move
+                .location
+                .iter()
+                .chain([&valued_move.valued_move.chess_move])
+            {
+                names.push(san(&tree.board_state, chess_move).unwrap());
+                tree = &tree.moves[chess_move];
+            }
+            lines.push(format!(
+                "{:.2} ({:.2}) {}",
+                valued_move.value_accounted_for_distance(),
+                valued_move.valued_move.value,
+                names.join(" ")
+            ));
+        }
+        lines
+    }
+
+    pub fn think_next_move(&mut self) -> Result<(), EngineError> {
+        let next_move = self.moves.pop().ok_or(EngineError::NoValidMovesErrror)?;
+        let mut location = next_move.location.clone();
+        location.push_back(next_move.valued_move.chess_move);
+        if self.transposition_of(&location)?.is_some() {
+            self.linked_moves.push(next_move);
+            return Ok(());
+        }
+        self.generate_all_moves(location)?;
+        self.evicted += self.evict_if_needed();
+        Ok(())
+    }
+
+    pub fn tree_size(&self) -> usize {
+        self.tree_size
+    }
+
+    //how full the tree is, in permille of max_nodes, as UCI's hashfull reports it. the
+    //transposition table only indexes the tree, so the tree is where the memory goes.
+    //None while the tree is allowed to grow without bound
+    pub fn hashfull(&self) -> Option<usize> {
+        match self.minimax_settings.max_nodes {
+            0 => None,
+            max_nodes => Some((self.tree_size * 1000 / max_nodes).min(1000)),
+        }
+    }
+
+    //once the tree outgrows max_nodes, drops the least interesting unexpanded leaves
+    //(from the tree and the frontier together) until it is back to 90% of the cap.
+    //root moves are always kept. an evicted move is simply absent to minimax.
+    //returns how many nodes were dropped
+    pub(super) fn evict_if_needed(&mut self) -> usize {
+        let max_nodes = self.minimax_settings.max_nodes;
+        if max_nodes == 0 || self.tree_size <= max_nodes {
+            return 0;
+        }
+        let target = max_nodes * 9 / 10;
+        let before = self.tree_size;
+        //ascending, so the least promising entries come first
+        let entries = std::mem::take(&mut self.moves).into_sorted_vec();
+        let mut kept = Vec::new();
+        for entry in entries {
+            if self.tree_size <= target || entry.location.is_empty() {
+                kept.push(entry);
+                continue;
+            }
+            let chess_move = entry.valued_move.chess_move;
+            let parent = match self.go_to_location(&entry.location) {
+                Ok(parent) => parent,
+                Err(_) => continue,
+            };
+            match parent.moves.get(&chess_move) {
+                Some(child) if child.is_leaf() => {
+                    parent.moves.remove(&chess_move);
+                    self.tree_size -= 1;
+                }
+                _ => kept.push(entry),
+            }
+        }
+        self.moves = BinaryHeap::from(kept);
+        before - self.tree_size
+    }
+
+    //how many nodes were evicted to keep the tree under max_nodes since the last call,
+    //for the interface to report once a think is done
+    pub fn take_evicted(&mut self) -> usize {
+        std::mem::take(&mut self.evicted)
+    }
+}
+
+#[test]
+fn test_eviction_keeps_frontier_consistent() {
+    let mut minimax_settings = MinimaxSettings::new();
+    minimax_settings.max_nodes = 500;
+    let mut engine = Engine::new(
+        Board::new(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        minimax_settings,
+        SearchSettings::new(),
+    );
+    for _ in 0..100 {
+        engine.think_next_move().unwrap();
+    }
+    assert!(engine.tree_size() <= 500);
+    assert_eq!(engine.tree_size(), engine.move_tree.size());
+    //what was evicted is reported once
+    assert!(engine.take_evicted() > 0);
+    assert_eq!(engine.take_evicted(), 0);
+    for entry in engine.moves.iter() {
+        let parent = engine.get_location(&entry.location).unwrap();
+        assert!(parent.moves[&entry.valued_move.chess_move].is_leaf());
+    }
+}
+
+#[test]
+fn test_set_position_matches_new() {
+    let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+    let new_engine = |board: Board| {
+        Engine::new(
+            board,
+            StaticEvaluationWeights::new(),
+            InterestEvaluationWeights::new(),
+            MinimaxSettings::new(),
+            SearchSettings::new(),
+        )
+    };
+    let mut reused = new_engine(Board::new());
+    for _ in 0..200 {
+        reused.think_next_move().unwrap();
+    }
+    let (_, best_move) = reused.eval_and_best_move();
+    reused.make_move(&best_move.unwrap()).unwrap();
+    reused.set_position(make_board_from_fen(fen).unwrap());
+
+    let mut fresh = new_engine(make_board_from_fen(fen).unwrap());
+    assert!(reused.move_history().is_empty());
+    assert_eq!(reused.times_seen(), 1);
+    assert_eq!(reused.tree_size(), fresh.tree_size());
+    for _ in 0..200 {
+        reused.think_next_move().unwrap();
+        fresh.think_next_move().unwrap();
+    }
+    assert_eq!(reused.tree_size(), fresh.tree_size());
+    assert_eq!(reused.eval_and_best_move(), fresh.eval_and_best_move());
+
+    reused.reset();
+    assert!(same_position(reused.get_board(), &Board::new()));
+    assert_eq!(reused.tree_size(), new_engine(Board::new()).tree_size());
+}
+
+#[test]
+fn test_valued_move_location_ties() {
+    let board = Board::new();
+    let chess_move = board.interpret_move("e4").unwrap();
+    let entry = |depth_cost: f32| ValuedMoveLocation {
+        valued_move: ValuedChessMove {
+            value: NotNan::new(3.0).unwrap(),
+            chess_move,
+        },
+        location: VecDeque::new(),
+        depth_cost: NotNan::new(depth_cost).unwrap(),
+        strategy: FrontierStrategy::BestFirst,
+    };
+    //at the root the depth cost doesn't touch the value, so these tie on everything else
+    let (cheap, costly) = (entry(10.0), entry(15.0));
+    assert_eq!(
+        cheap.value_accounted_for_distance(),
+        costly.value_accounted_for_distance()
+    );
+    assert_ne!(cheap.cmp(&costly), Ordering::Equal);
+    assert_eq!(cheap.cmp(&costly), costly.cmp(&cheap).reverse());
+    assert_eq!(cheap.cmp(&cheap.clone()), Ordering::Equal);
+    let heap = BinaryHeap::from(vec![cheap, costly]);
+    assert_eq!(heap.len(), 2);
+}
+
+#[test]
+fn test_frontier_lines() {
+    let mut engine = Engine::new(
+        Board::new(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    for _ in 0..30 {
+        engine.think_next_move().unwrap();
+    }
+    let frontier_size = engine.moves.len();
+    let lines = engine.frontier_lines(5);
+    assert_eq!(lines.len(), 5);
+    assert_eq!(engine.moves.len(), frontier_size);
+
+    //the first line is the entry expanded next, spelled out from the root
+    let next = engine.moves.peek().unwrap();
+    let parent = engine.get_location(&next.location).unwrap();
+    let name = next
+        .valued_move
+        .chess_move
+        .name(&parent.board_state)
+        .unwrap();
+    assert!(lines[0].ends_with(&name));
+    assert_eq!(
+        lines[0].split_whitespace().count(),
+        2 + next.location.len() + 1
+    );
+    assert_eq!(
+        engine.frontier_lines(frontier_size + 10).len(),
+        frontier_size
+    );
+}
+
+#[test]
+fn test_game_over() {
+    let game_over = |fen: &str| {
+        Engine::new(
+            make_board_from_fen(fen).unwrap(),
+            StaticEvaluationWeights::new(),
+            InterestEvaluationWeights::new(),
+            MinimaxSettings::new(),
+            SearchSettings::new(),
+        )
+        .game_over()
+    };
+    //fool's mate
+    assert_eq!(
+        game_over("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3"),
+        Some(GameOver::Checkmate(Color::Black))
+    );
+    assert_eq!(
+        game_over("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"),
+        Some(GameOver::Stalemate)
+    );
+    assert_eq!(game_over("4k3/8/8/8/8/8/8/4K2R w K - 0 1"), None);
+}
+
+#[test]
+fn test_phase_interest_weights() {
+    let weights_at = |fen: &str| {
+        let board = make_board_from_fen(fen).unwrap();
+        Engine::new(
+            board.clone(),
+            StaticEvaluationWeights::new(),
+            InterestEvaluationWeights::new(),
+            MinimaxSettings::new(),
+            SearchSettings::new(),
+        )
+        .interest_weights_at(&board)
+    };
+    let opening = InterestEvaluationWeights::new();
+    let endgame = InterestEvaluationWeights::endgame();
+    let start = weights_at("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(start.king_moving_bonus, opening.king_moving_bonus);
+    assert_eq!(start.past_pawn_push_weight, opening.past_pawn_push_weight);
+    //kings and pawns only
+    let pawns = weights_at("8/4k3/8/3p4/3P4/8/4K3/8 w - - 0 1");
+    assert_eq!(pawns.king_moving_bonus, endgame.king_moving_bonus);
+    assert_eq!(pawns.past_pawn_push_weight, endgame.past_pawn_push_weight);
+    //with the queens off the penalty for moving a piece twice has faded by a third
+    let queenless = "r1b1k2r/pppp1ppp/2n2n2/2b1p3/2B1P3/2N2N2/PPPP1PPP/R1B1K2R w KQkq - 0 6";
+    let phase = game_phase(&make_board_from_fen(queenless).unwrap());
+    assert!((phase - 1.0 / 3.0).abs() < 1e-6);
+    assert!(
+        (weights_at(queenless).repeat_move_penalty - opening.repeat_move_penalty * (1.0 - phase))
+            .abs()
+            < 1e-6
+    );
+    //half the phase is halfway between
+    let half = opening.at_phase(&endgame, 0.5);
+    assert_eq!(
+        half.home_row_pawn_weight,
+        (opening.home_row_pawn_weight + endgame.home_row_pawn_weight) / 2.0
+    );
+}
+
+#[test]
+fn test_peek_after() {
+    let engine = Engine::new(
+        Board::new(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    let e4 = Board::new().interpret_move("e4").unwrap();
+    let mut expected = Board::new();
+    expected.make_legal_move(e4).unwrap();
+    assert!(engine.peek_after(&e4).unwrap() == expected);
+    assert!(*engine.get_board() == Board::new());
+    assert!(engine.move_history().is_empty());
+
+    let e5 = expected.interpret_move("e5").unwrap();
+    assert!(matches!(
+        engine.peek_after(&e5),
+        Err(EngineError::IllegalMoveError)
+    ));
+}
+
+#[test]
+fn test_frontier_strategy() -> Result<(), EngineError> {
+    let ponder = |frontier_strategy: FrontierStrategy, thinks: usize| {
+        let mut engine = Engine::new(
+            Board::new(),
+            StaticEvaluationWeights::new(),
+            InterestEvaluationWeights::new(),
+            MinimaxSettings::new(),
+            //no depth cost, so best-first goes wherever the interest is
+            SearchSettings {
+                frontier_strategy,
+                frontier_depth_cost: NotNan::new(0.0).unwrap(),
+                ..SearchSettings::new()
+            },
+        );
+        for _ in 0..thinks {
+            engine.think_next_move()?;
+        }
+        Ok::<Engine, EngineError>(engine)
+    };
+    let fully_expanded = |engine: &Engine| {
+        engine
+            .move_tree
+            .moves
+            .values()
+            .all(|child| !child.is_leaf())
+    };
+    let deepest_entry =
+        |engine: &Engine| engine.moves.iter().map(|entry| entry.location.len()).max();
+
+    //the 20 replies to the starting position come first, whatever their interest
+    let breadth = ponder(FrontierStrategy::BreadthFirst, 20)?;
+    assert!(fully_expanded(&breadth));
+    assert_eq!(deepest_entry(&breadth), Some(1));
+    let best = ponder(FrontierStrategy::BestFirst, 20)?;
+    assert!(!fully_expanded(&best));
+
+    //hybrid fills the first ply, then goes deep like best-first
+    let hybrid = ponder(FrontierStrategy::Hybrid { breadth_plies: 1 }, 20)?;
+    assert!(fully_expanded(&hybrid));
+    let hybrid = ponder(FrontierStrategy::Hybrid { breadth_plies: 1 }, 60)?;
+    assert!(deepest_entry(&hybrid) > Some(2));
+    Ok(())
+}
+
+#[test]
+fn test_hashfull() -> Result<(), EngineError> {
+    let new_engine = |max_nodes| {
+        Engine::new(
+            Board::new(),
+            StaticEvaluationWeights::new(),
+            InterestEvaluationWeights::new(),
+            MinimaxSettings {
+                max_nodes,
+                ..MinimaxSettings::new()
+            },
+            SearchSettings::new(),
+        )
+    };
+    assert_eq!(new_engine(0).hashfull(), None);
+    //the root's 20 moves and the tree's own root
+    assert_eq!(new_engine(1000).hashfull(), Some(21));
+    let mut engine = new_engine(100);
+    for _ in 0..20 {
+        engine.think_next_move()?;
+    }
+    assert!(engine.hashfull() <= Some(1000));
+    Ok(())
+}
+
+#[test]
+fn test_snapshot() -> Result<(), EngineError> {
+    let mut engine = Engine::new(
+        Board::new(),
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    for _ in 0..50 {
+        engine.think_next_move()?;
+    }
+    let snapshot = engine.snapshot();
+    assert!(snapshot.moves.is_empty());
+    assert_eq!(snapshot.eval_and_best_move(), engine.eval_and_best_move());
+    Ok(())
+}
+
+#[test]
+fn test_from_fen() {
+    let from_fen = |fen: &str| {
+        Engine::from_fen(
+            fen,
+            StaticEvaluationWeights::new(),
+            InterestEvaluationWeights::new(),
+            MinimaxSettings::new(),
+            SearchSettings::new(),
+        )
+        .map(|engine| board_fen(engine.get_board()))
+    };
+    let fen = "r3k2r/8/8/8/4Pp2/8/8/R3K2R b KQkq e3 0 1";
+    assert!(from_fen(fen).is_ok());
+    assert!(from_fen("4k3/8/8/8/8/8/8/4K3 w - -").is_ok());
+
+    let errors = [
+        ("4k3/8/8/8/8/8/8/4K3 w", FenError::FieldCount(2)),
+        ("4k3/8/8/8/8/8/4K3 w - - 0 1", FenError::RankCount(7)),
+        ("4k3/8/8/8/8/8/9/4K3 w - - 0 1", FenError::BadPiece('9')),
+        (
+            "4k3/8/8/8/8/8/ppppppppp/4K3 w - - 0 1",
+            FenError::RankLength(7),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 x - - 0 1",
+            FenError::BadTurn(String::from("x")),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w KK - 0 1",
+            FenError::BadCastling(String::from("KK")),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - e3 0 1",
+            FenError::BadEnPassant(String::from("e3")),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - - x 1",
+            FenError::BadClock(String::from("x")),
+        ),
+        (
+            "8/8/8/8/8/8/8/4K3 w - - 0 1",
+            FenError::Position(PositionError::MissingKing(Color::Black)),
+        ),
+    ];
+    for (fen, error) in errors {
+        assert_eq!(from_fen(fen), Err(error));
+    }
+    assert_eq!(
+        FenError::RankLength(7).to_string(),
+        "rank 2 doesn't add up to 8 squares"
+    );
+}
+
+#[test]
+fn test_from_fen_halfmove_clock() {
+    let from_fen = |fen: &str| {
+        Engine::from_fen(
+            fen,
+            StaticEvaluationWeights::new(),
+            InterestEvaluationWeights::new(),
+            MinimaxSettings::new(),
+            SearchSettings::new(),
+        )
+        .unwrap()
+    };
+    //one quiet move short of the fifty move rule
+    let mut engine = from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 60");
+    assert_eq!(engine.halfmove_clock(), 99);
+    assert!(engine.draw_rule_imminent());
+    assert_eq!(engine.can_claim_draw(), None);
+    let quiet = engine.get_board().clone().interpret_move("Ra2").unwrap();
+    engine.make_move(&quiet).unwrap();
+    assert_eq!(engine.can_claim_draw(), Some(DrawReason::FiftyMoveRule));
+    //a FEN without clocks starts counting from 0
+    assert_eq!(from_fen("4k3/8/8/8/8/8/8/R3K3 w - -").halfmove_clock(), 0);
+}
@@ -0,0 +1,450 @@
+use ordered_float::NotNan;
+
+#[derive(Debug, Clone)]
+pub struct StaticEvaluationWeights {
+    pub square_control_weight: f32,
+    //the side to move is the only one that can be in check, so these two always land
+    //together; they're kept apart so the initiative of giving check and the danger of
+    //being in it can be tuned separately
+    pub giving_check_weight: f32,
+    pub in_check_weight: f32,
+    pub value_weight: f32,
+    pub past_pawn_weight: f32,
+    pub castled_bonus: f32,
+    pub center_control_weight: f32,
+    pub knight_outpost_weight: f32,
+    //worth of having the move, for whoever is to play
+    pub tempo_weight: f32,
+    pub mobility_weight: f32,
+    pub trapped_piece_weight: f32,
+    //per flank where one side has more pawns, at full strength only in the endgame
+    pub pawn_majority_weight: f32,
+    pub king_tropism_weight: f32,
+    //for a king that can no longer castle and never did, fading out as the game
+    //reaches the endgame
+    pub lost_castling_penalty: f32,
+    //per rook on the file behind a passed pawn, its own or the enemy's, at full strength
+    //only in the endgame
+    pub rook_behind_passer_weight: f32,
+    //what's left of the eval in an endgame of opposite-colored bishops, which tends to be
+    //drawn whatever the pawn count says
+    pub ocb_draw_factor: f32,
+}
+
+//how pondering picks the next frontier entry to expand
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum FrontierStrategy {
+    //the most interesting entry, less frontier_depth_cost for every ply it is from the root
+    #[default]
+    BestFirst,
+    //the shallowest entry, interest only breaking ties, so the tree fills ply by ply
+    BreadthFirst,
+    //breadth-first until every line reaches breadth_plies, best-first past that
+    Hybrid {
+        breadth_plies: usize,
+    },
+}
+
+impl FrontierStrategy {
+    //whether an entry at ply (counting its own move) is picked breadth-first
+    pub(super) fn breadth_first_at(&self, ply: usize) -> bool {
+        match self {
+            FrontierStrategy::BestFirst => false,
+            FrontierStrategy::BreadthFirst => true,
+            FrontierStrategy::Hybrid { breadth_plies } => ply <= *breadth_plies,
+        }
+    }
+}
+
+//how hard the engine searches, in one place: how deep minimax looks, how pondering trades
+//depth for breadth, and how much pondering a turn gets
+#[derive(Debug, Clone)]
+pub struct SearchSettings {
+    pub min_depth: i32,
+    //minimax scores a node statically at this ply even if the tree goes on below it
+    pub max_depth: i32,
+    //interest lost per ply of distance from the root when the frontier picks what to
+    //expand next. lower values make pondering go deeper before it goes wider. this only
+    //shapes which nodes exist; how minimax scores leaves shallower than min_depth is
+    //up to min_depth and MinimaxSettings, so tuning one doesn't move the other
+    pub frontier_depth_cost: NotNan<f32>,
+    //only entries picked best-first lose frontier_depth_cost with distance
+    pub frontier_strategy: FrontierStrategy,
+    //frontier expansions pondered before each move the engine picks
+    pub expansion_budget: usize,
+    //interest within which a pondering worker other than the first takes frontier entries
+    //as tied, picking among them at random, so workers drift apart on near-equal moves
+    //without reordering clearly better ones. 0, the default, keeps every worker on the
+    //same ordering
+    pub ordering_epsilon: f32,
+    //each worker's tie breaks are seeded from this plus its index
+    pub ordering_seed: u64,
+}
+
+impl SearchSettings {
+    pub fn new() -> SearchSettings {
+        SearchSettings {
+            min_depth: 2,
+            max_depth: 1000,
+            frontier_depth_cost: NotNan::new(15.0).unwrap(),
+            frontier_strategy: FrontierStrategy::BestFirst,
+            expansion_budget: 5000,
+            ordering_epsilon: 0.0,
+            ordering_seed: 0x7e405,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MinimaxSettings {
+    //nodes a single unexpanded leaf may generate on the fly to reach min_depth.
+    //0 falls back to treating such leaves as unusable
+    pub on_the_fly_budget: usize,
+    //plies of captures searched past a leaf before trusting its static eval. 0 disables
+    //quiescence, which is the default since it multiplies the cost of every leaf
+    pub quiescence_depth: i32,
+    //slack added to a capture's gain before delta pruning gives up on it
+    pub delta_margin: f32,
+    //resign once the eval has been below this (from the engine's side) for resign_moves moves
+    pub resign_threshold: f32,
+    pub resign_moves: usize,
+    //claim a draw when the eval is within this of zero and a draw rule is about to apply
+    pub draw_threshold: f32,
+    //claim a draw the rules allow unless the eval (from the engine's side) is above this
+    pub claim_draw_threshold: f32,
+    //agree to a draw once the eval has stayed within agreement_draw_band of zero for
+    //agreement_draw_moves moves in a row, with no capture or pawn move in as many plies.
+    //0 moves, the default, disables it
+    pub agreement_draw_band: f32,
+    pub agreement_draw_moves: usize,
+    //nodes the move tree may hold before the least interesting unexpanded leaves are
+    //evicted. 0 lets it grow without bound
+    pub max_nodes: usize,
+    //futility margins by plies left to the horizon of the tree, nearest first. a quiet
+    //move is skipped when the static eval plus its margin can't reach the window.
+    //empty disables futility pruning
+    pub futility_margins: Vec<f32>,
+    //razoring margins by plies left to the horizon, nearest first. a node whose static
+    //eval plus its margin can't reach the window is scored by quiescence instead of
+    //searching its moves. empty disables razoring
+    pub razor_margins: Vec<f32>,
+}
+
+impl MinimaxSettings {
+    pub fn new() -> MinimaxSettings {
+        MinimaxSettings {
+            on_the_fly_budget: 1000,
+            quiescence_depth: 0,
+            delta_margin: 2.0,
+            resign_threshold: -8.0,
+            resign_moves: 3,
+            draw_threshold: 0.3,
+            claim_draw_threshold: 1.0,
+            agreement_draw_band: 0.25,
+            agreement_draw_moves: 0,
+            max_nodes: 0,
+            futility_margins: vec![1.5, 4.0],
+            razor_margins: vec![2.5, 5.0],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InterestEvaluationWeights {
+    pub square_control_weight: f32,
+    pub capture_weight: f32,
+    pub home_row_pawn_weight: f32,
+    //for moves that give check
+    pub check_weight: f32,
+    //for moves played while in check, so the answers to a check get expanded ahead of
+    //quiet moves elsewhere in the tree
+    pub check_evasion_weight: f32,
+    pub king_moving_bonus: f32,
+    pub queen_moving_bonus: f32,
+    pub rook_moving_bonus: f32,
+    pub minor_piece_moving_bouns: f32,
+    pub attack_weight: f32,
+    //weigh attacked pieces by how well they're defended, rather than the plain sum of
+    //everything attacked
+    pub attack_by_defenders: bool,
+    pub kingside_castling_bonus: f32,
+    pub queenside_castling_bonus: f32,
+    pub checking_underpromotion_bonus: f32,
+    //for moving a knight, bishop or queen that already left its start square while a
+    //minor piece of the same side hasn't
+    pub repeat_move_penalty: f32,
+    //per row a passed pawn's push reaches
+    pub past_pawn_push_weight: f32,
+}
+
+impl InterestEvaluationWeights {
+    pub fn new() -> Self {
+        InterestEvaluationWeights {
+            square_control_weight: 0.2,
+            capture_weight: 2.5,
+            home_row_pawn_weight: 3.5,
+            check_weight: 10.0,
+            check_evasion_weight: 5.0,
+            king_moving_bonus: -2.0,
+            queen_moving_bonus: 1.50,
+            rook_moving_bonus: 3.0,
+            minor_piece_moving_bouns: 7.0,
+            attack_weight: 0.75,
+            attack_by_defenders: true,
+            kingside_castling_bonus: 20.0,
+            queenside_castling_bonus: 15.0,
+            checking_underpromotion_bonus: 6.0,
+            repeat_move_penalty: 4.0,
+            past_pawn_push_weight: 1.0,
+        }
+    }
+
+    //the weights the engine moves toward as game_phase reaches 1.0: the king and passed
+    //pawns come forward, and developing, castling and moving a piece twice stop mattering
+    pub fn endgame() -> Self {
+        InterestEvaluationWeights {
+            home_row_pawn_weight: 0.5,
+            king_moving_bonus: 2.0,
+            minor_piece_moving_bouns: 3.0,
+            kingside_castling_bonus: 0.0,
+            queenside_castling_bonus: 0.0,
+            past_pawn_push_weight: 2.0,
+            repeat_move_penalty: 0.0,
+            ..InterestEvaluationWeights::new()
+        }
+    }
+
+    //these weights at game_phase 0.0, endgame's at 1.0 and in proportion in between.
+    //switches like attack_by_defenders are taken from these
+    pub fn at_phase(&self, endgame: &InterestEvaluationWeights, phase: f32) -> Self {
+        let mix = |opening: f32, endgame: f32| opening + (endgame - opening) * phase;
+        InterestEvaluationWeights {
+            square_control_weight: mix(self.square_control_weight, endgame.square_control_weight),
+            capture_weight: mix(self.capture_weight, endgame.capture_weight),
+            home_row_pawn_weight: mix(self.home_row_pawn_weight, endgame.home_row_pawn_weight),
+            check_weight: mix(self.check_weight, endgame.check_weight),
+            check_evasion_weight: mix(self.check_evasion_weight, endgame.check_evasion_weight),
+            king_moving_bonus: mix(self.king_moving_bonus, endgame.king_moving_bonus),
+            queen_moving_bonus: mix(self.queen_moving_bonus, endgame.queen_moving_bonus),
+            rook_moving_bonus: mix(self.rook_moving_bonus, endgame.rook_moving_bonus),
+            minor_piece_moving_bouns: mix(
+                self.minor_piece_moving_bouns,
+                endgame.minor_piece_moving_bouns,
+            ),
+            attack_weight: mix(self.attack_weight, endgame.attack_weight),
+            attack_by_defenders: self.attack_by_defenders,
+            kingside_castling_bonus: mix(
+                self.kingside_castling_bonus,
+                endgame.kingside_castling_bonus,
+            ),
+            queenside_castling_bonus: mix(
+                self.queenside_castling_bonus,
+                endgame.queenside_castling_bonus,
+            ),
+            checking_underpromotion_bonus: mix(
+                self.checking_underpromotion_bonus,
+                endgame.checking_underpromotion_bonus,
+            ),
+            repeat_move_penalty: mix(self.repeat_move_penalty, endgame.repeat_move_penalty),
+            past_pawn_push_weight: mix(self.past_pawn_push_weight, endgame.past_pawn_push_weight),
+        }
+    }
+
+    //every weight by its field name, for code that treats them all alike. switches like
+    //attack_by_defenders aren't weights and are left out
+    pub fn named_weights_mut(&mut self) -> Vec<(&'static str, &mut f32)> {
+        vec![
+            ("square_control_weight", &mut self.square_control_weight),
+            ("capture_weight", &mut self.capture_weight),
+            ("home_row_pawn_weight", &mut self.home_row_pawn_weight),
+            ("check_weight", &mut self.check_weight),
+            ("check_evasion_weight", &mut self.check_evasion_weight),
+            ("king_moving_bonus", &mut self.king_moving_bonus),
+            ("queen_moving_bonus", &mut self.queen_moving_bonus),
+            ("rook_moving_bonus", &mut self.rook_moving_bonus),
+            (
+                "minor_piece_moving_bouns",
+                &mut self.minor_piece_moving_bouns,
+            ),
+            ("attack_weight", &mut self.attack_weight),
+            ("kingside_castling_bonus", &mut self.kingside_castling_bonus),
+            (
+                "queenside_castling_bonus",
+                &mut self.queenside_castling_bonus,
+            ),
+            (
+                "checking_underpromotion_bonus",
+                &mut self.checking_underpromotion_bonus,
+            ),
+            ("repeat_move_penalty", &mut self.repeat_move_penalty),
+            ("past_pawn_push_weight", &mut self.past_pawn_push_weight),
+        ]
+    }
+}
+
+//a personality for the engine, as a pair of static and interest weights that suit each
+//other, for those who'd rather not tune every weight themselves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    //keeps its king safe and its pieces covered, and checks only when it pays
+    Solid,
+    //the default weights
+    Balanced,
+    //goes after the enemy king, taking some risk with its own
+    Aggressive,
+    //aggressive, and happy to give material for activity and tempo
+    Gambit,
+}
+
+impl Style {
+    pub const ALL: [Style; 4] = [
+        Style::Solid,
+        Style::Balanced,
+        Style::Aggressive,
+        Style::Gambit,
+    ];
+
+    //the lowercase name, as --style takes it
+    pub fn name(&self) -> &'static str {
+        match self {
+            Style::Solid => "solid",
+            Style::Balanced => "balanced",
+            Style::Aggressive => "aggressive",
+            Style::Gambit => "gambit",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Style> {
+        Style::ALL
+            .into_iter()
+            .find(|style| style.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn weights(&self) -> (StaticEvaluationWeights, InterestEvaluationWeights) {
+        let (static_weights, interest_weights) = (
+            StaticEvaluationWeights::new(),
+            InterestEvaluationWeights::new(),
+        );
+        match self {
+            Style::Solid => (
+                StaticEvaluationWeights {
+                    giving_check_weight: 1.0,
+                    castled_bonus: 0.7,
+                    trapped_piece_weight: 2.0,
+                    king_tropism_weight: 0.05,
+                    lost_castling_penalty: 0.6,
+                    ..static_weights
+                },
+                InterestEvaluationWeights {
+                    check_weight: 6.0,
+                    attack_weight: 0.5,
+                    kingside_castling_bonus: 25.0,
+                    queenside_castling_bonus: 18.0,
+                    ..interest_weights
+                },
+            ),
+            Style::Balanced => (static_weights, interest_weights),
+            Style::Aggressive => (
+                StaticEvaluationWeights {
+                    giving_check_weight: 2.0,
+                    in_check_weight: 1.2,
+                    castled_bonus: 0.4,
+                    mobility_weight: 0.04,
+                    king_tropism_weight: 0.2,
+                    lost_castling_penalty: 0.3,
+                    ..static_weights
+                },
+                InterestEvaluationWeights {
+                    square_control_weight: 0.3,
+                    check_weight: 14.0,
+                    attack_weight: 1.1,
+                    ..interest_weights
+                },
+            ),
+            Style::Gambit => {
+                let (static_weights, interest_weights) = Style::Aggressive.weights();
+                (
+                    StaticEvaluationWeights {
+                        value_weight: 0.85,
+                        center_control_weight: 0.15,
+                        tempo_weight: 0.06,
+                        mobility_weight: 0.05,
+                        ..static_weights
+                    },
+                    InterestEvaluationWeights {
+                        capture_weight: 2.0,
+                        minor_piece_moving_bouns: 8.5,
+                        repeat_move_penalty: 6.0,
+                        ..interest_weights
+                    },
+                )
+            }
+        }
+    }
+}
+
+impl StaticEvaluationWeights {
+    pub fn new() -> StaticEvaluationWeights {
+        StaticEvaluationWeights {
+            square_control_weight: 0.05,
+            giving_check_weight: 1.5,
+            in_check_weight: 1.5,
+            value_weight: 1.0,
+            past_pawn_weight: 0.5,
+            castled_bonus: 0.5,
+            center_control_weight: 0.1,
+            knight_outpost_weight: 0.4,
+            tempo_weight: 0.03,
+            mobility_weight: 0.03,
+            trapped_piece_weight: 1.5,
+            pawn_majority_weight: 0.3,
+            king_tropism_weight: 0.1,
+            lost_castling_penalty: 0.4,
+            rook_behind_passer_weight: 0.5,
+            ocb_draw_factor: 0.5,
+        }
+    }
+
+    //every weight by its field name, for code that treats them all alike
+    pub fn named_weights_mut(&mut self) -> Vec<(&'static str, &mut f32)> {
+        vec![
+            ("square_control_weight", &mut self.square_control_weight),
+            ("giving_check_weight", &mut self.giving_check_weight),
+            ("in_check_weight", &mut self.in_check_weight),
+            ("value_weight", &mut self.value_weight),
+            ("past_pawn_weight", &mut self.past_pawn_weight),
+            ("castled_bonus", &mut self.castled_bonus),
+            ("center_control_weight", &mut self.center_control_weight),
+            ("knight_outpost_weight", &mut self.knight_outpost_weight),
+            ("tempo_weight", &mut self.tempo_weight),
+            ("mobility_weight", &mut self.mobility_weight),
+            ("trapped_piece_weight", &mut self.trapped_piece_weight),
+            ("pawn_majority_weight", &mut self.pawn_majority_weight),
+            ("king_tropism_weight", &mut self.king_tropism_weight),
+            ("lost_castling_penalty", &mut self.lost_castling_penalty),
+            (
+                "rook_behind_passer_weight",
+                &mut self.rook_behind_passer_weight,
+            ),
+            ("ocb_draw_factor", &mut self.ocb_draw_factor),
+        ]
+    }
+}
+
+#[test]
+fn test_style_presets() {
+    for style in Style::ALL {
+        assert_eq!(Style::from_name(style.name()), Some(style));
+    }
+    assert_eq!(Style::from_name("Gambit"), Some(Style::Gambit));
+    assert_eq!(Style::from_name("reckless"), None);
+
+    let (solid_static, solid_interest) = Style::Solid.weights();
+    let (aggressive_static, aggressive_interest) = Style::Aggressive.weights();
+    assert!(aggressive_interest.attack_weight > solid_interest.attack_weight);
+    assert!(aggressive_interest.check_weight > solid_interest.check_weight);
+    assert!(aggressive_static.king_tropism_weight > solid_static.king_tropism_weight);
+    assert!(aggressive_static.lost_castling_penalty < solid_static.lost_castling_penalty);
+    let (gambit_static, _) = Style::Gambit.weights();
+    assert!(gambit_static.value_weight < aggressive_static.value_weight);
+}
@@ -0,0 +1,108 @@
+use std::{fs, sync::Arc};
+
+use rust_chess::chess::chess::{make_board_from_fen, Board};
+
+use crate::engine::teros_engine::{
+    Engine, InterestEvaluationWeights, MinimaxSettings, SearchSettings, StaticEvaluationWeights,
+};
+
+//an EPD record is a FEN without the move counters followed by "opcode operands;" pairs.
+//plain FEN lines are accepted too
+fn parse_line(line: &str) -> (String, Vec<String>) {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let has_counters =
+        tokens.len() >= 6 && tokens[4].parse::<u32>().is_ok() && tokens[5].parse::<u32>().is_ok();
+    let (fen, operations) = match has_counters {
+        true => (tokens[..6].join(" "), &tokens[6..]),
+        false => {
+            let split = tokens.len().min(4);
+            (
+                format!("{} 0 1", tokens[..split].join(" ")),
+                &tokens[split..],
+            )
+        }
+    };
+    let mut best_moves = Vec::new();
+    for operation in operations.join(" ").split(';') {
+        let mut words = operation.split_whitespace();
+        if words.next() == Some("bm") {
+            best_moves.extend(words.map(String::from));
+        }
+    }
+    (fen, best_moves)
+}
+
+//check and annotation marks don't decide whether a move matches
+fn bare_san(san: &str) -> &str {
+    san.trim_end_matches(['+', '#', '!', '?'])
+}
+
+fn analyse(
+    board: Board,
+    thread_count: usize,
+    search_settings: &SearchSettings,
+) -> (String, String) {
+    let engine = Engine::new(
+        board,
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        MinimaxSettings::new(),
+        search_settings.clone(),
+    );
+    let budget = search_settings.expansion_budget;
+    let engine = Arc::new(engine.multi_thread_think_next_num_moves(thread_count, budget, None));
+    let (eval, best_move) = engine.clone().parallel_eval_and_best_move(thread_count);
+    let best_move = match best_move {
+        Some(chess_move) => chess_move.name(engine.get_board()).unwrap(),
+        None => String::from("none"),
+    };
+    (best_move, eval.clamp_for_display().to_string())
+}
+
+//prints "fen bestmove eval" for every line of the file, and a solved count for lines with bm
+pub fn run_epd(path: &str, thread_count: usize, search_settings: &SearchSettings) {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) => {
+            println!("could not read {} ({})", path, err);
+            return;
+        }
+    };
+    let mut solved = 0;
+    let mut with_best_moves = 0;
+    for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+        let (fen, best_moves) = parse_line(line);
+        let board = match make_board_from_fen(&fen) {
+            Ok(board) => board,
+            Err(err) => {
+                println!("{} error ({:?})", fen, err);
+                continue;
+            }
+        };
+        let (best_move, eval) = analyse(board, thread_count, search_settings);
+        if best_moves.is_empty() {
+            println!("{} {} {}", fen, best_move, eval);
+            continue;
+        }
+        with_best_moves += 1;
+        let pass = best_moves
+            .iter()
+            .any(|expected| bare_san(expected) == bare_san(&best_move));
+        if pass {
+            solved += 1;
+        }
+        println!(
+            "{} {} {} {}",
+            fen,
+            best_move,
+            eval,
+            match pass {
+                true => "pass",
+                false => "fail",
+            }
+        );
+    }
+    if with_best_moves > 0 {
+        println!("solved {}/{}", solved, with_best_moves);
+    }
+}
@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+#[cfg(test)]
+use ordered_float::NotNan;
+use rust_chess::chess::chess::{Board, ChessMove};
+
+use crate::engine::teros_engine::{board_fen, Eval};
+
+//one line of JSON describing an analysis, for frontends that would rather not scrape the
+//human readable output. evals are from white's point of view
+pub fn analysis_json(
+    board: &Board,
+    eval: &Eval,
+    best_move: Option<ChessMove>,
+    principal_variation: &[String],
+) -> String {
+    format!(
+        "{{{}}}",
+        analysis_fields(board, eval, best_move, principal_variation).join(",")
+    )
+}
+
+//analysis_json for a move the engine played, with the nodes it searched and the
+//milliseconds it thought for. one of these per line makes a log of a game's decisions
+pub fn decision_json(
+    board: &Board,
+    eval: &Eval,
+    chess_move: ChessMove,
+    principal_variation: &[String],
+    nodes: usize,
+    elapsed: Duration,
+) -> String {
+    let mut fields = analysis_fields(board, eval, Some(chess_move), principal_variation);
+    fields.push(format!("\"nodes\":{}", nodes));
+    fields.push(format!("\"time_ms\":{}", elapsed.as_millis()));
+    format!("{{{}}}", fields.join(","))
+}
+
+fn analysis_fields(
+    board: &Board,
+    eval: &Eval,
+    best_move: Option<ChessMove>,
+    principal_variation: &[String],
+) -> Vec<String> {
+    let mut fields = vec![format!("\"fen\":{}", json_string(&board_fen(board)))];
+    let eval = eval.clamp_for_display();
+    match (eval.centipawns(), eval.mate_in_moves()) {
+        (Some(centipawns), _) => fields.push(format!("\"eval_cp\":{}", centipawns)),
+        (_, Some(moves)) => fields.push(format!("\"mate\":{}", moves)),
+        (None, None) => {}
+    }
+    fields.push(format!(
+        "\"bestmove\":{}",
+        match best_move {
+            Some(chess_move) => json_string(&chess_move.name(board).unwrap()),
+            None => String::from("null"),
+        }
+    ));
+    let pv: Vec<String> = principal_variation
+        .iter()
+        .map(|san| json_string(san))
+        .collect();
+    fields.push(format!("\"pv\":[{}]", pv.join(",")));
+    fields
+}
+
+fn json_string(text: &str) -> String {
+    let mut res = String::from("\"");
+    for character in text.chars() {
+        match character {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            character if character.is_control() => {
+                res.push_str(&format!("\\u{:04x}", character as u32))
+            }
+            character => res.push(character),
+        }
+    }
+    res.push('"');
+    res
+}
+
+#[test]
+fn test_analysis_json() {
+    let board = Board::new();
+    let chess_move = board.interpret_move("e4").unwrap();
+    let eval = Eval::MateIn(rust_chess::chess::chess::Color::Black, 3);
+    let pv = [String::from("e4"), String::from("e5")];
+    assert_eq!(
+        analysis_json(&board, &eval, Some(chess_move), &pv),
+        "{\"fen\":\"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\",\"mate\":-2,\"bestmove\":\"e4\",\"pv\":[\"e4\",\"e5\"]}"
+    );
+    assert_eq!(
+        decision_json(
+            &board,
+            &Eval::Numeric(NotNan::new(0.25).unwrap()),
+            chess_move,
+            &pv,
+            1200,
+            Duration::from_millis(340)
+        ),
+        "{\"fen\":\"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\",\"eval_cp\":25,\"bestmove\":\"e4\",\"pv\":[\"e4\",\"e5\"],\"nodes\":1200,\"time_ms\":340}"
+    );
+    assert_eq!(json_string("a\"b\\c"), "\"a\\\"b\\\\c\"");
+}
@@ -0,0 +1,144 @@
+use std::{fs, io::ErrorKind};
+
+#[cfg(test)]
+use ordered_float::NotNan;
+#[cfg(test)]
+use rust_chess::chess::chess::Board;
+
+use crate::engine::teros_engine::InterestEvaluationWeights;
+use crate::selfplay::{self, Decision, SelfPlaySettings};
+use crate::weights_file::{read_weights, weights_toml};
+
+//moves played at random at the start of every learning game, so the games differ
+const RANDOM_PLIES: usize = 4;
+//most a weight moves after one game
+const MAX_STEP: f32 = 0.1;
+
+pub struct LearnSettings {
+    //where the weights are read from and written back to after every game. a missing file
+    //starts from the default weights
+    pub path: String,
+    pub games: usize,
+    //game n opens with random moves from seed + n
+    pub seed: u64,
+    pub learning_rate: f32,
+}
+
+//the table the weights are saved under
+const SECTION: &str = "interest_evaluation_weights";
+
+//the weights saved at path, the defaults where the file doesn't give one
+fn load_weights(path: &str) -> Result<InterestEvaluationWeights, String> {
+    let mut weights = InterestEvaluationWeights::new();
+    match fs::read_to_string(path) {
+        Ok(text) => read_weights(&text, weights.named_weights_mut())?,
+        Err(err) if err.kind() == ErrorKind::NotFound => {}
+        Err(err) => return Err(format!("could not read {} ({})", path, err)),
+    }
+    Ok(weights)
+}
+
+//the direction each weight should move in to rank the moves the engine played above the
+//ones the frontier found more interesting at the time: the played move's gradient less the
+//mean gradient of those ranked over it, averaged over every decision. returns how many
+//decisions had a move ranked over the played one as well
+fn weight_direction(decisions: &[Decision], weight_count: usize) -> (Vec<f32>, usize) {
+    let mut direction = vec![0.0; weight_count];
+    let mut misranked = 0;
+    for decision in decisions {
+        let played = match decision
+            .interests
+            .iter()
+            .find(|(chess_move, _, _)| *chess_move == decision.played)
+        {
+            Some(played) => played,
+            //mates are left out of the interests, and there's nothing to learn from one
+            None => continue,
+        };
+        let above: Vec<&Vec<f32>> = decision
+            .interests
+            .iter()
+            .filter(|(_, interest, _)| *interest > played.1)
+            .map(|(_, _, gradient)| gradient)
+            .collect();
+        if above.is_empty() {
+            continue;
+        }
+        misranked += 1;
+        for (index, slope) in direction.iter_mut().enumerate() {
+            let mean_above =
+                above.iter().map(|gradient| gradient[index]).sum::<f32>() / above.len() as f32;
+            *slope += played.2[index] - mean_above;
+        }
+    }
+    if !decisions.is_empty() {
+        for slope in direction.iter_mut() {
+            *slope /= decisions.len() as f32;
+        }
+    }
+    (direction, misranked)
+}
+
+//plays learn.games self-play games, nudging the interest weights after each one and saving
+//them to learn.path. with a single thread the whole run is repeatable from the seed
+pub fn run_learning(settings: &SelfPlaySettings, learn: &LearnSettings) {
+    let mut weights = match load_weights(&learn.path) {
+        Ok(weights) => weights,
+        Err(message) => {
+            println!("{}", message);
+            return;
+        }
+    };
+    for game_index in 0..learn.games {
+        let game_settings = SelfPlaySettings {
+            interest_weights: weights.clone(),
+            random_plies: RANDOM_PLIES,
+            seed: learn.seed.wrapping_add(game_index as u64),
+            ..settings.clone()
+        };
+        let game = match selfplay::play_game(&game_settings, true) {
+            Ok(game) => game,
+            Err(message) => {
+                println!("{}", message);
+                return;
+            }
+        };
+        println!("{}\n", game.pgn);
+
+        let mut named = weights.named_weights_mut();
+        let (direction, misranked) = weight_direction(&game.decisions, named.len());
+        for ((_, weight), slope) in named.iter_mut().zip(direction) {
+            **weight += (learn.learning_rate * slope).clamp(-MAX_STEP, MAX_STEP);
+        }
+        if let Err(err) = fs::write(
+            &learn.path,
+            weights_toml(SECTION, weights.named_weights_mut()),
+        ) {
+            println!("could not write {} ({})", learn.path, err);
+            return;
+        }
+        println!(
+            "game {}: {} of {} moves ranked below the frontier's favorite, weights saved to {}\n",
+            game_index + 1,
+            misranked,
+            game.decisions.len(),
+            learn.path
+        );
+    }
+}
+
+#[test]
+fn test_learning_step() {
+    //the played move scores on the first weight, the move the frontier liked on the second
+    let moves = Board::new().generate_moves(1, 4).unwrap();
+    let decisions = [Decision {
+        played: moves[0],
+        interests: vec![
+            (moves[0], NotNan::new(1.0).unwrap(), vec![1.0, 0.0]),
+            (moves[1], NotNan::new(2.0).unwrap(), vec![0.0, 1.0]),
+        ],
+    }];
+    let (direction, misranked) = weight_direction(&decisions, 2);
+    assert_eq!(misranked, 1);
+    assert!(direction[0] > 0.0 && direction[1] < 0.0);
+}
@@ -1,45 +1,178 @@
+mod bench;
 mod engine;
+mod epd;
+mod json;
+mod learn;
+mod pgn;
+mod selfplay;
+mod tune;
+mod uci;
+mod weights_file;
 
 use std::{
     env,
+    fs::OpenOptions,
     io::stdin,
-    io::{stdout, Write},
+    io::{stdout, IsTerminal, Write},
     sync::Arc,
+    time::{Duration, Instant},
 };
 
 use rust_chess::chess::{
     self,
-    chess::{make_board_from_fen, Board, Color},
+    chess::{Board, Color},
 };
 use std::thread;
 use text_io::read;
 
 use crate::engine::teros_engine::{
-    InterestEvaluationWeights, MinimaxSettings, StaticEvaluationWeights,
+    chess960_fen, parse_fen, san, san_line, Adjudication, Adjudicator, EvalProfile, GameOver,
+    InterestEvaluationWeights, MinimaxSettings, SearchLimits, SearchSettings, Style,
 };
 
 const THREAD_COUNT: usize = 32;
+//how often analyze looks for a new best move
+const ANALYZE_INTERVAL: Duration = Duration::from_millis(500);
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let setup = args.iter().any(|arg| arg == "-su");
 
+    if args.iter().any(|arg| arg == "--bench") {
+        bench::run_bench(THREAD_COUNT);
+        return;
+    }
+
+    let mut search_settings = SearchSettings::new();
+    if let Some(min_depth) = flag_value(&args, "--depth") {
+        search_settings.min_depth = min_depth;
+    }
+    //--ponder is what self-play used to call it
+    if let Some(budget) = flag_value(&args, "--budget").or(flag_value(&args, "--ponder")) {
+        search_settings.expansion_budget = budget;
+    }
+    if let Some(seed) = flag_value(&args, "--seed") {
+        search_settings.ordering_seed = seed;
+    }
+    if let Some(epsilon) = flag_value(&args, "--ordering-epsilon") {
+        search_settings.ordering_epsilon = epsilon;
+    }
+    //a think capped at exactly this many search nodes. the tree is grown by
+    //expansion_budget on a single thread first, so the same position, --nodes and --seed
+    //always give the same move
+    let node_limit: Option<usize> = flag_value(&args, "--nodes");
+
+    if let Some(path) = flag_value::<String>(&args, "--epd") {
+        epd::run_epd(&path, THREAD_COUNT, &search_settings);
+        return;
+    }
+
+    if let Some(path) = flag_value::<String>(&args, "--tune") {
+        tune::run_tune(&path, THREAD_COUNT);
+        return;
+    }
+
+    if args.iter().any(|arg| arg == "--selfplay") {
+        let deterministic = args.iter().any(|arg| arg == "--deterministic");
+        let settings = selfplay::SelfPlaySettings {
+            search_settings: search_settings.clone(),
+            thread_count: match deterministic {
+                true => 1,
+                false => THREAD_COUNT,
+            },
+            opening: flag_value::<String>(&args, "--opening")
+                .map(|moves| moves.split_whitespace().map(String::from).collect())
+                .unwrap_or_default(),
+            max_moves: flag_value(&args, "--max-moves").unwrap_or(200),
+            minimax_settings: selfplay_minimax_settings_from(&args),
+            interest_weights: InterestEvaluationWeights::new(),
+            random_plies: 0,
+            seed: 0,
+        };
+        //--learn path tunes the interest weights kept in path over --games games
+        match flag_value::<String>(&args, "--learn") {
+            Some(path) => learn::run_learning(
+                &settings,
+                &learn::LearnSettings {
+                    path,
+                    games: flag_value(&args, "--games").unwrap_or(1),
+                    seed: flag_value(&args, "--seed").unwrap_or(0),
+                    learning_rate: flag_value(&args, "--learning-rate").unwrap_or(0.01),
+                },
+            ),
+            None => selfplay::run_selfplay(&settings),
+        }
+        return;
+    }
+
+    let multi_pv: usize = flag_value(&args, "--multipv").unwrap_or(1).max(1);
+    let json_output = flag_value::<String>(&args, "--format").as_deref() == Some("json");
+    let debug_search = args.iter().any(|arg| arg == "--debug-search");
+    let chess960 = args.iter().any(|arg| arg == "--chess960");
+    //the engine goes on thinking, about the reply it expects, while the user types theirs
+    let ponder_reply = args.iter().any(|arg| arg == "--ponder-reply");
+
+    //--compare "fenA" "fenB", each FEN quoted as one argument
+    if let Some(index) = args.iter().position(|arg| arg == "--compare") {
+        match (args.get(index + 1), args.get(index + 2)) {
+            (Some(first), Some(second)) => {
+                let style = flag_value::<String>(&args, "--style")
+                    .and_then(|name| Style::from_name(&name))
+                    .unwrap_or(Style::Balanced);
+                compare_positions(first, second, chess960, style);
+            }
+            _ => println!("--compare expects two FENs"),
+        }
+        return;
+    }
+
+    if args.iter().any(|arg| arg == "--uci") {
+        uci::run_uci(
+            THREAD_COUNT,
+            multi_pv,
+            minimax_settings_from(&args),
+            search_settings,
+            chess960,
+            flag_value::<String>(&args, "--moves").as_deref(),
+        );
+        return;
+    }
+
+    let pgn_game = match flag_value::<String>(&args, "--pgn") {
+        Some(path) => match pgn::load_game(&path, flag_value(&args, "--game").unwrap_or(1)) {
+            Ok(game) => Some(game),
+            Err(message) => {
+                println!("{}", message);
+                return;
+            }
+        },
+        None => None,
+    };
+
     let stdin = stdin();
-    let board = match setup && yes_or_no("use fen?") {
-        true => {
-            println!("enter fen");
-            loop {
-                let mut text = String::new();
-                stdin.read_line(&mut text).unwrap();
-                match make_board_from_fen(&text) {
-                    Ok(board) => break board,
-                    Err(err) => {
-                        println!("Error! Please try again! ({:?})", err)
+    //with the FEN it came from, if any, for the move numbers to count on from
+    let (board, start_fen) = match &pgn_game {
+        Some(game) => match game.starting_board() {
+            Ok(board) => (board, game.tag("FEN").map(String::from)),
+            Err(message) => {
+                println!("{}", message);
+                return;
+            }
+        },
+        None => match setup && yes_or_no("use fen?") {
+            true => {
+                println!("enter fen");
+                loop {
+                    let mut text = String::new();
+                    stdin.read_line(&mut text).unwrap();
+                    match read_position(&text, chess960) {
+                        Ok(board) => break (board, Some(text)),
+                        Err(message) => println!("Error! Please try again! ({})", message),
                     }
                 }
             }
-        }
-        false => Board::new(),
+            false => (Board::new(), None),
+        },
     };
 
     let turns_to_eval = vec![
@@ -64,12 +197,95 @@ fn main() {
         },
     ];
 
+    //the side printed balances are seen from. the search itself always works from white's
+    let perspective = match flag_value::<String>(&args, "--perspective").as_deref() {
+        Some("white") => Color::White,
+        Some("black") => Color::Black,
+        Some(other) => {
+            println!("unknown perspective {}, expected white or black", other);
+            return;
+        }
+        None => match turns_to_play.iter().flatten().collect::<Vec<_>>()[..] {
+            [engine_side] if setup && yes_or_no("Show the balance from your side?") => {
+                engine_side.opposite()
+            }
+            _ => Color::White,
+        },
+    };
+
+    let minimax_settings = minimax_settings_from(&args);
+    //a preset personality, or the default weights
+    let (mut static_weights, mut interest_weights) = match flag_value::<String>(&args, "--style") {
+        Some(name) => match Style::from_name(&name) {
+            Some(style) => style.weights(),
+            None => {
+                let names: Vec<&str> = Style::ALL.iter().map(|style| style.name()).collect();
+                println!(
+                    "unknown style {}, expected one of {}",
+                    name,
+                    names.join(", ")
+                );
+                return;
+            }
+        },
+        None => Style::Balanced.weights(),
+    };
+    //the old attack term, which ignores whether the attacked pieces are defended
+    if args.iter().any(|arg| arg == "--plain-attack") {
+        interest_weights.attack_by_defenders = false;
+    }
+    //--interest-weights path plays with the interest weights --learn saved to path
+    if let Some(path) = flag_value::<String>(&args, "--interest-weights") {
+        if let Err(message) =
+            weights_file::load_weights(&path, interest_weights.named_weights_mut())
+        {
+            println!("COULDN'T LOAD WEIGHTS!!!! ({})", message);
+            return;
+        }
+    }
+    //--static-weights path plays with the static weights --tune printed, saved to path
+    if let Some(path) = flag_value::<String>(&args, "--static-weights") {
+        if let Err(message) = weights_file::load_weights(&path, static_weights.named_weights_mut())
+        {
+            println!("COULDN'T LOAD WEIGHTS!!!! ({})", message);
+            return;
+        }
+    }
+    let mut adjudicator = Adjudicator::new(minimax_settings.clone());
     let mut engine = engine::teros_engine::Engine::new(
         board,
-        StaticEvaluationWeights::new(),
-        InterestEvaluationWeights::new(),
-        MinimaxSettings::new(),
+        static_weights,
+        interest_weights,
+        minimax_settings,
+        search_settings,
     );
+    //material and piece-square tables only, for very fast play
+    if args.iter().any(|arg| arg == "--fast-eval") {
+        engine.set_eval_profile(EvalProfile::Fast);
+    }
+    if let Some(game) = &pgn_game {
+        if let Err(message) = game.replay(&mut engine) {
+            println!("{}", message);
+            return;
+        }
+    }
+    //--moves "e4 e5 Nf3 Nc6", played on from the starting position, FEN or game
+    if let Some(moves) = flag_value::<String>(&args, "--moves") {
+        if let Err(message) = pgn::play_moves(&mut engine, &moves) {
+            println!("{}", message);
+            return;
+        }
+    }
+    //a checkpoint saved from an earlier run, to carry on pondering where it stopped
+    if let Some(path) = flag_value::<String>(&args, "--resume") {
+        let resumed = std::fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|checkpoint| engine.resume(&checkpoint));
+        if let Err(message) = resumed {
+            println!("COULDN'T RESUME {}!!!! ({})", path, message);
+            return;
+        }
+    }
 
     let max_pondering: Option<usize> = match setup && yes_or_no("Limit pondering?") {
         false => None,
@@ -78,33 +294,102 @@ fn main() {
             Some(read!())
         }
     };
+    //only asked when pondering isn't limited by count, which takes precedence
+    let ponder_secs: Option<u64> = match flag_value(&args, "--ponder-secs") {
+        Some(secs) => Some(secs),
+        None => match setup && max_pondering.is_none() && yes_or_no("Ponder for a fixed time?") {
+            false => None,
+            true => {
+                println!("how many seconds?");
+                Some(read!())
+            }
+        },
+    };
 
     let pgn_mode = setup && yes_or_no("pgn only mode?");
 
+    //a line of JSON for every move the engine plays, appended so games can share a file
+    let mut decision_log = match flag_value::<String>(&args, "--log") {
+        Some(path) => match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => Some(file),
+            Err(err) => {
+                println!("COULDN'T OPEN LOG {}!!!! ({})", path, err);
+                return;
+            }
+        },
+        None => None,
+    };
+
     // engine.print_tree(10);
     let mut stdout = stdout();
     const START_EVAL_TURN: i32 = 0;
     let mut i = 1;
-    let mut move_number = 1;
+    let mut engine_moved = false;
+    let mut move_numbering = match &start_fen {
+        Some(fen) => pgn::MoveNumbering::from_fen(fen, engine.move_history().len()),
+        None => {
+            pgn::MoveNumbering::resuming(engine.get_board().get_turn(), engine.move_history().len())
+        }
+    };
     loop {
+        if let Some(game_over) = engine.game_over() {
+            print_game_over(pgn_mode, &engine, game_over);
+            break;
+        }
         if turns_to_eval.contains(&Some(engine.get_board().get_turn())) && i >= START_EVAL_TURN {
             let (stop_sender, stop_reciever) = std::sync::mpsc::channel();
+            let thinking_started = Instant::now();
 
-            engine = match max_pondering {
-                Some(max_pondering_num) => {
+            engine = match (max_pondering, ponder_secs) {
+                _ if node_limit.is_some() => {
+                    let budget = engine.search_settings().expansion_budget;
+                    if !pgn_mode {
+                        println!("PONDERING!!!! ({} times on one thread)", budget);
+                    }
+                    let res = engine.multi_thread_think_next_num_moves(1, budget, None);
+                    if !pgn_mode {
+                        println!("EVALUATING!!!!");
+                    }
+                    res
+                }
+                (Some(max_pondering_num), _) => {
                     if !pgn_mode {
                         println!("PONDERING!!!! (until done as much as you told me)");
                     }
 
-                    let res =
-                        engine.multi_thread_think_next_num_moves(THREAD_COUNT, max_pondering_num);
+                    let report_progress = |done: usize| {
+                        print!("\rPONDERED {}/{}", done, max_pondering_num);
+                        std::io::stdout().flush().unwrap();
+                    };
+                    let res = engine.multi_thread_think_next_num_moves(
+                        THREAD_COUNT,
+                        max_pondering_num,
+                        match pgn_mode {
+                            true => None,
+                            false => Some(&report_progress),
+                        },
+                    );
 
                     if !pgn_mode {
+                        println!();
                         println!("EVALUATING!!!!");
                     }
                     res
                 }
-                None => {
+                (None, Some(secs)) => {
+                    if !pgn_mode {
+                        println!("PONDERING!!!! (for {} seconds)", secs);
+                    }
+
+                    let res = engine.think_for(THREAD_COUNT, Duration::from_secs(secs));
+
+                    if !pgn_mode {
+                        println!("PONDERED {} TIMES!!!!", res.1);
+                        println!("EVALUATING!!!!");
+                    }
+                    res.0
+                }
+                (None, None) => {
                     if !pgn_mode {
                         println!("PONDERING!!!! (enter any value to stop)");
                     }
@@ -126,31 +411,104 @@ fn main() {
                     res.0
                 }
             };
+            let evicted = engine.take_evicted();
+            if evicted > 0 && !pgn_mode {
+                println!("EVICTED {} NODES!!!! (the tree hit --max-nodes)", evicted);
+            }
             let engine_arc = Arc::new(engine);
-            let eval = engine_arc.clone().parallel_eval_and_best_move(THREAD_COUNT);
+            engine_arc.reset_search_stats();
+            //a node limited think only looks for the one best move
+            let lines = match (multi_pv, node_limit) {
+                (1, _) | (_, Some(_)) => Vec::new(),
+                (n, None) => engine_arc.clone().best_n_moves(THREAD_COUNT, n),
+            };
+            let eval = match (node_limit, lines.first()) {
+                (Some(nodes), _) => {
+                    let (_stop_sender, stop) = std::sync::mpsc::channel();
+                    let limits = SearchLimits {
+                        nodes: Some(nodes),
+                        ..SearchLimits::default()
+                    };
+                    let (eval, best_move, _) = engine_arc.go(limits, stop);
+                    (eval, best_move)
+                }
+                (None, Some((eval, chess_move))) => (eval.clone(), Some(*chess_move)),
+                (None, None) => engine_arc.clone().parallel_eval_and_best_move(THREAD_COUNT),
+            };
             engine = Arc::try_unwrap(engine_arc).unwrap();
+            //taken before the principal variation is searched for the printout
+            let nodes = engine.nodes_searched();
+            let thinking_time = thinking_started.elapsed();
+            if debug_search {
+                print!("{}", engine.search_stats());
+            }
             // engine.print_tree(10);
-            if !pgn_mode {
+            //only looked up for the output that shows it, pgn_mode has no place for it
+            let san_principal_variation = |engine: &engine::teros_engine::Engine| {
+                san_line(engine.get_board(), &engine.principal_variation()).unwrap()
+            };
+            if json_output {
+                println!(
+                    "{}",
+                    json::analysis_json(
+                        engine.get_board(),
+                        &eval.0,
+                        eval.1,
+                        &san_principal_variation(&engine)
+                    )
+                );
+            } else if !pgn_mode {
                 println!(
                     "BALANCE IS {}. I LIKE THE MOVE {}",
-                    eval.0,
+                    eval.0.clamp_for_display().relative_to(perspective),
                     match eval.1 {
                         Some(chess_move) => chess_move.name(engine.get_board()).unwrap(),
                         None => String::from("THERE ARE NO MOVES"),
                     }
                 );
+                let principal_variation = san_principal_variation(&engine);
+                if !principal_variation.is_empty() {
+                    println!("PV: {}", principal_variation.join(" "));
+                }
+                for (index, (line_eval, chess_move)) in lines.iter().enumerate() {
+                    println!(
+                        "LINE {}: {} {}",
+                        index + 1,
+                        line_eval.clamp_for_display().relative_to(perspective),
+                        chess_move.name(engine.get_board()).unwrap()
+                    );
+                }
             }
             if turns_to_play.contains(&Some(engine.get_board().get_turn())) {
+                if let Some(adjudication) = adjudicator.adjudicate(&engine, &eval.0) {
+                    print_adjudication(pgn_mode, &engine, adjudication);
+                    break;
+                }
                 match eval.1 {
                     Some(chess_move) => {
+                        if let Some(log) = &mut decision_log {
+                            let line = json::decision_json(
+                                engine.get_board(),
+                                &eval.0,
+                                chess_move,
+                                &san_principal_variation(&engine),
+                                nodes,
+                                thinking_time,
+                            );
+                            if let Err(err) = writeln!(log, "{}", line) {
+                                println!("COULDN'T LOG!!!! ({})", err);
+                            }
+                        }
                         make_engine_move_and_print(
                             pgn_mode,
                             &mut engine,
-                            &mut move_number,
+                            &mut move_numbering,
                             chess_move,
+                            false,
                             &mut stdout,
                         )
                         .unwrap();
+                        engine_moved = true;
                         continue;
                     }
                     None => {
@@ -158,21 +516,182 @@ fn main() {
                     }
                 }
             } else {
-                engine.get_board().print_board(&mut stdout).unwrap();
+                print!("{}", engine.pretty_board(stdout.is_terminal()));
             }
         }
         if !pgn_mode {
             println!("U THINK NOW!!!!");
         }
+        //a line typed while pondering on the reply that wasn't the reply, still to be handled
+        let mut pending_line = None;
+        if ponder_reply && std::mem::take(&mut engine_moved) {
+            let mut replied = None;
+            engine = match engine.ponder(THREAD_COUNT) {
+                Err(unpondered) => *unpondered,
+                Ok(ponder) => {
+                    if !pgn_mode {
+                        println!(
+                            "(I EXPECT {})",
+                            san(ponder.board(), ponder.predicted()).unwrap()
+                        );
+                    }
+                    let mut line = String::new();
+                    if stdin.read_line(&mut line).unwrap() == 0 {
+                        ponder.cancel();
+                        return;
+                    }
+                    let board = ponder.board().clone();
+                    match ponder.try_interpret(line.trim()) {
+                        Ok(chess_move) => match ponder.resolve(&chess_move) {
+                            Ok((resolved, hit)) => {
+                                replied = Some((board, chess_move, hit));
+                                resolved
+                            }
+                            Err(ponder) => {
+                                pending_line = Some(line);
+                                ponder.cancel()
+                            }
+                        },
+                        Err(_) => {
+                            pending_line = Some(line);
+                            ponder.cancel()
+                        }
+                    }
+                }
+            };
+            if let Some((board, chess_move, hit)) = replied {
+                if hit && !pgn_mode {
+                    println!("SAW THAT COMING!!!!");
+                }
+                print_move_played(
+                    pgn_mode,
+                    &board,
+                    &engine,
+                    &mut move_numbering,
+                    chess_move,
+                    true,
+                    &mut stdout,
+                );
+                i += 1;
+                continue;
+            }
+        }
         loop {
-            let chess_move_string: String = read!();
-            let chess_move = engine.get_board().interpret_move(&chess_move_string);
+            let line = match pending_line.take() {
+                Some(line) => line,
+                None => {
+                    let mut line = String::new();
+                    if stdin.read_line(&mut line).unwrap() == 0 {
+                        return;
+                    }
+                    line
+                }
+            };
+            //a move or a command, and whatever follows a command on its line
+            let (chess_move_string, argument) = match line.trim().split_once(char::is_whitespace) {
+                Some((command, argument)) => (command.to_string(), argument.trim()),
+                None => (line.trim().to_string(), ""),
+            };
+            if chess_move_string.is_empty() {
+                continue;
+            }
+            if chess_move_string == "why" {
+                println!("{}", engine.evaluation_breakdown(engine.get_board()));
+                continue;
+            }
+            if chess_move_string == "threats" {
+                print_threats(&engine);
+                continue;
+            }
+            if chess_move_string == "fen" || chess_move_string == "startpos" {
+                let board = match chess_move_string.as_str() {
+                    "fen" => match read_position(argument, chess960) {
+                        Ok(board) => board,
+                        Err(message) => {
+                            println!("BAD FEN!!!! ({})", message);
+                            continue;
+                        }
+                    },
+                    _ => Board::new(),
+                };
+                engine.set_position(board);
+                adjudicator.reset();
+                move_numbering = pgn::MoveNumbering::from_fen(argument, 0);
+                print!("{}", engine.pretty_board(stdout.is_terminal()));
+                break;
+            }
+            if chess_move_string == "save" {
+                match std::fs::write(argument, engine.checkpoint()) {
+                    Ok(()) => println!("SAVED!!!!"),
+                    Err(err) => println!("COULDN'T SAVE!!!! ({})", err),
+                }
+                continue;
+            }
+            //ponders, reprinting the best move whenever it changes, until anything is entered.
+            //each reprint first tells at which depths the search changed its mind
+            if chess_move_string == "analyze" {
+                let (stop_sender, stop_reciever) = std::sync::mpsc::channel();
+                let thread_handle = thread::spawn(move || {
+                    engine.analyze(
+                        THREAD_COUNT,
+                        ANALYZE_INTERVAL,
+                        stop_reciever,
+                        |board, iterations| {
+                            for iteration in iterations {
+                                if let (Some(from), Some(to)) =
+                                    (iteration.best_move_changed_from, iteration.best_move)
+                                {
+                                    println!(
+                                        "depth {}: best move changed from {} to {}",
+                                        iteration.depth,
+                                        san(board, &from).unwrap(),
+                                        san(board, &to).unwrap()
+                                    );
+                                }
+                            }
+                            let last = iterations.last().unwrap();
+                            println!(
+                                "BALANCE IS {}. I LIKE THE MOVE {}",
+                                last.eval.clamp_for_display().relative_to(perspective),
+                                match last.best_move {
+                                    Some(chess_move) => san(board, &chess_move).unwrap(),
+                                    None => String::from("THERE ARE NO MOVES"),
+                                }
+                            )
+                        },
+                    )
+                });
+                let _: String = read!();
+                stop_sender.send(()).unwrap();
+                let res = thread_handle.join().unwrap();
+                engine = res.0;
+                println!("PONDERED {} TIMES!!!!", res.1);
+                continue;
+            }
+            //"frontier N" shows the next N entries pondering would expand, a bare
+            //"frontier" the interest of every move from here
+            if chess_move_string == "frontier" {
+                if let Ok(n) = argument.parse() {
+                    engine.print_frontier(n);
+                    continue;
+                }
+                for (chess_move, interest) in engine.root_move_interests() {
+                    println!(
+                        "{} {}",
+                        chess_move.name(engine.get_board()).unwrap(),
+                        interest
+                    );
+                }
+                continue;
+            }
+            let chess_move = engine.try_interpret(&chess_move_string);
             match chess_move {
                 Ok(chess_move) => match make_engine_move_and_print(
                     pgn_mode,
                     &mut engine,
-                    &mut move_number,
+                    &mut move_numbering,
                     chess_move,
+                    true,
                     &mut stdout,
                 ) {
                     Ok(_) => {
@@ -183,7 +702,7 @@ fn main() {
                     }
                 },
                 Err(err) => {
-                    println! {"NO CHEATING!!!! ({:?})", err }
+                    println! {"NO CHEATING!!!! ({})", err }
                 }
             }
         }
@@ -193,34 +712,223 @@ fn main() {
     // println!("{:#?}", engine); // Debug print the engine variable
 }
 
+//users_move echoes the move as it was read, so a typed move taken for a different one
+//shows before the board does
 fn make_engine_move_and_print(
     pgn_mode: bool,
     engine: &mut engine::teros_engine::Engine,
-    move_number: &mut i32,
+    move_numbering: &mut pgn::MoveNumbering,
     chess_move: chess::chess::ChessMove,
+    users_move: bool,
     stdout: &mut std::io::Stdout,
 ) -> Result<(), engine::teros_engine::EngineError> {
+    let board = engine.get_board().clone();
+    engine.make_move(&chess_move)?;
+    print_move_played(
+        pgn_mode,
+        &board,
+        engine,
+        move_numbering,
+        chess_move,
+        users_move,
+        stdout,
+    );
+    Ok(())
+}
+
+//the output of make_engine_move_and_print for a move already made, board being the one
+//it was played on
+fn print_move_played(
+    pgn_mode: bool,
+    board: &Board,
+    engine: &engine::teros_engine::Engine,
+    move_numbering: &mut pgn::MoveNumbering,
+    chess_move: chess::chess::ChessMove,
+    users_move: bool,
+    stdout: &mut std::io::Stdout,
+) {
+    let name = san(board, &chess_move).unwrap();
     if pgn_mode {
-        match engine.get_board().get_turn() {
+        let turn = board.get_turn();
+        let opens_movetext = !move_numbering.started();
+        let text = move_numbering.next(turn, &name);
+        match turn {
             Color::White => {
-                print!(
-                    "{}. {}",
-                    move_number,
-                    chess_move.name(engine.get_board()).unwrap()
-                );
+                print!("{}", text);
                 stdout.flush().unwrap();
             }
-            Color::Black => {
-                print!(" {}\n", chess_move.name(engine.get_board()).unwrap());
-                *move_number += 1;
+            Color::Black if opens_movetext => println!("{}", text),
+            Color::Black => println!(" {}", text),
+        }
+    } else {
+        if users_move {
+            println!("YOU PLAYED {}", name);
+        }
+        print!("{}", engine.pretty_board(stdout.is_terminal()));
+    }
+}
+
+fn print_adjudication(
+    pgn_mode: bool,
+    engine: &engine::teros_engine::Engine,
+    adjudication: Adjudication,
+) {
+    let reason = adjudication.description();
+    if pgn_mode {
+        //black to move means white's half of the move line is still open
+        match engine.get_board().get_turn() {
+            Color::White => println!("{{{}}} {}", reason, adjudication.result()),
+            Color::Black => println!(" {{{}}} {}", reason, adjudication.result()),
+        }
+    } else {
+        match adjudication {
+            Adjudication::Resign(_) => println!("I RESIGN!!!! ({})", adjudication.result()),
+            Adjudication::Draw | Adjudication::ClaimDraw(_) | Adjudication::AgreedDraw => {
+                println!("I CLAIM A DRAW!!!! ({}, {})", reason, adjudication.result())
             }
         }
-        engine.make_move(&chess_move)?;
+    }
+}
+
+fn print_game_over(pgn_mode: bool, engine: &engine::teros_engine::Engine, game_over: GameOver) {
+    if pgn_mode {
+        //black to move means white's half of the move line is still open
+        match engine.get_board().get_turn() {
+            Color::White => println!("{{{}}} {}", game_over.description(), game_over.result()),
+            Color::Black => println!(" {{{}}} {}", game_over.description(), game_over.result()),
+        }
     } else {
-        engine.make_move(&chess_move)?;
-        engine.get_board().print_board(stdout).unwrap();
+        println!(
+            "GAME OVER!!!! ({}, {})",
+            game_over.description(),
+            game_over.result()
+        );
     }
-    Ok(())
+}
+
+//the "threats" command: the side to move's tactics, in SAN
+fn print_threats(engine: &engine::teros_engine::Engine) {
+    let board = engine.get_board();
+    let threats = engine.threats();
+    let line = |moves: Vec<String>| match moves.is_empty() {
+        true => String::from("NONE"),
+        false => moves.join(" "),
+    };
+    println!(
+        "HANGING: {}",
+        line(
+            threats
+                .hanging
+                .iter()
+                .map(|chess_move| san(board, chess_move).unwrap())
+                .collect()
+        )
+    );
+    println!(
+        "WINNING CAPTURES: {}",
+        line(
+            threats
+                .winning_captures
+                .iter()
+                .map(|(chess_move, gain)| format!(
+                    "{} (+{})",
+                    san(board, chess_move).unwrap(),
+                    gain
+                ))
+                .collect()
+        )
+    );
+    println!(
+        "CHECKS: {}",
+        line(
+            threats
+                .checks
+                .iter()
+                .map(|chess_move| san(board, chess_move).unwrap())
+                .collect()
+        )
+    );
+}
+
+//a typed FEN, checked to be a position the engine can play. Chess960 castling rights are
+//read the way chess960_fen reads them, so only those from the standard squares
+fn read_position(text: &str, chess960: bool) -> Result<Board, String> {
+    let fen = match chess960 {
+        true => chess960_fen(text.trim()).map_err(|err| err.to_string())?,
+        false => text.trim().to_string(),
+    };
+    parse_fen(&fen).map_err(|err| err.to_string())
+}
+
+//the static eval breakdowns of two positions side by side, with how much each term moved
+//from the first to the second. every number is from white's side, whoever is to move
+fn compare_positions(first: &str, second: &str, chess960: bool, style: Style) {
+    let (first, second) = match (
+        read_position(first, chess960),
+        read_position(second, chess960),
+    ) {
+        (Ok(first), Ok(second)) => (first, second),
+        (Err(err), _) | (_, Err(err)) => {
+            println!("{}", err);
+            return;
+        }
+    };
+    let (static_weights, interest_weights) = style.weights();
+    let engine = engine::teros_engine::Engine::new(
+        Board::new(),
+        static_weights,
+        interest_weights,
+        MinimaxSettings::new(),
+        SearchSettings::new(),
+    );
+    let first = engine.evaluation_breakdown(&first);
+    let second = engine.evaluation_breakdown(&second);
+    println!("{:<20}{:>8}{:>8}{:>8}", "", "first", "second", "delta");
+    let rows = first
+        .terms()
+        .into_iter()
+        .zip(second.terms())
+        .map(|((name, first), (_, second))| (name, first, second))
+        .chain([("total", first.total(), second.total())]);
+    for (name, first, second) in rows {
+        println!(
+            "{:<20}{:>+8.2}{:>+8.2}{:>+8.2}",
+            format!("{}:", name),
+            first,
+            second,
+            second - first
+        );
+    }
+}
+
+//--max-nodes
+fn minimax_settings_from(args: &[String]) -> MinimaxSettings {
+    let mut minimax_settings = MinimaxSettings::new();
+    if let Some(max_nodes) = flag_value(args, "--max-nodes") {
+        minimax_settings.max_nodes = max_nodes;
+    }
+    minimax_settings
+}
+
+//self-play games are also drawn by agreement, which --draw-band and --draw-moves tune.
+//games against a person only end the way the rules say
+fn selfplay_minimax_settings_from(args: &[String]) -> MinimaxSettings {
+    let mut minimax_settings = minimax_settings_from(args);
+    minimax_settings.agreement_draw_moves = selfplay::AGREEMENT_DRAW_MOVES;
+    if let Some(band) = flag_value(args, "--draw-band") {
+        minimax_settings.agreement_draw_band = band;
+    }
+    if let Some(moves) = flag_value(args, "--draw-moves") {
+        minimax_settings.agreement_draw_moves = moves;
+    }
+    minimax_settings
+}
+
+fn flag_value<T: std::str::FromStr>(args: &[String], flag: &str) -> Option<T> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|index| args.get(index + 1))
+        .and_then(|value| value.parse().ok())
 }
 
 fn yes_or_no(question: &str) -> bool {
@@ -0,0 +1,302 @@
+use std::fs;
+
+#[cfg(test)]
+use rust_chess::chess::chess::{make_board_from_fen, PieceKind};
+use rust_chess::chess::chess::{Board, Color};
+
+use crate::engine::teros_engine::{parse_fen, Engine};
+#[cfg(test)]
+use crate::engine::teros_engine::{
+    InterestEvaluationWeights, MinimaxSettings, SearchSettings, StaticEvaluationWeights,
+};
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+#[derive(Debug, Clone, Default)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    //the mainline in SAN, without move numbers or annotations
+    pub moves: Vec<String>,
+    pub result: Option<String>,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    //the position the game starts from, honoring a FEN tag
+    pub fn starting_board(&self) -> Result<Board, String> {
+        match self.tag("FEN") {
+            Some(fen) => parse_fen(fen).map_err(|err| format!("invalid FEN tag ({})", err)),
+            None => Ok(Board::new()),
+        }
+    }
+
+    //plays the mainline on an engine set up on starting_board
+    pub fn replay(&self, engine: &mut Engine) -> Result<(), String> {
+        for (ply, san) in self.moves.iter().enumerate() {
+            let chess_move = engine
+                .try_interpret(san)
+                .map_err(|err| format!("illegal move {} at ply {} ({})", san, ply + 1, err))?;
+            engine
+                .make_move(&chess_move)
+                .map_err(|err| format!("illegal move {} at ply {} ({:?})", san, ply + 1, err))?;
+        }
+        Ok(())
+    }
+}
+
+//a space separated list of moves, as --moves takes them, played one after another from
+//wherever the engine stands. stops at the first one that can't be played and names it
+pub fn play_moves(engine: &mut Engine, moves: &str) -> Result<(), String> {
+    for (index, move_string) in moves.split_whitespace().enumerate() {
+        engine
+            .interpret_and_make_move(move_string)
+            .map_err(|err| format!("can't play move {} ({}): {:?}", index + 1, move_string, err))?;
+    }
+    Ok(())
+}
+
+//numbers movetext written one move at a time. a game that opens on black's turn starts
+//with "N..." and white's next move takes the number after it
+pub struct MoveNumbering {
+    move_number: i32,
+    started: bool,
+}
+
+impl MoveNumbering {
+    //picks up numbering plies_played moves into a game that now has turn to move
+    pub fn resuming(turn: Color, plies_played: usize) -> MoveNumbering {
+        let black_started = (turn == Color::Black) != (plies_played % 2 == 1);
+        MoveNumbering {
+            move_number: 1 + (plies_played + black_started as usize) as i32 / 2,
+            started: false,
+        }
+    }
+
+    //picks up numbering plies_played moves into a game that started from fen, counting on
+    //from its fullmove number
+    pub fn from_fen(fen: &str, plies_played: usize) -> MoveNumbering {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let black_started = fields.get(1) == Some(&"b");
+        let fullmove = fields
+            .get(5)
+            .and_then(|number| number.parse::<i32>().ok())
+            .filter(|number| *number > 0)
+            .unwrap_or(1);
+        MoveNumbering {
+            move_number: fullmove + (plies_played + black_started as usize) as i32 / 2,
+            started: false,
+        }
+    }
+
+    //whether any move has been numbered yet
+    pub fn started(&self) -> bool {
+        self.started
+    }
+
+    //the movetext for the next move, played by turn
+    pub fn next(&mut self, turn: Color, san: &str) -> String {
+        let text = match (turn, self.started) {
+            (Color::White, _) => format!("{}. {}", self.move_number, san),
+            (Color::Black, false) => format!("{}... {}", self.move_number, san),
+            (Color::Black, true) => String::from(san),
+        };
+        self.started = true;
+        if turn == Color::Black {
+            self.move_number += 1;
+        }
+        text
+    }
+}
+
+//the index-th game (counting from 1) of a PGN file
+pub fn load_game(path: &str, index: usize) -> Result<PgnGame, String> {
+    let text =
+        fs::read_to_string(path).map_err(|err| format!("could not read {} ({})", path, err))?;
+    let games = parse_pgn(&text);
+    let game_count = games.len();
+    games
+        .into_iter()
+        .nth(index.saturating_sub(1))
+        .ok_or(format!(
+            "{} has {} games, no game {}",
+            path, game_count, index
+        ))
+}
+
+//splits a PGN file into games. comments, variations, NAGs and move numbers are dropped
+pub fn parse_pgn(text: &str) -> Vec<PgnGame> {
+    let mut games = Vec::new();
+    let mut game = PgnGame::default();
+    let mut chars = text.chars().peekable();
+    let mut token = String::new();
+
+    let finish_token = |token: &mut String, game: &mut PgnGame, games: &mut Vec<PgnGame>| {
+        let word = std::mem::take(token);
+        if RESULTS.contains(&word.as_str()) {
+            game.result = Some(word);
+            games.push(std::mem::take(game));
+            return;
+        }
+        //"12." "12..." and "12.e4" all carry a move number in front
+        let word = word.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+        if !word.is_empty() && !word.starts_with('$') {
+            game.moves.push(String::from(word));
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '[' => {
+                finish_token(&mut token, &mut game, &mut games);
+                //tags after movetext open the next game
+                if !game.moves.is_empty() {
+                    games.push(std::mem::take(&mut game));
+                }
+                let tag: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                let (name, value) = tag.trim().split_once(' ').unwrap_or((tag.trim(), ""));
+                game.tags.push((
+                    String::from(name),
+                    String::from(value.trim().trim_matches('"')),
+                ));
+            }
+            '{' => {
+                finish_token(&mut token, &mut game, &mut games);
+                chars.by_ref().find(|c| *c == '}');
+            }
+            ';' => {
+                finish_token(&mut token, &mut game, &mut games);
+                chars.by_ref().find(|c| *c == '\n');
+            }
+            '(' => {
+                finish_token(&mut token, &mut game, &mut games);
+                let mut nesting = 1;
+                for c in chars.by_ref() {
+                    match c {
+                        '(' => nesting += 1,
+                        ')' => nesting -= 1,
+                        _ => {}
+                    }
+                    if nesting == 0 {
+                        break;
+                    }
+                }
+            }
+            c if c.is_whitespace() => finish_token(&mut token, &mut game, &mut games),
+            c => token.push(c),
+        }
+    }
+    finish_token(&mut token, &mut game, &mut games);
+    if !game.moves.is_empty() || !game.tags.is_empty() {
+        games.push(game);
+    }
+    games
+}
+
+#[test]
+fn test_parse_pgn() {
+    let text = "[Event \"a\"]\n[White \"me\"]\n\n1. e4 {best by test} e5 2. Nf3 (2. f4 exf4) Nc6 $1 3... a6 1/2-1/2\n\n[Event \"b\"]\n\n1.d4 d5 *\n";
+    let games = parse_pgn(text);
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[0].tag("White"), Some("me"));
+    assert_eq!(games[0].moves, ["e4", "e5", "Nf3", "Nc6", "a6"]);
+    assert_eq!(games[0].result.as_deref(), Some("1/2-1/2"));
+    assert_eq!(games[1].tag("Event"), Some("b"));
+    assert_eq!(games[1].moves, ["d4", "d5"]);
+}
+
+#[test]
+fn test_numbering_from_black() {
+    let board = make_board_from_fen("4k3/4p3/8/8/8/8/4P3/4K3 b - - 0 1").unwrap();
+    let mut numbering = MoveNumbering::resuming(board.get_turn(), 0);
+    let movetext: Vec<String> = [
+        (Color::Black, "e5"),
+        (Color::White, "e4"),
+        (Color::Black, "Kd7"),
+    ]
+    .iter()
+    .map(|(turn, san)| numbering.next(*turn, san))
+    .collect();
+    assert_eq!(movetext, ["1... e5", "2. e4", "Kd7"]);
+
+    //two plies into a game black started, black is to move again on move 2
+    let mut numbering = MoveNumbering::resuming(Color::Black, 2);
+    assert_eq!(numbering.next(Color::Black, "Ke8"), "2... Ke8");
+    //one ply into it, white is to move on move 2
+    let mut numbering = MoveNumbering::resuming(Color::White, 1);
+    assert_eq!(numbering.next(Color::White, "Kf1"), "2. Kf1");
+}
+
+#[test]
+fn test_numbering_from_fen() {
+    let mut numbering = MoveNumbering::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 20", 0);
+    assert_eq!(numbering.next(Color::White, "e4"), "20. e4");
+    let mut numbering = MoveNumbering::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 20", 0);
+    assert_eq!(numbering.next(Color::Black, "Kd7"), "20... Kd7");
+    //two plies played from black's move 20 leave black on move 21
+    let mut numbering = MoveNumbering::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 20", 2);
+    assert_eq!(numbering.next(Color::Black, "Kd7"), "21... Kd7");
+    let mut numbering = MoveNumbering::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - -", 0);
+    assert_eq!(numbering.next(Color::White, "e4"), "1. e4");
+}
+
+#[test]
+fn test_play_moves() {
+    let new_engine = || {
+        Engine::new(
+            Board::new(),
+            StaticEvaluationWeights::new(),
+            InterestEvaluationWeights::new(),
+            MinimaxSettings::new(),
+            SearchSettings::new(),
+        )
+    };
+    let mut engine = new_engine();
+    play_moves(&mut engine, "e4 e5  Nf3 Nc6").unwrap();
+    assert_eq!(engine.move_history().len(), 4);
+    assert_eq!(engine.get_board().get_turn(), Color::White);
+
+    //the moves before the bad one stay played
+    let mut engine = new_engine();
+    let message = play_moves(&mut engine, "e4 Ke3 d4").unwrap_err();
+    assert!(message.contains("move 2 (Ke3)"));
+    assert_eq!(engine.move_history().len(), 1);
+}
+
+#[test]
+fn test_promotion_without_piece() {
+    let board = || make_board_from_fen("8/2P5/8/8/8/8/8/k1K5 w - - 0 1").unwrap();
+    let new_engine = || {
+        Engine::new(
+            board(),
+            StaticEvaluationWeights::new(),
+            InterestEvaluationWeights::new(),
+            MinimaxSettings::new(),
+            SearchSettings::new(),
+        )
+    };
+    let queened = |engine: &Engine| engine.get_board().get_piece(7, 2).unwrap().unwrap().kind;
+
+    let game = PgnGame {
+        tags: vec![(
+            String::from("FEN"),
+            String::from("8/2P5/8/8/8/8/8/k1K5 w - - 0 1"),
+        )],
+        moves: vec![String::from("c8")],
+        result: None,
+    };
+    let mut engine = new_engine();
+    game.replay(&mut engine).unwrap();
+    assert_eq!(queened(&engine), PieceKind::Queen);
+
+    for moves in ["c8", "c7c8"] {
+        let mut engine = new_engine();
+        play_moves(&mut engine, moves).unwrap();
+        assert_eq!(queened(&engine), PieceKind::Queen);
+    }
+}
@@ -0,0 +1,194 @@
+use std::sync::Arc;
+
+use ordered_float::NotNan;
+#[cfg(test)]
+use rust_chess::chess::chess::make_board_from_fen;
+use rust_chess::chess::chess::{Board, ChessMove};
+
+use crate::engine::teros_engine::{
+    Adjudicator, Engine, InterestEvaluationWeights, MinimaxSettings, SearchSettings,
+    StaticEvaluationWeights,
+};
+
+//moves of a balanced, eventless game before the players agree to a draw
+pub const AGREEMENT_DRAW_MOVES: usize = 40;
+
+#[derive(Clone)]
+pub struct SelfPlaySettings {
+    //expansion_budget is pondered before every move
+    pub search_settings: SearchSettings,
+    //pondering threads. more than one makes the expansion order, and so the game, vary
+    pub thread_count: usize,
+    //SAN moves played before the engine takes over
+    pub opening: Vec<String>,
+    pub max_moves: usize,
+    //the resign and draw thresholds the game is adjudicated by, among the rest
+    pub minimax_settings: MinimaxSettings,
+    pub interest_weights: InterestEvaluationWeights,
+    //moves played at random after the opening, each picked by a linear congruential
+    //sequence started from seed, so games can differ and still be repeated
+    pub random_plies: usize,
+    pub seed: u64,
+}
+
+//a move the engine played, with every move it had to choose from as
+//Engine::root_interest_gradients gave them
+pub struct Decision {
+    pub played: ChessMove,
+    pub interests: Vec<(ChessMove, NotNan<f32>, Vec<f32>)>,
+}
+
+pub struct SelfPlayGame {
+    pub pgn: String,
+    //the engine's own moves, if they were asked for
+    pub decisions: Vec<Decision>,
+}
+
+//plays the engine against itself and prints the game as PGN. with one thread every
+//choice is deterministic (the frontier and minimax both break ties by move order),
+//so the same settings always produce the same game
+pub fn run_selfplay(settings: &SelfPlaySettings) {
+    match play_game(settings, false) {
+        Ok(game) => println!("{}", game.pgn),
+        Err(message) => println!("{}", message),
+    }
+}
+
+//one self-play game, recording the engine's decisions along the way when record_decisions
+//is set
+pub fn play_game(
+    settings: &SelfPlaySettings,
+    record_decisions: bool,
+) -> Result<SelfPlayGame, String> {
+    let minimax_settings = settings.minimax_settings.clone();
+    let mut adjudicator = Adjudicator::new(minimax_settings.clone());
+    let mut engine = Engine::new(
+        Board::new(),
+        StaticEvaluationWeights::new(),
+        settings.interest_weights.clone(),
+        minimax_settings,
+        settings.search_settings.clone(),
+    );
+    let mut sans = Vec::new();
+    let mut decisions = Vec::new();
+
+    for san in settings.opening.iter() {
+        let chess_move = match engine.try_interpret(san) {
+            Ok(chess_move) => chess_move,
+            Err(err) => return Err(format!("illegal opening move {} ({})", san, err)),
+        };
+        sans.push(chess_move.name(engine.get_board()).unwrap());
+        engine.make_move(&chess_move).unwrap();
+    }
+
+    let mut seed = settings.seed;
+    for _ in 0..settings.random_plies {
+        let moves = engine.root_move_interests();
+        if moves.is_empty() || engine.game_over().is_some() {
+            break;
+        }
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        let (chess_move, _) = moves[(seed >> 33) as usize % moves.len()];
+        sans.push(chess_move.name(engine.get_board()).unwrap());
+        engine.make_move(&chess_move).unwrap();
+    }
+
+    //how an adjudicated game ended, noted before the result
+    let mut adjudicated = None;
+    let result = loop {
+        //threefold and fifty moves have to be claimed through the adjudicator
+        if let Some(game_over) = engine.game_over() {
+            break game_over.result();
+        }
+        if sans.len() >= settings.max_moves * 2 {
+            break "*";
+        }
+
+        engine = engine.multi_thread_think_next_num_moves(
+            settings.thread_count,
+            settings.search_settings.expansion_budget,
+            None,
+        );
+        let engine_arc = Arc::new(engine);
+        let (eval, best_move) = match settings.thread_count {
+            1 => engine_arc.eval_and_best_move(),
+            thread_count => engine_arc.clone().parallel_eval_and_best_move(thread_count),
+        };
+        engine = Arc::try_unwrap(engine_arc).unwrap();
+
+        if let Some(adjudication) = adjudicator.adjudicate(&engine, &eval) {
+            adjudicated = Some(adjudication);
+            break adjudication.result();
+        }
+        let chess_move = match best_move {
+            Some(chess_move) => chess_move,
+            None => break "*",
+        };
+        if record_decisions {
+            decisions.push(Decision {
+                played: chess_move,
+                interests: engine.root_interest_gradients(),
+            });
+        }
+        sans.push(chess_move.name(engine.get_board()).unwrap());
+        engine.make_move(&chess_move).unwrap();
+    };
+
+    let mut pgn = String::from("[Event \"Teros self-play\"]\n");
+    pgn += "[White \"Teros\"]\n";
+    pgn += "[Black \"Teros\"]\n";
+    pgn += &format!("[Result \"{}\"]\n\n", result);
+    let mut movetext = Vec::new();
+    for (ply, san) in sans.iter().enumerate() {
+        match ply % 2 {
+            0 => movetext.push(format!("{}. {}", ply / 2 + 1, san)),
+            _ => movetext.push(san.clone()),
+        }
+    }
+    if let Some(adjudication) = adjudicated {
+        movetext.push(format!("{{{}}}", adjudication.description()));
+    }
+    movetext.push(String::from(result));
+    pgn += &movetext.join(" ");
+    Ok(SelfPlayGame { pgn, decisions })
+}
+
+#[test]
+fn test_selfplay_deterministic() {
+    let search_settings = SearchSettings {
+        expansion_budget: 200,
+        ..SearchSettings::new()
+    };
+    //the same position searched twice on one thread
+    let search = || {
+        Engine::new(
+            make_board_from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3")
+                .unwrap(),
+            StaticEvaluationWeights::new(),
+            InterestEvaluationWeights::new(),
+            MinimaxSettings::new(),
+            search_settings.clone(),
+        )
+        .multi_thread_think_next_num_moves(1, search_settings.expansion_budget, None)
+        .eval_and_best_move()
+    };
+    let (eval, best_move) = search();
+    assert!(best_move.is_some());
+    assert_eq!(search(), (eval, best_move));
+
+    //and a whole game from the same seed
+    let settings = SelfPlaySettings {
+        search_settings,
+        thread_count: 1,
+        opening: vec![String::from("e4"), String::from("e5")],
+        max_moves: 3,
+        minimax_settings: MinimaxSettings::new(),
+        interest_weights: InterestEvaluationWeights::new(),
+        random_plies: 2,
+        seed: 0x7e405,
+    };
+    let game = play_game(&settings, false).unwrap().pgn;
+    assert_eq!(play_game(&settings, false).unwrap().pgn, game);
+}
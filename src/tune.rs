@@ -0,0 +1,184 @@
+use std::{fs, thread};
+
+use rust_chess::chess::chess::{make_board_from_fen, Board, Color};
+
+use crate::engine::teros_engine::{refresh_status, Engine, Eval, StaticEvaluationWeights};
+use crate::weights_file::weights_toml;
+
+//passes over every weight before giving up on improving further
+const MAX_PASSES: usize = 50;
+//a weight stops being nudged once its step falls below this
+const MIN_STEP: f32 = 0.001;
+
+//a line is a FEN followed by the game's result for white: 1.0, 0.5 or 0.0 (or 1-0, 1/2-1/2,
+//0-1), optionally in brackets or quotes
+fn parse_line(line: &str) -> Option<(String, f32)> {
+    let (fen, result) = line.trim().rsplit_once(char::is_whitespace)?;
+    let result = result.trim_matches(['[', ']', '"', ';']);
+    let result = match result {
+        "1-0" => 1.0,
+        "1/2-1/2" => 0.5,
+        "0-1" => 0.0,
+        _ => result.parse::<f32>().ok()?,
+    };
+    Some((fen.trim().to_string(), result))
+}
+
+//expected score for white, 0 to 1, of an eval in pawns. scale is how many tenfold odds a
+//pawn is worth, Texel's K/4
+fn expected_score(eval: &Eval, scale: f32) -> f32 {
+    match eval {
+        Eval::Numeric(value) => 1.0 / (1.0 + 10f32.powf(-scale * value.into_inner())),
+        Eval::MateIn(Color::White, _) => 1.0,
+        Eval::MateIn(Color::Black, _) => 0.0,
+    }
+}
+
+//mean squared error of the expected scores against the results, over all threads
+fn mean_error(
+    positions: &[(Board, f32)],
+    weights: &StaticEvaluationWeights,
+    scale: f32,
+    thread_count: usize,
+) -> f32 {
+    let chunk_size = positions.len().div_ceil(thread_count).max(1);
+    let total: f32 = thread::scope(|scope| {
+        let handles: Vec<_> = positions
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|(board, result)| {
+                            (expected_score(&weights.evaluate(board), scale) - result).powi(2)
+                        })
+                        .sum::<f32>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .sum()
+    });
+    total / positions.len() as f32
+}
+
+//the sigmoid scale that fits the untuned weights best, so the weights aren't tuned to make
+//up for a badly scaled sigmoid
+fn fit_scale(
+    positions: &[(Board, f32)],
+    weights: &StaticEvaluationWeights,
+    thread_count: usize,
+) -> f32 {
+    (1..=40)
+        .map(|tenths| tenths as f32 / 10.0)
+        .map(|scale| (scale, mean_error(positions, weights, scale, thread_count)))
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(scale, _)| scale)
+        .unwrap()
+}
+
+//coordinate descent: nudge each weight up or down by its step while that lowers the error,
+//halving the step whenever neither direction helps
+fn tune(positions: &[(Board, f32)], scale: f32, thread_count: usize) -> StaticEvaluationWeights {
+    let mut weights = StaticEvaluationWeights::new();
+    let mut best_error = mean_error(positions, &weights, scale, thread_count);
+    let mut steps: Vec<f32> = weights
+        .named_weights_mut()
+        .iter()
+        .map(|(_, weight)| (weight.abs() / 4.0).max(0.01))
+        .collect();
+    for pass in 0..MAX_PASSES {
+        let mut improved = false;
+        for (index, step) in steps.iter_mut().enumerate() {
+            if *step < MIN_STEP {
+                continue;
+            }
+            let mut moved = false;
+            for direction in [1.0, -1.0] {
+                let mut candidate = weights.clone();
+                *candidate.named_weights_mut()[index].1 += direction * *step;
+                let error = mean_error(positions, &candidate, scale, thread_count);
+                if error < best_error {
+                    best_error = error;
+                    weights = candidate;
+                    moved = true;
+                    break;
+                }
+            }
+            match moved {
+                true => improved = true,
+                false => *step /= 2.0,
+            }
+        }
+        println!("pass {} error {:.6}", pass + 1, best_error);
+        if !improved && steps.iter().all(|step| *step < MIN_STEP) {
+            break;
+        }
+    }
+    weights
+}
+
+//tunes the static weights against a file of results-labeled positions and prints them as
+//TOML, which --static-weights reads back
+pub fn run_tune(path: &str, thread_count: usize) {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) => {
+            println!("could not read {} ({})", path, err);
+            return;
+        }
+    };
+    let mut positions = Vec::new();
+    let mut tactical = 0;
+    for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+        let board = parse_line(line).and_then(|(fen, result)| {
+            let mut board = make_board_from_fen(&fen).ok()?;
+            refresh_status(&mut board);
+            Some((board, result))
+        });
+        match board {
+            //the static eval of a position with a capture or check pending says little
+            //about the result, so those are left out
+            Some((board, _)) if !Engine::is_quiet(&board) => tactical += 1,
+            Some(position) => positions.push(position),
+            None => println!("skipping {}", line),
+        }
+    }
+    if positions.is_empty() {
+        println!("no positions to tune on");
+        return;
+    }
+
+    let scale = fit_scale(&positions, &StaticEvaluationWeights::new(), thread_count);
+    println!(
+        "{} positions ({} tactical ones left out), sigmoid scale {}",
+        positions.len(),
+        tactical,
+        scale
+    );
+    let mut weights = tune(&positions, scale, thread_count);
+    print!(
+        "{}",
+        weights_toml("static_evaluation_weights", weights.named_weights_mut())
+    );
+}
+
+#[test]
+fn test_parse_line() {
+    let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1";
+    assert_eq!(
+        parse_line(&format!("{} 1.0", fen)),
+        Some((fen.to_string(), 1.0))
+    );
+    assert_eq!(
+        parse_line(&format!("{} [0.5]", fen)),
+        Some((fen.to_string(), 0.5))
+    );
+    assert_eq!(
+        parse_line(&format!("{} \"0-1\";", fen)),
+        Some((fen.to_string(), 0.0))
+    );
+    assert_eq!(parse_line(&format!("{} white", fen)), None);
+}
@@ -0,0 +1,578 @@
+use std::{
+    io::{self, stdin, BufRead},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+#[cfg(test)]
+use rust_chess::chess::chess::make_board_from_fen;
+use rust_chess::chess::chess::{Board, ChessMove, Color};
+
+use crate::engine::teros_engine::{
+    chess960_fen, Adjudicator, CastlingStyle, Engine, Eval, InterestEvaluationWeights,
+    InterpretLan, MinimaxSettings, SearchLimits, SearchSettings, StaticEvaluationWeights, ToLan,
+};
+use crate::pgn::play_moves;
+
+const MAX_MULTI_PV: usize = 256;
+const MAX_MIN_DEPTH: i32 = 64;
+const MAX_EXPANSIONS: usize = 10_000_000;
+//bound on the resign and draw thresholds, in centipawns
+const MAX_THRESHOLD: i32 = 10_000;
+
+fn new_engine(
+    board: Board,
+    minimax_settings: &MinimaxSettings,
+    search_settings: &SearchSettings,
+) -> Engine {
+    Engine::new(
+        board,
+        StaticEvaluationWeights::new(),
+        InterestEvaluationWeights::new(),
+        minimax_settings.clone(),
+        search_settings.clone(),
+    )
+}
+
+pub fn run_uci(
+    thread_count: usize,
+    multi_pv: usize,
+    minimax_settings: MinimaxSettings,
+    search_settings: SearchSettings,
+    chess960: bool,
+    moves: Option<&str>,
+) {
+    let mut multi_pv = multi_pv;
+    let mut search_settings = search_settings;
+    let mut chess960 = chess960;
+    //the command line's, with the resign and draw thresholds set over UCI for the
+    //adjudicator
+    let mut minimax_settings = minimax_settings;
+    let adjudicator = Arc::new(Mutex::new(Adjudicator::new(minimax_settings.clone())));
+    let mut engine = new_engine(Board::new(), &minimax_settings, &search_settings);
+    //where a go lands before the GUI sets up a position of its own
+    if let Some(moves) = moves {
+        if let Err(message) = play_moves(&mut engine, moves) {
+            println!("info string {}", message);
+            return;
+        }
+    }
+
+    let mut lines = stdin().lock().lines();
+    //a command that came in while a search was running, handled once it's done
+    let mut pending = None;
+    loop {
+        let line = match pending.take() {
+            Some(line) => line,
+            None => match lines.next() {
+                Some(line) => line.unwrap(),
+                None => break,
+            },
+        };
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.first() {
+            Some(&"uci") => {
+                println!("id name Teros");
+                println!("id author AbyssPortal");
+                println!(
+                    "option name MultiPV type spin default 1 min 1 max {}",
+                    MAX_MULTI_PV
+                );
+                println!(
+                    "option name MinDepth type spin default {} min 1 max {}",
+                    search_settings.min_depth, MAX_MIN_DEPTH
+                );
+                println!(
+                    "option name Expansions type spin default {} min 1 max {}",
+                    search_settings.expansion_budget, MAX_EXPANSIONS
+                );
+                println!(
+                    "option name ResignThreshold type spin default {} min {} max 0",
+                    (minimax_settings.resign_threshold * 100.0).round() as i32,
+                    -MAX_THRESHOLD
+                );
+                println!(
+                    "option name DrawThreshold type spin default {} min 0 max {}",
+                    (minimax_settings.draw_threshold * 100.0).round() as i32,
+                    MAX_THRESHOLD
+                );
+                println!("option name Ponder type check default false");
+                println!("option name UCI_Chess960 type check default {}", chess960);
+                println!("uciok");
+            }
+            Some(&"isready") => println!("readyok"),
+            Some(&"ucinewgame") => {
+                engine.reset();
+                adjudicator.lock().unwrap().reset();
+            }
+            Some(&"setoption") => {
+                let (name, value) = parse_setoption(&tokens[1..]);
+                if name.eq_ignore_ascii_case("multipv") {
+                    match value.parse::<usize>() {
+                        Ok(value) => multi_pv = value.clamp(1, MAX_MULTI_PV),
+                        Err(_) => println!("info string invalid MultiPV value {}", value),
+                    }
+                } else if name.eq_ignore_ascii_case("mindepth") {
+                    match value.parse::<i32>() {
+                        Ok(value) => search_settings.min_depth = value.clamp(1, MAX_MIN_DEPTH),
+                        Err(_) => println!("info string invalid MinDepth value {}", value),
+                    }
+                } else if name.eq_ignore_ascii_case("expansions") {
+                    match value.parse::<usize>() {
+                        Ok(value) => {
+                            search_settings.expansion_budget = value.clamp(1, MAX_EXPANSIONS)
+                        }
+                        Err(_) => println!("info string invalid Expansions value {}", value),
+                    }
+                } else if name.eq_ignore_ascii_case("resignthreshold") {
+                    match value.parse::<i32>() {
+                        Ok(value) => {
+                            minimax_settings.resign_threshold =
+                                value.clamp(-MAX_THRESHOLD, 0) as f32 / 100.0;
+                            *adjudicator.lock().unwrap() =
+                                Adjudicator::new(minimax_settings.clone());
+                        }
+                        Err(_) => println!("info string invalid ResignThreshold value {}", value),
+                    }
+                } else if name.eq_ignore_ascii_case("drawthreshold") {
+                    match value.parse::<i32>() {
+                        Ok(value) => {
+                            minimax_settings.draw_threshold =
+                                value.clamp(0, MAX_THRESHOLD) as f32 / 100.0;
+                            *adjudicator.lock().unwrap() =
+                                Adjudicator::new(minimax_settings.clone());
+                        }
+                        Err(_) => println!("info string invalid DrawThreshold value {}", value),
+                    }
+                } else if name.eq_ignore_ascii_case("uci_chess960") {
+                    match value.parse::<bool>() {
+                        Ok(value) => chess960 = value,
+                        Err(_) => println!("info string invalid UCI_Chess960 value {}", value),
+                    }
+                }
+                engine.set_search_settings(search_settings.clone());
+            }
+            Some(&"position") => {
+                match position_engine(&tokens[1..], &minimax_settings, &search_settings, chess960) {
+                    Ok(new_engine) => engine = new_engine,
+                    Err(message) => println!("info string {}", message),
+                }
+            }
+            Some(&"go") => {
+                let style = castling_style(chess960);
+                let limits = parse_go(&tokens[1..]);
+                //the GUI has already played the reply it expects, so the position is
+                //pondered until it says whether the opponent played it
+                if tokens.contains(&"ponder") {
+                    let (stop_sender, stop) = mpsc::channel();
+                    let pondering = thread::spawn(move || {
+                        engine
+                            .multi_thread_think_next_moves_until_stop(thread_count, stop)
+                            .0
+                    });
+                    let hit;
+                    (engine, pending, hit) = wait_for_ponderhit(pondering, stop_sender, &mut lines);
+                    if !hit {
+                        //a stopped ponder still owes the GUI a bestmove, though it's ignored
+                        let (_, best_move) = engine.eval_and_best_move();
+                        println!("{}", bestmove_line(&engine, best_move, None, style));
+                        continue;
+                    }
+                }
+                match limits == SearchLimits::default() {
+                    true => engine = go(engine, thread_count, multi_pv, &adjudicator, style),
+                    false => {
+                        let (stop_sender, stop) = mpsc::channel();
+                        let adjudicator = adjudicator.clone();
+                        let search = thread::spawn(move || {
+                            go_with_limits(engine, thread_count, limits, stop, &adjudicator, style)
+                        });
+                        (engine, pending) = wait_for_search(search, stop_sender, &mut lines);
+                    }
+                }
+            }
+            Some(&"quit") => break,
+            _ => {}
+        }
+    }
+}
+
+//"name <words...> value <words...>" -> (name, value)
+fn parse_setoption(tokens: &[&str]) -> (String, String) {
+    let value_index = tokens.iter().position(|token| *token == "value");
+    let name_tokens = match value_index {
+        Some(index) => &tokens[..index],
+        None => tokens,
+    };
+    let value_tokens = match value_index {
+        Some(index) => &tokens[index + 1..],
+        None => &[],
+    };
+    let name_tokens = match name_tokens.first() {
+        Some(&"name") => &name_tokens[1..],
+        _ => name_tokens,
+    };
+    (name_tokens.join(" "), value_tokens.join(" "))
+}
+
+//"depth 5", "movetime 2000", "nodes 100000" or "infinite". anything else, the clock
+//commands included, is ignored
+fn parse_go(tokens: &[&str]) -> SearchLimits {
+    let mut limits = SearchLimits::default();
+    let mut tokens = tokens.iter();
+    while let Some(token) = tokens.next() {
+        let mut value = || tokens.next().and_then(|value| value.parse::<u64>().ok());
+        match *token {
+            "depth" => limits.depth = value().map(|depth| depth.min(i32::MAX as u64) as i32),
+            "movetime" => limits.movetime = value().map(Duration::from_millis),
+            "nodes" => limits.nodes = value().map(|nodes| nodes as usize),
+            "infinite" => limits.infinite = true,
+            _ => {}
+        }
+    }
+    limits
+}
+
+fn castling_style(chess960: bool) -> CastlingStyle {
+    match chess960 {
+        true => CastlingStyle::KingTakesRook,
+        false => CastlingStyle::KingMove,
+    }
+}
+
+fn position_engine(
+    tokens: &[&str],
+    minimax_settings: &MinimaxSettings,
+    search_settings: &SearchSettings,
+    chess960: bool,
+) -> Result<Engine, String> {
+    let (setup, moves) = match tokens.iter().position(|token| *token == "moves") {
+        Some(index) => (&tokens[..index], &tokens[index + 1..]),
+        None => (tokens, &[][..]),
+    };
+    //a FEN also brings its castling rights and halfmove clock
+    let mut engine = match setup.first() {
+        Some(&"startpos") => new_engine(Board::new(), minimax_settings, search_settings),
+        Some(&"fen") => {
+            let fen = match chess960 {
+                true => chess960_fen(&setup[1..].join(" "))
+                    .map_err(|err| format!("invalid fen ({})", err))?,
+                false => setup[1..].join(" "),
+            };
+            Engine::from_fen(
+                &fen,
+                StaticEvaluationWeights::new(),
+                InterestEvaluationWeights::new(),
+                minimax_settings.clone(),
+                search_settings.clone(),
+            )
+            .map_err(|err| format!("invalid fen ({})", err))?
+        }
+        _ => return Err(String::from("expected startpos or fen")),
+    };
+    for move_string in moves {
+        let chess_move = engine
+            .get_board()
+            .interpret_lan_with(move_string, castling_style(chess960))
+            .map_err(|_| format!("illegal move {}", move_string))?;
+        engine
+            .make_move(&chess_move)
+            .map_err(|err| format!("could not play {} ({:?})", move_string, err))?;
+    }
+    Ok(engine)
+}
+
+//UCI scores are from the point of view of the side to move
+fn uci_score(eval: &Eval, side: Color) -> String {
+    let eval = eval.clamp_for_display().relative_to(side);
+    match (eval.centipawns(), eval.mate_in_moves()) {
+        (Some(centipawns), _) => format!("cp {}", centipawns),
+        (_, Some(moves)) => format!("mate {}", moves),
+        (None, None) => unreachable!("an eval is either numeric or a mate"),
+    }
+}
+
+//hashfull (when the tree has a cap) and tbhits for an info line, counted since the
+//search stats were last reset
+fn usage_info(engine: &Engine) -> String {
+    let hashfull = match engine.hashfull() {
+        Some(permille) => format!("hashfull {} ", permille),
+        None => String::new(),
+    };
+    format!(
+        "{}tbhits {} ",
+        hashfull,
+        engine.search_stats().tablebase_hits
+    )
+}
+
+//tells the GUI, ahead of the bestmove, when the adjudicator would resign or settle for a
+//draw here. UCI has no command for either, so it's left to the GUI to act on
+fn adjudication_info(adjudicator: &Mutex<Adjudicator>, engine: &Engine, eval: &Eval) {
+    if let Some(adjudication) = adjudicator.lock().unwrap().adjudicate(engine, eval) {
+        println!(
+            "info string {} ({})",
+            adjudication.description(),
+            adjudication.result()
+        );
+    }
+}
+
+//tells the GUI when the tree outgrew its cap during the think and nodes were dropped
+fn eviction_info(engine: &mut Engine) {
+    let evicted = engine.take_evicted();
+    if evicted > 0 {
+        println!(
+            "info string evicted {} nodes to keep the tree under its cap",
+            evicted
+        );
+    }
+}
+
+fn go(
+    engine: Engine,
+    thread_count: usize,
+    multi_pv: usize,
+    adjudicator: &Mutex<Adjudicator>,
+    style: CastlingStyle,
+) -> Engine {
+    let budget = engine.search_settings().expansion_budget;
+    let mut engine = engine.multi_thread_think_next_num_moves(thread_count, budget, None);
+    eviction_info(&mut engine);
+    engine.reset_search_stats();
+    let side = engine.get_board().get_turn();
+    let engine = Arc::new(engine);
+
+    let board = engine.get_board().clone();
+    let report_currmove = |index: usize, chess_move: &ChessMove| {
+        println!(
+            "info currmove {} currmovenumber {}",
+            chess_move.to_lan_with(&board, style),
+            index + 1
+        );
+    };
+
+    //only rank the root moves when more than one line was asked for
+    let lines = match multi_pv {
+        1 => {
+            let result = engine
+                .clone()
+                .parallel_search(thread_count, Some(&report_currmove));
+            match result.best_move {
+                Some(chess_move) => vec![(result.eval, chess_move)],
+                None => Vec::new(),
+            }
+        }
+        n => engine.clone().best_n_moves(thread_count, n),
+    };
+    let (depth, seldepth) = engine.depths_reached();
+    let search_info = format!("depth {} seldepth {} ", depth, seldepth) + &usage_info(&engine);
+
+    for (index, (eval, chess_move)) in lines.iter().enumerate() {
+        println!(
+            "info {}multipv {} score {} pv {}",
+            search_info,
+            index + 1,
+            uci_score(eval, side),
+            chess_move.to_lan_with(engine.get_board(), style)
+        );
+    }
+    if let Some((eval, _)) = lines.first() {
+        adjudication_info(adjudicator, &engine, eval);
+    }
+    let best_move = lines.first().map(|(_, chess_move)| *chess_move);
+    println!("{}", bestmove_line(&engine, best_move, None, style));
+
+    Arc::try_unwrap(engine).unwrap()
+}
+
+//ponders and searches within limits. a movetime is split evenly between growing the
+//tree and searching it
+fn go_with_limits(
+    engine: Engine,
+    thread_count: usize,
+    limits: SearchLimits,
+    stop: Receiver<()>,
+    adjudicator: &Mutex<Adjudicator>,
+    style: CastlingStyle,
+) -> Engine {
+    let (mut engine, limits) = match limits.movetime {
+        Some(movetime) => {
+            let (engine, _) = engine.think_for(thread_count, movetime / 2);
+            let limits = SearchLimits {
+                movetime: Some(movetime - movetime / 2),
+                ..limits
+            };
+            (engine, limits)
+        }
+        None => {
+            let budget = engine.search_settings().expansion_budget;
+            //grown on one thread under a node limit, so the same go gives the same answer
+            let thread_count = match limits.nodes {
+                Some(_) => 1,
+                None => thread_count,
+            };
+            let engine = engine.multi_thread_think_next_num_moves(thread_count, budget, None);
+            (engine, limits)
+        }
+    };
+    eviction_info(&mut engine);
+    let (eval, best_move, principal_variation) = engine.go(limits, stop);
+
+    let mut board = engine.get_board().clone();
+    let mut pv = Vec::new();
+    for &chess_move in &principal_variation {
+        pv.push(chess_move.to_lan_with(&board, style));
+        board.make_legal_move(chess_move).unwrap();
+    }
+    println!(
+        "info nodes {} {}score {} pv {}",
+        engine.nodes_searched(),
+        usage_info(&engine),
+        uci_score(&eval, engine.get_board().get_turn()),
+        pv.join(" ")
+    );
+    adjudication_info(adjudicator, &engine, &eval);
+    //the reply the principal variation expects is the one to ponder on
+    let reply = principal_variation.get(1).copied();
+    println!("{}", bestmove_line(&engine, best_move, reply, style));
+    engine
+}
+
+//"bestmove <move>", with "ponder <reply>" when a reply to it is expected
+fn bestmove_line(
+    engine: &Engine,
+    best_move: Option<ChessMove>,
+    reply: Option<ChessMove>,
+    style: CastlingStyle,
+) -> String {
+    let chess_move = match best_move {
+        Some(chess_move) => chess_move,
+        None => return String::from("bestmove 0000"),
+    };
+    let mut line = format!(
+        "bestmove {}",
+        chess_move.to_lan_with(engine.get_board(), style)
+    );
+    let after = engine.peek_after(&chess_move);
+    if let (Some(reply), Ok(after)) = (reply, after) {
+        line += &format!(" ponder {}", reply.to_lan_with(&after, style));
+    }
+    line
+}
+
+//answers isready while pondering, until ponderhit (true) or stop, quit or the end of the
+//input (false) ends it. any other command ends it too and is handed back like
+//wait_for_search does
+fn wait_for_ponderhit(
+    pondering: JoinHandle<Engine>,
+    stop: Sender<()>,
+    lines: &mut impl Iterator<Item = io::Result<String>>,
+) -> (Engine, Option<String>, bool) {
+    for line in lines {
+        let line = line.unwrap();
+        let command = line.trim();
+        if command == "isready" {
+            println!("readyok");
+            continue;
+        }
+        let _ = stop.send(());
+        let engine = pondering.join().unwrap();
+        return match command {
+            "ponderhit" => (engine, None, true),
+            "stop" => (engine, None, false),
+            _ => (engine, Some(line), false),
+        };
+    }
+    let _ = stop.send(());
+    (pondering.join().unwrap(), None, false)
+}
+
+//answers isready and passes stop on while a search runs. any other command waits for
+//the search to finish and is handed back to be handled after it
+fn wait_for_search(
+    search: JoinHandle<Engine>,
+    stop: Sender<()>,
+    lines: &mut impl Iterator<Item = io::Result<String>>,
+) -> (Engine, Option<String>) {
+    for line in lines {
+        let line = line.unwrap();
+        match line.trim() {
+            "isready" => println!("readyok"),
+            "stop" => {
+                let _ = stop.send(());
+                return (search.join().unwrap(), None);
+            }
+            "quit" => {
+                let _ = stop.send(());
+                return (search.join().unwrap(), Some(line));
+            }
+            _ => {
+                //a finite search still runs to its end, but an infinite one would wait
+                //for a stop forever, so the sender goes before the join
+                drop(stop);
+                return (search.join().unwrap(), Some(line));
+            }
+        }
+    }
+    //with the input gone, nothing is left to stop an infinite search
+    drop(stop);
+    (search.join().unwrap(), None)
+}
+
+#[test]
+fn test_uci_score_side_relative() {
+    //black is a queen up, so a black to move engine reports a positive score
+    let engine = new_engine(
+        make_board_from_fen("3qk3/8/8/8/8/8/8/4K3 b - - 0 1").unwrap(),
+        &MinimaxSettings::new(),
+        &SearchSettings::new(),
+    );
+    let (eval, _) = engine.eval_and_best_move();
+    assert!(eval.centipawns().unwrap() < 0);
+    let score = uci_score(&eval, Color::Black);
+    let centipawns: i32 = score.strip_prefix("cp ").unwrap().parse().unwrap();
+    assert!(centipawns > 0);
+    assert_eq!(
+        uci_score(&Eval::MateIn(Color::Black, 1), Color::Black),
+        "mate 1"
+    );
+    assert_eq!(
+        uci_score(&Eval::MateIn(Color::Black, 1), Color::White),
+        "mate -1"
+    );
+}
+
+#[test]
+fn test_infinite_search_ends_on_other_command() {
+    let mut search_settings = SearchSettings::new();
+    search_settings.expansion_budget = 200;
+    let engine = new_engine(Board::new(), &MinimaxSettings::new(), &search_settings);
+    let limits = SearchLimits {
+        infinite: true,
+        ..SearchLimits::default()
+    };
+    let (stop_sender, stop) = mpsc::channel();
+    let search = thread::spawn(move || {
+        let adjudicator = Mutex::new(Adjudicator::new(MinimaxSettings::new()));
+        go_with_limits(
+            engine,
+            1,
+            limits,
+            stop,
+            &adjudicator,
+            CastlingStyle::KingMove,
+        )
+    });
+    let mut lines = vec![
+        Ok(String::from("isready")),
+        Ok(String::from("position startpos")),
+    ]
+    .into_iter();
+    let (_, pending) = wait_for_search(search, stop_sender, &mut lines);
+    assert_eq!(pending.as_deref(), Some("position startpos"));
+}
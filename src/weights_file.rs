@@ -0,0 +1,89 @@
+use std::fs;
+
+#[cfg(test)]
+use crate::engine::teros_engine::{InterestEvaluationWeights, StaticEvaluationWeights};
+
+//weights as --tune prints them and --learn saves them: a TOML table, section, with one
+//line per weight of named
+pub fn weights_toml(section: &str, named: Vec<(&'static str, &mut f32)>) -> String {
+    let mut toml = format!("[{}]\n", section);
+    for (name, weight) in named {
+        toml += &format!("{} = {}\n", name, weight);
+    }
+    toml
+}
+
+//sets each weight of named that text, written by weights_toml, gives a value.
+//weights missing from text are left as they were
+pub fn read_weights(text: &str, mut named: Vec<(&'static str, &mut f32)>) -> Result<(), String> {
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('[') {
+            continue;
+        }
+        let (name, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("not a weight: {}", line))?;
+        let value: f32 = value
+            .trim()
+            .parse()
+            .map_err(|_| format!("bad value in {}", line))?;
+        let weight = named
+            .iter_mut()
+            .find(|(weight_name, _)| *weight_name == name.trim())
+            .ok_or_else(|| format!("unknown weight {}", name.trim()))?;
+        *weight.1 = value;
+    }
+    Ok(())
+}
+
+//read_weights on the file at path
+pub fn load_weights(path: &str, named: Vec<(&'static str, &mut f32)>) -> Result<(), String> {
+    let text =
+        fs::read_to_string(path).map_err(|err| format!("could not read {} ({})", path, err))?;
+    read_weights(&text, named)
+}
+
+#[test]
+fn test_weights_toml_round_trip() {
+    let mut weights = InterestEvaluationWeights::new();
+    *weights.named_weights_mut()[0].1 = 1.25;
+    let toml = weights_toml("interest_evaluation_weights", weights.named_weights_mut());
+    assert!(toml.starts_with("[interest_evaluation_weights]\n"));
+
+    let mut read = InterestEvaluationWeights::new();
+    read_weights(&toml, read.named_weights_mut()).unwrap();
+    assert_eq!(read.square_control_weight, 1.25);
+    assert_eq!(
+        weights_toml("interest_evaluation_weights", read.named_weights_mut()),
+        toml
+    );
+    assert!(read_weights("no_such_weight = 1", read.named_weights_mut()).is_err());
+}
+
+#[test]
+fn test_static_weights_round_trip() {
+    //every weight changed, the way --tune would leave them
+    let mut weights = StaticEvaluationWeights::new();
+    for (index, (_, weight)) in weights.named_weights_mut().into_iter().enumerate() {
+        *weight += 0.25 * (index + 1) as f32;
+    }
+    let toml = weights_toml("static_evaluation_weights", weights.named_weights_mut());
+
+    let path = std::env::temp_dir().join("teros_static_weights_round_trip.toml");
+    fs::write(&path, &toml).unwrap();
+    let mut read = StaticEvaluationWeights::new();
+    load_weights(path.to_str().unwrap(), read.named_weights_mut()).unwrap();
+    fs::remove_file(&path).unwrap();
+    let read_values: Vec<f32> = read
+        .named_weights_mut()
+        .into_iter()
+        .map(|(_, weight)| *weight)
+        .collect();
+    let values: Vec<f32> = weights
+        .named_weights_mut()
+        .into_iter()
+        .map(|(_, weight)| *weight)
+        .collect();
+    assert_eq!(read_values, values);
+    assert!(load_weights("no/such/weights.toml", read.named_weights_mut()).is_err());
+}